@@ -10,6 +10,14 @@ then
     kv_channel_size_args=("--kv-channel-size" "${SPALHAD_KV_CHANNEL_SIZE}")
 fi
 
+control_channel_size_args=()
+if [ -n "${SPALHAD_CONTROL_CHANNEL_SIZE}" ]
+then
+    control_channel_size_args=(
+        "--control-channel-size" "${SPALHAD_CONTROL_CHANNEL_SIZE}"
+    )
+fi
+
 persistence_dir_args=()
 if ([ -n "${SPALHAD_KV_DIR}" ] \
     && [ "${SPALHAD_KV_DIR}" != 0 ] \
@@ -33,6 +41,44 @@ then
     persistence_dir_args=("--persistence-dir" "${persistence_dir}")
 fi
 
+actor_metrics_args=()
+if [ -n "${SPALHAD_ACTOR_METRICS}" ] && [ "${SPALHAD_ACTOR_METRICS}" != 0 ]
+then
+    actor_metrics_args=("--actor-metrics")
+fi
+
+otlp_endpoint_args=()
+if [ -n "${SPALHAD_OTLP_ENDPOINT}" ]
+then
+    otlp_endpoint_args=("--otlp-endpoint" "${SPALHAD_OTLP_ENDPOINT}")
+fi
+
+hedge_percentile_args=()
+if [ -n "${SPALHAD_HEDGE_PERCENTILE}" ]
+then
+    hedge_percentile_args=("--hedge-percentile" "${SPALHAD_HEDGE_PERCENTILE}")
+fi
+
+breaker_args=()
+if [ -n "${SPALHAD_BREAKER_FAILURES}" ]
+then
+    breaker_args+=("--breaker-failures" "${SPALHAD_BREAKER_FAILURES}")
+fi
+if [ -n "${SPALHAD_BREAKER_COOLDOWN}" ]
+then
+    breaker_args+=("--breaker-cooldown" "${SPALHAD_BREAKER_COOLDOWN}")
+fi
+
+peer_retry_args=()
+if [ -n "${SPALHAD_PEER_RETRIES}" ]
+then
+    peer_retry_args+=("--peer-retries" "${SPALHAD_PEER_RETRIES}")
+fi
+if [ -n "${SPALHAD_PEER_RETRY_BACKOFF}" ]
+then
+    peer_retry_args+=("--peer-retry-backoff" "${SPALHAD_PEER_RETRY_BACKOFF}")
+fi
+
 cluster_config_args=()
 if [ -n "${SPALHAD_CLUSTER_CONFIG}" ]
 then
@@ -49,6 +95,12 @@ self_id_args=("--self-id" "${SPALHAD_SELF_ID}")
 exec ./server \
     "${bind_args[@]}" \
     "${kv_channel_size_args[@]}" \
+    "${control_channel_size_args[@]}" \
     "${persistence_dir_args[@]}" \
+    "${actor_metrics_args[@]}" \
+    "${otlp_endpoint_args[@]}" \
+    "${hedge_percentile_args[@]}" \
+    "${breaker_args[@]}" \
+    "${peer_retry_args[@]}" \
     "${cluster_config_args[@]}" \
     "${self_id_args[@]}"
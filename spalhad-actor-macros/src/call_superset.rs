@@ -1,19 +1,20 @@
+use std::collections::HashMap;
+
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{ToTokens, quote};
 use syn::{
     Attribute,
     Data,
     DeriveInput,
     Field,
-    Fields,
-    FieldsNamed,
-    FieldsUnnamed,
     Generics,
     Ident,
+    Member,
     Meta,
     Result,
     Token,
     Type,
+    Variant,
     WherePredicate,
     braced,
     bracketed,
@@ -25,67 +26,192 @@ use syn::{
     token::{Brace, Bracket, Comma, Paren},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum BraceType {
-    Paren,
-    Curly,
+#[derive(Debug, Clone)]
+struct CallField<'a> {
+    member: Member,
+    ty: &'a Type,
+}
+
+#[derive(Debug, Clone)]
+enum VariantKind<'a> {
+    Skip,
+    Call {
+        call: CallField<'a>,
+        context: Vec<CallField<'a>>,
+        flatten_tys: Option<Punctuated<Type, Comma>>,
+        control: bool,
+    },
 }
 
 #[derive(Debug, Clone)]
 struct CallVariant<'a> {
     variant_ident: &'a Ident,
-    brace_type: BraceType,
-    field_ident: Ident,
-    ty: &'a Type,
-    flatten_tys: Option<Punctuated<Type, Comma>>,
+    kind: VariantKind<'a>,
 }
 
 impl<'a> CallVariant<'a> {
-    pub fn new(
-        variant_ident: &'a Ident,
-        variant_attrs: &'a [Attribute],
-        field: &'a Field,
-    ) -> Result<Self> {
+    pub fn new(variant: &'a Variant) -> Result<Self> {
         let mut flatten_tys = None;
-        for attribute in variant_attrs {
-            if let Some(attr) = Attr::from_meta(&attribute.meta)? {
-                match attr {
-                    Attr::Flatten(tys) => {
-                        flatten_tys = Some(tys);
-                    },
-                }
+        let mut control = false;
+        let mut skip = false;
+        for (attribute, attr) in parse_attrs(&variant.attrs)? {
+            match attr {
+                Attr::Flatten(tys) => {
+                    flatten_tys = Some(tys);
+                },
+                Attr::Control => {
+                    control = true;
+                },
+                Attr::Skip => {
+                    skip = true;
+                },
+                Attr::Call | Attr::Default => Err(syn::Error::new(
+                    attribute.span(),
+                    "#[spalhad(call)] and #[spalhad(default)] are only \
+                     allowed on variant fields",
+                ))?,
             }
         }
 
-        let ty = &field.ty;
+        let variant_ident = &variant.ident;
 
-        let field_ident = field
-            .ident
-            .clone()
-            .unwrap_or_else(|| Ident::new("__field", field.span()));
+        if skip {
+            if flatten_tys.is_some() || control {
+                Err(syn::Error::new(
+                    variant_ident.span(),
+                    "#[spalhad(skip)] cannot be combined with flatten or \
+                     control",
+                ))?
+            }
+            return Ok(Self { variant_ident, kind: VariantKind::Skip });
+        }
 
-        let brace_type = if field.ident.is_some() {
-            BraceType::Curly
-        } else {
-            BraceType::Paren
+        if variant.fields.is_empty() {
+            Err(syn::Error::new(
+                variant_ident.span(),
+                "Variants without fields cannot carry calls and must be \
+                 marked with #[spalhad(skip)]",
+            ))?
+        }
+
+        let mut call = None;
+        let mut context = Vec::new();
+        let mut undefaulted = None;
+        let single = variant.fields.len() == 1;
+        for (index, field) in variant.fields.iter().enumerate() {
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(index.into()),
+            };
+            let role = field_role(field)?;
+            if role.call || single {
+                if call.is_some() {
+                    Err(syn::Error::new(
+                        field.span(),
+                        "Only one field per variant can be marked with \
+                         #[spalhad(call)]",
+                    ))?
+                }
+                if let Some(attribute) = role.default {
+                    Err(syn::Error::new(
+                        attribute.span(),
+                        "The call field cannot be marked with \
+                         #[spalhad(default)]",
+                    ))?
+                }
+                call = Some(CallField { member, ty: &field.ty });
+            } else {
+                if role.default.is_none() {
+                    undefaulted.get_or_insert(field);
+                }
+                context.push(CallField { member, ty: &field.ty });
+            }
+        }
+
+        let Some(call) = call else {
+            Err(syn::Error::new(
+                variant.fields.span(),
+                "Variants with multiple fields must mark the call field with \
+                 #[spalhad(call)]",
+            ))?
         };
 
-        Ok(Self { variant_ident, brace_type, ty, field_ident, flatten_tys })
+        if let Some(field) = undefaulted {
+            Err(syn::Error::new(
+                field.span(),
+                "Fields next to the call are filled with Default::default() \
+                 when a call is injected and must be marked with \
+                 #[spalhad(default)]",
+            ))?
+        }
+
+        Ok(Self {
+            variant_ident,
+            kind: VariantKind::Call { call, context, flatten_tys, control },
+        })
     }
 
-    pub fn reply_error_tokens(&self) -> TokenStream {
+    fn pattern_tokens(&self) -> TokenStream {
         let variant_ident = self.variant_ident;
-        let field_ident = &self.field_ident;
-        let field_tokens = match self.brace_type {
-            BraceType::Paren => quote! { ( #field_ident ) },
-            BraceType::Curly => quote! { { #field_ident } },
-        };
-        quote! {
-            Self::#variant_ident #field_tokens =>
-                ::spalhad_actor::CallSuperset::reply_error(
-                    #field_ident,
-                    error
-                ),
+        match &self.kind {
+            VariantKind::Skip => quote! { Self::#variant_ident { .. } },
+            VariantKind::Call { call, .. } => {
+                let member = &call.member;
+                quote! { Self::#variant_ident { #member: __call, .. } }
+            },
+        }
+    }
+
+    pub fn reply_error_tokens(&self) -> TokenStream {
+        let pattern = self.pattern_tokens();
+        match &self.kind {
+            VariantKind::Skip => quote! {
+                #pattern => {
+                    ::core::mem::drop(error);
+                    false
+                },
+            },
+            VariantKind::Call { .. } => quote! {
+                #pattern =>
+                    ::spalhad_actor::CallSuperset::reply_error(__call, error),
+            },
+        }
+    }
+
+    pub fn lane_tokens(&self) -> TokenStream {
+        let pattern = self.pattern_tokens();
+        match &self.kind {
+            VariantKind::Skip => quote! {
+                #pattern => ::spalhad_actor::Lane::Regular,
+            },
+            VariantKind::Call { control: true, .. } => quote! {
+                #pattern => ::spalhad_actor::Lane::Control,
+            },
+            VariantKind::Call { control: false, .. } => quote! {
+                #pattern => ::spalhad_actor::CallSuperset::lane(__call),
+            },
+        }
+    }
+
+    pub fn span_tokens(&self) -> TokenStream {
+        let pattern = self.pattern_tokens();
+        match &self.kind {
+            VariantKind::Skip => quote! {
+                #pattern => ::spalhad_actor::Span::none(),
+            },
+            VariantKind::Call { .. } => quote! {
+                #pattern => ::spalhad_actor::CallSuperset::span(__call),
+            },
+        }
+    }
+
+    pub fn injected_tys(&self) -> Vec<&Type> {
+        match &self.kind {
+            VariantKind::Skip => Vec::new(),
+            VariantKind::Call { flatten_tys: Some(tys), .. } => {
+                tys.iter().collect()
+            },
+            VariantKind::Call { call, flatten_tys: None, .. } => vec![call.ty],
         }
     }
 
@@ -94,21 +220,16 @@ impl<'a> CallVariant<'a> {
         super_ident: &Ident,
         generics: &Generics,
     ) -> TokenStream {
-        match &self.flatten_tys {
-            Some(tys) => {
-                let mut tokens = quote! {};
-                for ty in tys {
-                    let curr_tokens =
-                        self.injection_tokens_for(ty, super_ident, generics);
-                    tokens = quote! {
-                        #tokens
-                        #curr_tokens
-                    };
-                }
-                tokens
-            },
-            None => self.injection_tokens_for(self.ty, super_ident, generics),
+        let mut tokens = quote! {};
+        for ty in self.injected_tys() {
+            let curr_tokens =
+                self.injection_tokens_for(ty, super_ident, generics);
+            tokens = quote! {
+                #tokens
+                #curr_tokens
+            };
         }
+        tokens
     }
 
     fn injection_tokens_for(
@@ -117,35 +238,86 @@ impl<'a> CallVariant<'a> {
         super_ident: &Ident,
         generics: &Generics,
     ) -> TokenStream {
+        let VariantKind::Call { call, context, .. } = &self.kind else {
+            return quote! {};
+        };
         let variant_ident = self.variant_ident;
-        let field_ty = self.ty;
-        let params = &generics.params;
-        let where_clause = &generics.where_clause;
+        let member = &call.member;
+        let field_ty = call.ty;
+        let (impl_generics, ty_generics, where_clause) =
+            generics.split_for_impl();
+        let context_members = context.iter().map(|field| &field.member);
+        let context_tys = context.iter().map(|field| field.ty);
 
         quote! {
-            impl<#params>
+            impl #impl_generics
                 ::spalhad_actor::CallInjection<#call_ty>
-                for #super_ident<#params>
+                for #super_ident #ty_generics
             #where_clause
             {
                 fn inject(
                     call: #call_ty,
                 ) -> Self {
-                    Self::#variant_ident(
-                        <
+                    Self::#variant_ident {
+                        #member: <
                             #field_ty as
                             ::spalhad_actor::CallInjection::<#call_ty>
-                        >::inject(call)
-                    )
+                        >::inject(call),
+                        #(
+                            #context_members: <
+                                #context_tys as ::core::default::Default
+                            >::default(),
+                        )*
+                    }
                 }
             }
         }
     }
 }
 
+#[derive(Debug, Default)]
+struct FieldRole<'a> {
+    call: bool,
+    default: Option<&'a Attribute>,
+}
+
+fn field_role(field: &Field) -> Result<FieldRole<'_>> {
+    let mut role = FieldRole::default();
+    for (attribute, attr) in parse_attrs(&field.attrs)? {
+        match attr {
+            Attr::Call => {
+                role.call = true;
+            },
+            Attr::Default => {
+                role.default = Some(attribute);
+            },
+            _ => Err(syn::Error::new(
+                attribute.span(),
+                "Only #[spalhad(call)] and #[spalhad(default)] are allowed on \
+                 variant fields",
+            ))?,
+        }
+    }
+    Ok(role)
+}
+
+fn parse_attrs(attributes: &[Attribute]) -> Result<Vec<(&Attribute, Attr)>> {
+    let mut attrs = Vec::new();
+    for attribute in attributes {
+        if let Some(attr) = Attr::from_meta(&attribute.meta)? {
+            attrs.push((attribute, attr));
+        }
+    }
+    Ok(attrs)
+}
+
 #[derive(Debug)]
 enum Attr {
     Flatten(Punctuated<Type, Comma>),
+    Control,
+    Skip,
+    Call,
+    Default,
 }
 
 impl Attr {
@@ -186,6 +358,14 @@ impl Parse for Attr {
                 Err(syn::Error::new(ident.span(), "missing type list"))?
             };
             Ok(Self::Flatten(types))
+        } else if ident == "control" {
+            Ok(Self::Control)
+        } else if ident == "skip" {
+            Ok(Self::Skip)
+        } else if ident == "call" {
+            Ok(Self::Call)
+        } else if ident == "default" {
+            Ok(Self::Default)
         } else {
             Err(syn::Error::new(ident.span(), "unknown attribute name"))
         }
@@ -198,68 +378,64 @@ pub fn derive(input: DeriveInput) -> Result<TokenStream> {
     };
 
     let mut cases = quote! {};
+    let mut lane_cases = quote! {};
+    let mut span_cases = quote! {};
     let mut injections = quote! {};
+    let mut injected: HashMap<String, &Ident> = HashMap::new();
     for variant in &data_enum.variants {
-        let variant_ident = &variant.ident;
+        let call_variant = CallVariant::new(variant)?;
 
-        let call_variant = match &variant.fields {
-            Fields::Named(FieldsNamed { named, .. }) => {
-                let Some(field) = named.first().filter(|_| named.len() == 1)
-                else {
-                    Err(syn::Error::new(
-                        named.span(),
-                        "Only exactly one field per variant is supported",
-                    ))?
-                };
-                CallVariant::new(variant_ident, &variant.attrs, field)?
-            },
-
-            Fields::Unnamed(FieldsUnnamed { unnamed, .. }) => {
-                let Some(field) =
-                    unnamed.first().filter(|_| unnamed.len() == 1)
-                else {
-                    Err(syn::Error::new(
-                        unnamed.span(),
-                        "Only exactly one field per variant is supported",
-                    ))?
-                };
-                CallVariant::new(variant_ident, &variant.attrs, field)?
-            },
-
-            Fields::Unit => Err(syn::Error::new(
-                variant.fields.span(),
-                "Only exactly one field per variant is supported",
-            ))?,
-        };
+        for ty in call_variant.injected_tys() {
+            let key = ty.to_token_stream().to_string();
+            if let Some(previous) = injected.insert(key, &variant.ident) {
+                Err(syn::Error::new(
+                    ty.span(),
+                    format!(
+                        "`{}` is already injected through variant `{previous}`",
+                        ty.to_token_stream(),
+                    ),
+                ))?
+            }
+        }
 
         let reply_error_tokens = call_variant.reply_error_tokens();
         cases = quote! { #cases #reply_error_tokens };
 
+        let lane_tokens = call_variant.lane_tokens();
+        lane_cases = quote! { #lane_cases #lane_tokens };
+
+        let span_tokens = call_variant.span_tokens();
+        span_cases = quote! { #span_cases #span_tokens };
+
         let inject_tokens =
             call_variant.inject_tokens(&input.ident, &input.generics);
         injections = quote! { #injections #inject_tokens };
     }
 
-    let params = &input.generics.params;
-    let ty_ident = &input.ident;
-    let where_clause = &input.generics.where_clause;
+    let scrutinee = if data_enum.variants.is_empty() {
+        quote! { *self }
+    } else {
+        quote! { self }
+    };
 
-    let mut from_params = params.clone();
-    from_params.push(parse_quote! { __I });
-    from_params.push(parse_quote! { __O });
+    let ty_ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
 
+    let mut from_generics = input.generics.clone();
+    from_generics.params.push(parse_quote! { __I });
+    from_generics.params.push(parse_quote! { __O });
     let from_where_predicate: WherePredicate = parse_quote! {
-        #ty_ident<#params>:
+        #ty_ident #ty_generics:
             ::spalhad_actor::CallInjection<::spalhad_actor::ActorCall<__I, __O>>
     };
-    let mut from_where_clause = where_clause.clone();
-    from_where_clause
-        .get_or_insert_with(|| parse_quote! { where })
-        .predicates
-        .push(from_where_predicate);
+    from_generics.make_where_clause().predicates.push(from_where_predicate);
+    let (from_impl_generics, _, from_where_clause) =
+        from_generics.split_for_impl();
 
     let tokens = quote! {
-        impl<#params> ::spalhad_actor::CallSuperset for #ty_ident<#params>
+        impl #impl_generics ::spalhad_actor::CallSuperset
+            for #ty_ident #ty_generics
         #where_clause
         {
             fn reply_error<__ErrorType>(self, error: __ErrorType) -> bool
@@ -270,10 +446,22 @@ pub fn derive(input: DeriveInput) -> Result<TokenStream> {
                     #cases
                 }
             }
+
+            fn lane(&self) -> ::spalhad_actor::Lane {
+                match #scrutinee {
+                    #lane_cases
+                }
+            }
+
+            fn span(&self) -> ::spalhad_actor::Span {
+                match #scrutinee {
+                    #span_cases
+                }
+            }
         }
 
-        impl<#from_params> From<::spalhad_actor::ActorCall<__I, __O>>
-            for #ty_ident<#params>
+        impl #from_impl_generics From<::spalhad_actor::ActorCall<__I, __O>>
+            for #ty_ident #ty_generics
         #from_where_clause
         {
             fn from(call: ::spalhad_actor::ActorCall<__I, __O>) -> Self {
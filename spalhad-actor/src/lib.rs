@@ -1,18 +1,54 @@
+use std::{
+    any,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
 use anyhow::{Result, bail};
 use tokio::{
     select,
     sync::{mpsc, oneshot},
 };
 use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 
-use spalhad_task::TaskManager;
+use spalhad_task::{Criticality, TaskManager};
 
+pub use metrics::{
+    ActorMetricsRegistry,
+    ActorMetricsSnapshot,
+    LatencyBucket,
+    LatencyHistogram,
+};
+pub use registry::{ActorRegistry, RegisteredActor};
+pub use scatter::{Gathered, Hedging, Reply, Scatter, Votes, quorum};
 pub use spalhad_actor_macros::CallSuperset;
+pub use tracing::Span;
+
+use metrics::ActorMetrics;
+
+mod metrics;
+mod registry;
+mod scatter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Lane {
+    Control,
+    Regular,
+}
 
 pub trait CallSuperset {
     fn reply_error<E>(self, error: E) -> bool
     where
         E: Into<anyhow::Error>;
+
+    fn lane(&self) -> Lane {
+        Lane::Regular
+    }
+
+    fn span(&self) -> Span {
+        Span::none()
+    }
 }
 
 pub trait CallInjection<C: CallConnectors>: CallSuperset + Sized {
@@ -51,12 +87,16 @@ pub trait TrivialLoopActor {
     type Call;
 
     async fn on_call(&mut self, call: Self::Call) -> Result<()>;
+
+    fn on_stop(&mut self) -> impl Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
 }
 
 impl<T> Actor for T
 where
     T: TrivialLoopActor,
-    T::Call: Send,
+    T::Call: CallSuperset + Send,
 {
     type Call = T::Call;
 
@@ -65,26 +105,58 @@ where
         mut inbox: ActorInbox<Self::Call>,
         cancellation_token: CancellationToken,
     ) -> Result<()> {
+        let mut draining = false;
         loop {
             let result = select! {
-                _ = cancellation_token.cancelled() => break Ok(()),
+                _ = cancellation_token.cancelled(), if !draining => {
+                    tracing::debug!("cancelled, draining inbox");
+                    draining = true;
+                    continue;
+                },
                 message = inbox.recv() => message,
             };
-            let Some(call) = result else { break Ok(()) };
-            self.on_call(call).await?;
+            let Some(call) = result else { break };
+            let span = call.span();
+            let started = Instant::now();
+            let handled = self.on_call(call).instrument(span).await;
+            inbox.record_handled(started.elapsed());
+            handled?;
         }
+        self.on_stop().await
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct ActorOptions<'a> {
-    task_manager: &'a TaskManager,
+pub struct ActorOptions {
+    task_manager: TaskManager,
     channel_size: usize,
+    control_channel_size: usize,
+    metrics: Option<ActorMetricsRegistry>,
+    registry: Option<ActorRegistry>,
 }
 
-impl<'a> ActorOptions<'a> {
-    pub fn new(task_manager: &'a TaskManager) -> Self {
-        Self { task_manager, channel_size: 10 }
+impl ActorOptions {
+    pub fn new(task_manager: &TaskManager) -> Self {
+        Self {
+            task_manager: task_manager.clone(),
+            channel_size: 10,
+            control_channel_size: 10,
+            metrics: None,
+            registry: None,
+        }
+    }
+
+    pub fn set_task_manager(
+        &mut self,
+        task_manager: &TaskManager,
+    ) -> &mut Self {
+        self.task_manager = task_manager.clone();
+        self
+    }
+
+    pub fn with_task_manager(mut self, task_manager: &TaskManager) -> Self {
+        self.set_task_manager(task_manager);
+        self
     }
 
     pub fn set_channel_size(&mut self, size: usize) -> &mut Self {
@@ -97,24 +169,111 @@ impl<'a> ActorOptions<'a> {
         self
     }
 
+    pub fn set_control_channel_size(&mut self, size: usize) -> &mut Self {
+        self.control_channel_size = size;
+        self
+    }
+
+    pub fn with_control_channel_size(mut self, size: usize) -> Self {
+        self.set_control_channel_size(size);
+        self
+    }
+
+    pub fn set_metrics(
+        &mut self,
+        registry: Option<ActorMetricsRegistry>,
+    ) -> &mut Self {
+        self.metrics = registry;
+        self
+    }
+
+    pub fn with_metrics(
+        mut self,
+        registry: Option<ActorMetricsRegistry>,
+    ) -> Self {
+        self.set_metrics(registry);
+        self
+    }
+
+    pub fn metrics(&self) -> Option<&ActorMetricsRegistry> {
+        self.metrics.as_ref()
+    }
+
+    pub fn set_registry(
+        &mut self,
+        registry: Option<ActorRegistry>,
+    ) -> &mut Self {
+        self.registry = registry;
+        self
+    }
+
+    pub fn with_registry(mut self, registry: Option<ActorRegistry>) -> Self {
+        self.set_registry(registry);
+        self
+    }
+
+    pub fn registry(&self) -> Option<&ActorRegistry> {
+        self.registry.as_ref()
+    }
+
     pub fn spawn<A>(&self, actor: A) -> ActorHandle<A::Call>
+    where
+        A: Actor + Send + 'static,
+        A::Call: Send + 'static,
+    {
+        let type_name = any::type_name::<A>();
+        let name = type_name.rsplit("::").next().unwrap_or(type_name);
+        self.spawn_named(name, actor)
+    }
+
+    pub fn spawn_named<A>(
+        &self,
+        name: impl Into<String>,
+        actor: A,
+    ) -> ActorHandle<A::Call>
     where
         A: Actor + Send + 'static,
         A::Call: Send + 'static,
     {
         let (sender, receiver) = mpsc::channel(self.channel_size);
-        let handle = ActorHandle { inner: sender };
-        let inbox = ActorInbox { inner: receiver };
+        let (control_sender, control_receiver) =
+            mpsc::channel(self.control_channel_size);
+        let name = name.into();
+        let metrics = self.metrics.as_ref().map(|registry| {
+            registry.register(name.clone(), &sender, &control_sender)
+        });
+        let handle = ActorHandle {
+            regular: sender,
+            control: control_sender,
+            metrics: metrics.clone(),
+        };
+        if let Some(registry) = &self.registry {
+            registry.register(name.clone(), &handle);
+        }
+        let inbox = ActorInbox {
+            regular: receiver,
+            control: control_receiver,
+            metrics: metrics.clone(),
+        };
         let cancellation_token = self.task_manager.cancellation_token();
-        let task = async move { actor.start(inbox, cancellation_token).await };
-        self.task_manager.spawn(task);
+        let metrics_registry = self.metrics.clone();
+        let task = async move {
+            let result = actor.start(inbox, cancellation_token).await;
+            if let Some((registry, metrics)) = metrics_registry.zip(metrics) {
+                registry.unregister(&metrics);
+            }
+            result
+        };
+        self.task_manager.spawn_named(name, Criticality::Critical, task);
         handle
     }
 }
 
 #[derive(Debug)]
 pub struct ActorHandle<M> {
-    inner: mpsc::Sender<M>,
+    regular: mpsc::Sender<M>,
+    control: mpsc::Sender<M>,
+    metrics: Option<Arc<ActorMetrics>>,
 }
 
 impl<M> ActorHandle<M> {
@@ -123,17 +282,22 @@ impl<M> ActorHandle<M> {
         M: CallInjection<ActorCall<I, O>>,
     {
         let (sender, receiver) = oneshot::channel();
-        let callback = ActorCallback { sender };
-        let call = ActorCall { input, back: callback };
+        let callback = ActorCallback { sender, metrics: self.metrics.clone() };
+        let call = ActorCall { input, back: callback, span: Span::current() };
         self.forward(M::inject(call)).await?;
         receiver.await?
     }
 
     pub async fn forward<C>(&self, call: C) -> Result<()>
     where
-        M: From<C>,
+        M: From<C> + CallSuperset,
     {
-        if self.inner.send(call.into()).await.is_err() {
+        let call = M::from(call);
+        let sender = match call.lane() {
+            Lane::Control => &self.control,
+            Lane::Regular => &self.regular,
+        };
+        if sender.send(call).await.is_err() {
             tracing::warn!("callee has closed");
             bail!("callee actor disconnected");
         }
@@ -143,19 +307,32 @@ impl<M> ActorHandle<M> {
 
 impl<M> Clone for ActorHandle<M> {
     fn clone(&self) -> Self {
-        Self { inner: self.inner.clone() }
+        Self {
+            regular: self.regular.clone(),
+            control: self.control.clone(),
+            metrics: self.metrics.clone(),
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct ActorCallback<O> {
     sender: oneshot::Sender<Result<O>>,
+    metrics: Option<Arc<ActorMetrics>>,
 }
 
 impl<O> ActorCallback<O> {
     pub fn reply(self, output: Result<O>) -> bool {
+        if let Some(metrics) = &self.metrics
+            && output.is_err()
+        {
+            metrics.record_error_reply();
+        }
         let success = self.sender.send(output).is_ok();
         if !success {
+            if let Some(metrics) = &self.metrics {
+                metrics.record_dropped_reply();
+            }
             tracing::warn!("caller has closed");
         }
         success
@@ -179,6 +356,7 @@ impl<O> CallSuperset for ActorCallback<O> {
 pub struct ActorCall<I, O> {
     pub input: I,
     pub back: ActorCallback<O>,
+    pub span: Span,
 }
 
 impl<I, O> ActorCall<I, O> {
@@ -187,7 +365,7 @@ impl<I, O> ActorCall<I, O> {
         F: FnOnce(I) -> A,
         A: Future<Output = Result<O>>,
     {
-        let output = handler(self.input).await;
+        let output = handler(self.input).instrument(self.span).await;
         self.back.reply(output)
     }
 }
@@ -199,15 +377,37 @@ impl<I, O> CallSuperset for ActorCall<I, O> {
     {
         self.back.reply_error(error)
     }
+
+    fn span(&self) -> Span {
+        self.span.clone()
+    }
 }
 
 #[derive(Debug)]
 pub struct ActorInbox<M> {
-    inner: mpsc::Receiver<M>,
+    regular: mpsc::Receiver<M>,
+    control: mpsc::Receiver<M>,
+    metrics: Option<Arc<ActorMetrics>>,
 }
 
 impl<M> ActorInbox<M> {
     pub async fn recv(&mut self) -> Option<M> {
-        self.inner.recv().await
+        let message = select! {
+            biased;
+            Some(message) = self.control.recv() => Some(message),
+            message = self.regular.recv() => message,
+        };
+        if let Some(metrics) = &self.metrics
+            && message.is_some()
+        {
+            metrics.record_call();
+        }
+        message
+    }
+
+    fn record_handled(&self, elapsed: Duration) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_latency(elapsed);
+        }
     }
 }
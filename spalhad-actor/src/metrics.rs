@@ -0,0 +1,236 @@
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::sync::mpsc;
+
+const LATENCY_BOUNDS_MICROS: [u64; 16] = [
+    50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000,
+    250_000, 500_000, 1_000_000, 2_500_000, 5_000_000,
+];
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Default)]
+pub struct ActorMetricsRegistry {
+    actors: Arc<Mutex<Vec<Arc<ActorMetrics>>>>,
+}
+
+impl ActorMetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn register<M>(
+        &self,
+        name: impl Into<String>,
+        regular: &mpsc::Sender<M>,
+        control: &mpsc::Sender<M>,
+    ) -> Arc<ActorMetrics>
+    where
+        M: Send + 'static,
+    {
+        let metrics = Arc::new(ActorMetrics::new(
+            name.into(),
+            MailboxProbe::new(regular),
+            MailboxProbe::new(control),
+        ));
+        self.actors.lock().expect("poisoned lock").push(metrics.clone());
+        metrics
+    }
+
+    pub(crate) fn unregister(&self, metrics: &Arc<ActorMetrics>) {
+        let mut actors = self.actors.lock().expect("poisoned lock");
+        actors.retain(|actor| !Arc::ptr_eq(actor, metrics));
+    }
+
+    pub fn snapshot(&self) -> Vec<ActorMetricsSnapshot> {
+        let actors = self.actors.lock().expect("poisoned lock").clone();
+        actors.iter().map(|metrics| metrics.snapshot()).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActorMetricsSnapshot {
+    pub name: String,
+    pub uptime: Duration,
+    pub mailbox_len: Option<usize>,
+    pub control_mailbox_len: Option<usize>,
+    pub calls: u64,
+    pub calls_per_second: u64,
+    pub error_replies: u64,
+    pub dropped_replies: u64,
+    pub latency: LatencyHistogram,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    pub buckets: Vec<LatencyBucket>,
+    pub count: u64,
+    pub total: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyBucket {
+    pub upper_bound: Option<Duration>,
+    pub count: u64,
+}
+
+pub(crate) struct ActorMetrics {
+    name: String,
+    started_at: Instant,
+    regular: MailboxProbe,
+    control: MailboxProbe,
+    calls: AtomicU64,
+    error_replies: AtomicU64,
+    dropped_replies: AtomicU64,
+    rate: Mutex<RateWindow>,
+    latency_buckets: [AtomicU64; LATENCY_BOUNDS_MICROS.len() + 1],
+    latency_total_micros: AtomicU64,
+}
+
+impl ActorMetrics {
+    fn new(name: String, regular: MailboxProbe, control: MailboxProbe) -> Self {
+        let started_at = Instant::now();
+        Self {
+            name,
+            started_at,
+            regular,
+            control,
+            calls: AtomicU64::new(0),
+            error_replies: AtomicU64::new(0),
+            dropped_replies: AtomicU64::new(0),
+            rate: Mutex::new(RateWindow::new(started_at)),
+            latency_buckets: Default::default(),
+            latency_total_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn record_call(&self) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.rate.lock().expect("poisoned lock").record(Instant::now());
+    }
+
+    pub(crate) fn record_latency(&self, elapsed: Duration) {
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        let index = LATENCY_BOUNDS_MICROS
+            .iter()
+            .position(|bound| micros <= *bound)
+            .unwrap_or(LATENCY_BOUNDS_MICROS.len());
+        self.latency_buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.latency_total_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_error_reply(&self) {
+        self.error_replies.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_dropped_reply(&self) {
+        self.dropped_replies.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> ActorMetricsSnapshot {
+        let now = Instant::now();
+        let calls_per_second =
+            self.rate.lock().expect("poisoned lock").per_second(now);
+
+        let mut buckets = Vec::with_capacity(self.latency_buckets.len());
+        let mut count = 0;
+        for (i, bucket) in self.latency_buckets.iter().enumerate() {
+            let bucket_count = bucket.load(Ordering::Relaxed);
+            count += bucket_count;
+            buckets.push(LatencyBucket {
+                upper_bound: LATENCY_BOUNDS_MICROS
+                    .get(i)
+                    .copied()
+                    .map(Duration::from_micros),
+                count: bucket_count,
+            });
+        }
+        let total = Duration::from_micros(
+            self.latency_total_micros.load(Ordering::Relaxed),
+        );
+
+        ActorMetricsSnapshot {
+            name: self.name.clone(),
+            uptime: now.duration_since(self.started_at),
+            mailbox_len: self.regular.len(),
+            control_mailbox_len: self.control.len(),
+            calls: self.calls.load(Ordering::Relaxed),
+            calls_per_second,
+            error_replies: self.error_replies.load(Ordering::Relaxed),
+            dropped_replies: self.dropped_replies.load(Ordering::Relaxed),
+            latency: LatencyHistogram { buckets, count, total },
+        }
+    }
+}
+
+impl fmt::Debug for ActorMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ActorMetrics").field("name", &self.name).finish()
+    }
+}
+
+struct MailboxProbe {
+    len: Box<dyn Fn() -> Option<usize> + Send + Sync>,
+}
+
+impl MailboxProbe {
+    fn new<M>(sender: &mpsc::Sender<M>) -> Self
+    where
+        M: Send + 'static,
+    {
+        let weak = sender.downgrade();
+        let len = move || {
+            let sender = weak.upgrade()?;
+            Some(sender.max_capacity() - sender.capacity())
+        };
+        Self { len: Box::new(len) }
+    }
+
+    fn len(&self) -> Option<usize> {
+        (self.len)()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RateWindow {
+    start: Instant,
+    count: u64,
+    last_rate: u64,
+}
+
+impl RateWindow {
+    fn new(start: Instant) -> Self {
+        Self { start, count: 0, last_rate: 0 }
+    }
+
+    fn roll(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.start);
+        if elapsed >= RATE_WINDOW * 2 {
+            self.last_rate = 0;
+            self.count = 0;
+            self.start = now;
+        } else if elapsed >= RATE_WINDOW {
+            self.last_rate = self.count;
+            self.count = 0;
+            self.start += RATE_WINDOW;
+        }
+    }
+
+    fn record(&mut self, now: Instant) {
+        self.roll(now);
+        self.count += 1;
+    }
+
+    fn per_second(&mut self, now: Instant) -> u64 {
+        self.roll(now);
+        self.last_rate
+    }
+}
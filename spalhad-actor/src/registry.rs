@@ -0,0 +1,148 @@
+use std::{
+    any::{self, Any},
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        Arc,
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use spalhad_task::{Criticality, TaskManager};
+
+use crate::ActorHandle;
+
+struct Entry {
+    call_type: &'static str,
+    closed: Box<dyn Fn() -> bool + Send + Sync>,
+    handle: Box<dyn Any + Send + Sync>,
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Entry").field("call_type", &self.call_type).finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: Mutex<BTreeMap<String, Entry>>,
+    generation: AtomicU64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ActorRegistry {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredActor {
+    pub name: String,
+    pub call_type: &'static str,
+    pub closed: bool,
+}
+
+impl ActorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_entries<F, T>(&self, visitor: F) -> T
+    where
+        F: FnOnce(&mut BTreeMap<String, Entry>) -> T,
+    {
+        visitor(&mut self.inner.entries.lock().expect("poisoned lock"))
+    }
+
+    pub fn register<M>(
+        &self,
+        name: impl Into<String>,
+        handle: &ActorHandle<M>,
+    ) -> Option<ActorHandle<M>>
+    where
+        M: Send + 'static,
+    {
+        let type_name = any::type_name::<M>();
+        let probe = handle.clone();
+        let entry = Entry {
+            call_type: type_name.rsplit("::").next().unwrap_or(type_name),
+            closed: Box::new(move || probe.regular.is_closed()),
+            handle: Box::new(handle.clone()),
+        };
+        let previous =
+            self.with_entries(|entries| entries.insert(name.into(), entry));
+        self.inner.generation.fetch_add(1, Ordering::Release);
+        previous
+            .and_then(|entry| entry.handle.downcast().ok())
+            .map(|handle| *handle)
+    }
+
+    pub fn lookup<M>(&self, name: &str) -> Option<ActorHandle<M>>
+    where
+        M: 'static,
+    {
+        self.with_entries(|entries| {
+            entries.get(name)?.handle.downcast_ref::<ActorHandle<M>>().cloned()
+        })
+    }
+
+    pub fn lookup_all<M>(&self) -> Vec<(String, ActorHandle<M>)>
+    where
+        M: 'static,
+    {
+        self.with_entries(|entries| {
+            entries
+                .iter()
+                .filter_map(|(name, entry)| {
+                    let handle =
+                        entry.handle.downcast_ref::<ActorHandle<M>>()?;
+                    Some((name.clone(), handle.clone()))
+                })
+                .collect()
+        })
+    }
+
+    pub fn remove(&self, name: &str) -> bool {
+        let removed = self.with_entries(|entries| entries.remove(name));
+        self.inner.generation.fetch_add(1, Ordering::Release);
+        removed.is_some()
+    }
+
+    pub fn clear(&self) {
+        let entries = self.with_entries(std::mem::take);
+        self.inner.generation.fetch_add(1, Ordering::Release);
+        drop(entries);
+    }
+
+    pub fn clear_on_shutdown(&self, task_manager: &TaskManager) {
+        let registry = self.clone();
+        let cancellation_token = task_manager.cancellation_token();
+        task_manager.spawn_named(
+            "actor-registry",
+            Criticality::NonCritical,
+            async move {
+                cancellation_token.cancelled().await;
+                registry.clear();
+                Ok(())
+            },
+        );
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.inner.generation.load(Ordering::Acquire)
+    }
+
+    pub fn list(&self) -> Vec<RegisteredActor> {
+        self.with_entries(|entries| {
+            entries
+                .iter()
+                .map(|(name, entry)| RegisteredActor {
+                    name: name.clone(),
+                    call_type: entry.call_type,
+                    closed: (entry.closed)(),
+                })
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,256 @@
+use std::{future, time::Duration};
+
+use anyhow::Result;
+use futures::{StreamExt, stream::FuturesUnordered};
+use tokio::{
+    pin,
+    select,
+    time::{self, Instant},
+};
+
+use crate::{ActorCall, ActorHandle, CallInjection};
+
+#[derive(Debug)]
+pub struct Reply<K, O> {
+    pub target: K,
+    pub output: Result<O>,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug)]
+pub struct Gathered<K, O> {
+    pub replies: Vec<Reply<K, O>>,
+    pub abandoned: Vec<(K, Duration)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hedging {
+    pub initial: usize,
+    pub delay: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct Scatter<'a, K, M> {
+    targets: Vec<(K, &'a ActorHandle<M>)>,
+    concurrency: usize,
+    timeout: Option<Duration>,
+    hedging: Option<Hedging>,
+}
+
+impl<'a, K, M> Scatter<'a, K, M> {
+    pub fn new(
+        targets: impl IntoIterator<Item = (K, &'a ActorHandle<M>)>,
+    ) -> Self {
+        Self {
+            targets: targets.into_iter().collect(),
+            concurrency: 1,
+            timeout: None,
+            hedging: None,
+        }
+    }
+
+    pub fn set_concurrency(&mut self, concurrency: usize) -> &mut Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.set_concurrency(concurrency);
+        self
+    }
+
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.set_timeout(timeout);
+        self
+    }
+
+    pub fn set_hedging(&mut self, hedging: Option<Hedging>) -> &mut Self {
+        self.hedging = hedging;
+        self
+    }
+
+    pub fn with_hedging(mut self, hedging: Option<Hedging>) -> Self {
+        self.set_hedging(hedging);
+        self
+    }
+
+    pub async fn gather<I, O, P>(self, input: I, mut done: P) -> Gathered<K, O>
+    where
+        M: CallInjection<ActorCall<I, O>>,
+        K: Clone + PartialEq,
+        I: Clone,
+        P: FnMut(&[Reply<K, O>]) -> bool,
+    {
+        let mut targets = self.targets.into_iter();
+        let mut calls = FuturesUnordered::new();
+        let mut in_flight = Vec::new();
+        let mut launched = 0;
+        let mut budget =
+            self.hedging.map_or(usize::MAX, |hedging| hedging.initial.max(1));
+
+        let deadline = async {
+            match self.timeout {
+                Some(timeout) => time::sleep(timeout).await,
+                None => future::pending().await,
+            }
+        };
+        pin!(deadline);
+
+        let hedge_delay = self.hedging.and_then(|hedging| hedging.delay);
+        let hedge_timer = time::sleep(hedge_delay.unwrap_or(Duration::MAX));
+        pin!(hedge_timer);
+
+        let mut replies = Vec::new();
+        loop {
+            while calls.len() < self.concurrency && launched < budget {
+                let Some((target, handle)) = targets.next() else { break };
+                in_flight.push((target.clone(), Instant::now()));
+                calls.push(Self::call(target, handle, input.clone()));
+                launched += 1;
+            }
+            if calls.is_empty() {
+                if targets.len() == 0 {
+                    break;
+                }
+                budget = launched + 1;
+                continue;
+            }
+
+            let can_hedge = hedge_delay.is_some() && targets.len() > 0;
+            select! {
+                biased;
+                reply = calls.next() => {
+                    let Some(reply) = reply else { break };
+                    in_flight.retain(|(target, _)| *target != reply.target);
+                    if reply.output.is_err() {
+                        budget = budget.saturating_add(1);
+                    }
+                    replies.push(reply);
+                    if done(&replies) {
+                        break;
+                    }
+                },
+                _ = &mut hedge_timer, if can_hedge => {
+                    tracing::debug!(launched, "hedging scatter-gather call");
+                    budget = budget.saturating_add(1);
+                    if let Some(delay) = hedge_delay {
+                        hedge_timer.as_mut().reset(Instant::now() + delay);
+                    }
+                },
+                _ = &mut deadline => {
+                    tracing::debug!(
+                        replies = replies.len(),
+                        "scatter-gather timed out",
+                    );
+                    break;
+                },
+            }
+        }
+
+        let abandoned = in_flight
+            .into_iter()
+            .map(|(target, since)| (target, since.elapsed()))
+            .collect();
+        Gathered { replies, abandoned }
+    }
+
+    async fn call<I, O>(
+        target: K,
+        handle: &'a ActorHandle<M>,
+        input: I,
+    ) -> Reply<K, O>
+    where
+        M: CallInjection<ActorCall<I, O>>,
+    {
+        let started = Instant::now();
+        let output = handle.send(input).await;
+        Reply { target, output, elapsed: started.elapsed() }
+    }
+}
+
+pub fn quorum<K, O>(min_matching: usize) -> impl FnMut(&[Reply<K, O>]) -> bool
+where
+    O: PartialEq,
+{
+    move |replies| {
+        let Some(Ok(last)) = replies.last().map(|reply| &reply.output) else {
+            return false;
+        };
+        let matching = replies
+            .iter()
+            .filter(|reply| reply.output.as_ref().is_ok_and(|o| o == last))
+            .count();
+        matching >= min_matching
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Votes<O> {
+    entries: Vec<(O, usize)>,
+}
+
+impl<O> Default for Votes<O> {
+    fn default() -> Self {
+        Self { entries: Vec::new() }
+    }
+}
+
+impl<O> Votes<O>
+where
+    O: PartialEq,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_replies<K>(
+        replies: impl IntoIterator<Item = Reply<K, O>>,
+    ) -> Self {
+        let mut votes = Self::new();
+        for reply in replies {
+            if let Ok(output) = reply.output {
+                votes.add(output);
+            }
+        }
+        votes
+    }
+
+    pub fn add(&mut self, output: O) {
+        match self.entries.iter_mut().find(|(entry, _)| *entry == output) {
+            Some((_, count)) => *count += 1,
+            None => self.entries.push((output, 1)),
+        }
+    }
+
+    pub fn count(&self, output: &O) -> usize {
+        self.entries
+            .iter()
+            .find(|(entry, _)| entry == output)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    pub fn total(&self) -> usize {
+        self.entries.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn max_count(&self) -> usize {
+        self.entries.iter().map(|(_, count)| *count).max().unwrap_or(0)
+    }
+
+    pub fn into_winner(self, min_votes: usize) -> Option<O> {
+        let mut winner: Option<(O, usize)> = None;
+        for (output, count) in self.entries {
+            let has_more_votes =
+                winner.as_ref().is_none_or(|(_, best)| count > *best);
+            if has_more_votes && count >= min_votes {
+                winner = Some((output, count));
+            }
+        }
+        winner.map(|(output, _)| output)
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use spalhad_actor::{ActorCall, ActorOptions, CallSuperset, TrivialLoopActor};
+use spalhad_task::TaskManager;
+use tokio::{sync::Notify, task};
+
+#[derive(Debug)]
+struct Work(&'static str);
+
+#[derive(Debug)]
+struct Urgent(&'static str);
+
+#[derive(Debug, CallSuperset)]
+enum RecorderCall {
+    Work(ActorCall<Work, ()>),
+    #[spalhad(control)]
+    Urgent(ActorCall<Urgent, ()>),
+}
+
+#[derive(Debug)]
+struct Recorder {
+    gate: Arc<Notify>,
+    handled: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl TrivialLoopActor for Recorder {
+    type Call = RecorderCall;
+
+    async fn on_call(&mut self, call: Self::Call) -> Result<()> {
+        match call {
+            RecorderCall::Work(call) => {
+                let Work(label) = call.input;
+                if label == "blocker" {
+                    self.gate.notified().await;
+                }
+                self.handled.lock().expect("poisoned lock").push(label);
+                call.back.reply_ok(());
+            },
+            RecorderCall::Urgent(call) => {
+                let Urgent(label) = call.input;
+                self.handled.lock().expect("poisoned lock").push(label);
+                call.back.reply_ok(());
+            },
+        }
+        Ok(())
+    }
+}
+
+async fn settle() {
+    for _ in 0 .. 16 {
+        task::yield_now().await;
+    }
+}
+
+#[tokio::test]
+async fn control_call_overtakes_full_regular_queue() {
+    let task_manager = TaskManager::new();
+    let gate = Arc::new(Notify::new());
+    let handled = Arc::new(Mutex::new(Vec::new()));
+    let recorder = Recorder { gate: gate.clone(), handled: handled.clone() };
+    let handle = ActorOptions::new(&task_manager)
+        .with_channel_size(2)
+        .with_control_channel_size(1)
+        .spawn(recorder);
+
+    let mut calls = Vec::new();
+    for label in ["blocker", "first", "second", "third"] {
+        let handle = handle.clone();
+        calls.push(task::spawn(async move { handle.send(Work(label)).await }));
+        settle().await;
+    }
+    let urgent = {
+        let handle = handle.clone();
+        task::spawn(async move { handle.send(Urgent("urgent")).await })
+    };
+    settle().await;
+    assert!(handled.lock().expect("poisoned lock").is_empty());
+
+    gate.notify_one();
+    urgent.await.expect("join urgent").expect("urgent call");
+    for call in calls {
+        call.await.expect("join work").expect("work call");
+    }
+
+    let handled = handled.lock().expect("poisoned lock").clone();
+    assert_eq!(handled, ["blocker", "urgent", "first", "second", "third"]);
+
+    drop(handle);
+    task_manager.cancel();
+    task_manager.wait_all().await.expect("actor should stop cleanly");
+}
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use spalhad_actor::{
+    ActorCall,
+    ActorHandle,
+    ActorMetricsRegistry,
+    ActorMetricsSnapshot,
+    ActorOptions,
+    CallSuperset,
+    TrivialLoopActor,
+};
+use spalhad_task::TaskManager;
+use tokio::time;
+
+#[derive(Debug)]
+enum Job {
+    Sleep(Duration),
+    FailLater,
+    ReplyLater(Duration),
+}
+
+#[derive(Debug, CallSuperset)]
+enum WorkerCall {
+    Job(ActorCall<Job, ()>),
+}
+
+#[derive(Debug)]
+struct Worker;
+
+impl TrivialLoopActor for Worker {
+    type Call = WorkerCall;
+
+    async fn on_call(&mut self, call: Self::Call) -> Result<()> {
+        let WorkerCall::Job(call) = call;
+        match call.input {
+            Job::Sleep(duration) => {
+                time::sleep(duration).await;
+                call.back.reply_ok(());
+            },
+            Job::FailLater => {
+                tokio::spawn(async move {
+                    call.back.reply(Err(anyhow!("failed in a spawned task")));
+                });
+            },
+            Job::ReplyLater(delay) => {
+                tokio::spawn(async move {
+                    time::sleep(delay).await;
+                    call.back.reply_ok(());
+                });
+            },
+        }
+        Ok(())
+    }
+}
+
+async fn eventually<F>(registry: &ActorMetricsRegistry, mut check: F)
+where
+    F: FnMut(&[ActorMetricsSnapshot]) -> bool,
+{
+    for _ in 0 .. 1000 {
+        if check(&registry.snapshot()) {
+            return;
+        }
+        time::sleep(Duration::from_millis(1)).await;
+    }
+    panic!("metrics never matched: {:?}", registry.snapshot());
+}
+
+fn spawn_worker(
+    task_manager: &TaskManager,
+    registry: &ActorMetricsRegistry,
+    name: &str,
+) -> ActorHandle<WorkerCall> {
+    ActorOptions::new(task_manager)
+        .with_metrics(Some(registry.clone()))
+        .spawn_named(name, Worker)
+}
+
+#[tokio::test]
+async fn latency_covers_only_the_handler() {
+    let task_manager = TaskManager::new();
+    let registry = ActorMetricsRegistry::new();
+    let worker = spawn_worker(&task_manager, &registry, "worker");
+
+    let handler_time = Duration::from_millis(20);
+    worker.send(Job::Sleep(handler_time)).await.expect("first call");
+    time::sleep(Duration::from_millis(300)).await;
+    worker.send(Job::Sleep(handler_time)).await.expect("second call");
+
+    eventually(&registry, |actors| actors[0].latency.count == 2).await;
+    let latency = registry.snapshot()[0].latency.clone();
+    assert!(latency.total >= handler_time * 2, "{latency:?}");
+    assert!(latency.total < Duration::from_millis(250), "{latency:?}");
+
+    drop(worker);
+    task_manager.wait_all().await.expect("worker should stop cleanly");
+}
+
+#[tokio::test]
+async fn replies_from_spawned_tasks_are_counted() {
+    let task_manager = TaskManager::new();
+    let registry = ActorMetricsRegistry::new();
+    let worker = spawn_worker(&task_manager, &registry, "worker");
+
+    worker
+        .send(Job::FailLater)
+        .await
+        .expect_err("spawned task replies with an error");
+    let abandoned = time::timeout(
+        Duration::from_millis(10),
+        worker.send(Job::ReplyLater(Duration::from_millis(50))),
+    )
+    .await;
+    assert!(abandoned.is_err(), "caller should give up first");
+
+    eventually(&registry, |actors| {
+        actors[0].error_replies == 1 && actors[0].dropped_replies == 1
+    })
+    .await;
+    assert_eq!(registry.snapshot()[0].calls, 2);
+
+    drop(worker);
+    task_manager.wait_all().await.expect("worker should stop cleanly");
+}
+
+#[tokio::test]
+async fn stopped_actors_are_unregistered() {
+    let task_manager = TaskManager::new();
+    let registry = ActorMetricsRegistry::new();
+    let first = spawn_worker(&task_manager, &registry, "first");
+    let second = spawn_worker(&task_manager, &registry, "second");
+    eventually(&registry, |actors| actors.len() == 2).await;
+
+    drop(first);
+    eventually(&registry, |actors| {
+        actors.iter().map(|actor| actor.name.as_str()).eq(["second"])
+    })
+    .await;
+
+    drop(second);
+    task_manager.wait_all().await.expect("workers should stop cleanly");
+    assert!(registry.snapshot().is_empty());
+}
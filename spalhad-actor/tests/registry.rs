@@ -0,0 +1,143 @@
+use anyhow::Result;
+use spalhad_actor::{
+    ActorCall,
+    ActorHandle,
+    ActorOptions,
+    ActorRegistry,
+    CallSuperset,
+    RegisteredActor,
+    TrivialLoopActor,
+};
+use spalhad_task::TaskManager;
+
+#[derive(Debug)]
+struct Ping;
+
+#[derive(Debug, CallSuperset)]
+enum EchoCall {
+    Ping(ActorCall<Ping, u32>),
+}
+
+#[derive(Debug, CallSuperset)]
+enum OtherCall {
+    Ping(ActorCall<Ping, ()>),
+}
+
+#[derive(Debug)]
+struct Echo(u32);
+
+impl TrivialLoopActor for Echo {
+    type Call = EchoCall;
+
+    async fn on_call(&mut self, call: Self::Call) -> Result<()> {
+        let EchoCall::Ping(call) = call;
+        call.back.reply_ok(self.0);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Other;
+
+impl TrivialLoopActor for Other {
+    type Call = OtherCall;
+
+    async fn on_call(&mut self, call: Self::Call) -> Result<()> {
+        let OtherCall::Ping(call) = call;
+        call.back.reply_ok(());
+        Ok(())
+    }
+}
+
+fn spawn_echo(task_manager: &TaskManager, id: u32) -> ActorHandle<EchoCall> {
+    ActorOptions::new(task_manager).spawn(Echo(id))
+}
+
+#[tokio::test]
+async fn every_change_bumps_the_generation() {
+    let task_manager = TaskManager::new();
+    let registry = ActorRegistry::new();
+    let echo = spawn_echo(&task_manager, 0);
+    assert_eq!(registry.generation(), 0);
+
+    registry.register("echo", &echo);
+    assert_eq!(registry.generation(), 1);
+    registry.register("echo", &echo);
+    assert_eq!(registry.generation(), 2);
+    assert!(registry.remove("echo"));
+    assert_eq!(registry.generation(), 3);
+    assert!(!registry.remove("echo"));
+    assert_eq!(registry.generation(), 4);
+    registry.clear();
+    assert_eq!(registry.generation(), 5);
+}
+
+#[tokio::test]
+async fn registering_a_name_again_replaces_the_handle() {
+    let task_manager = TaskManager::new();
+    let registry = ActorRegistry::new();
+    let first = spawn_echo(&task_manager, 1);
+    let second = spawn_echo(&task_manager, 2);
+
+    assert!(registry.register("echo", &first).is_none());
+    let previous = registry.register("echo", &second).expect("previous");
+    assert_eq!(previous.send(Ping).await.expect("previous ping"), 1);
+
+    let current = registry.lookup::<EchoCall>("echo").expect("current");
+    assert_eq!(current.send(Ping).await.expect("current ping"), 2);
+    assert_eq!(registry.list().len(), 1);
+}
+
+#[tokio::test]
+async fn lookups_are_typed() {
+    let task_manager = TaskManager::new();
+    let registry = ActorRegistry::new();
+    let echo = spawn_echo(&task_manager, 0);
+    let other = ActorOptions::new(&task_manager).spawn(Other);
+    registry.register("echo", &echo);
+    registry.register("other", &other);
+
+    assert!(registry.lookup::<OtherCall>("echo").is_none());
+    assert!(registry.lookup::<EchoCall>("missing").is_none());
+    let names: Vec<_> = registry
+        .lookup_all::<EchoCall>()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(names, ["echo"]);
+
+    assert!(registry.register::<OtherCall>("echo", &other).is_none());
+    assert!(registry.lookup::<EchoCall>("echo").is_none());
+}
+
+#[tokio::test]
+async fn clear_drops_handles_so_actors_stop() {
+    let task_manager = TaskManager::new();
+    let registry = ActorRegistry::new();
+    registry.register("echo", &spawn_echo(&task_manager, 0));
+    assert_eq!(
+        registry.list(),
+        [RegisteredActor {
+            name: "echo".to_owned(),
+            call_type: "EchoCall",
+            closed: false,
+        }]
+    );
+
+    registry.clear();
+    assert!(registry.list().is_empty());
+    assert!(registry.lookup::<EchoCall>("echo").is_none());
+    task_manager.wait_all().await.expect("actor should stop cleanly");
+}
+
+#[tokio::test]
+async fn clear_on_shutdown_releases_handles() {
+    let task_manager = TaskManager::new();
+    let registry = ActorRegistry::new();
+    registry.register("echo", &spawn_echo(&task_manager, 0));
+    registry.clear_on_shutdown(&task_manager);
+
+    task_manager.cancel();
+    task_manager.wait_all().await.expect("registry should shut down");
+    assert!(registry.list().is_empty());
+}
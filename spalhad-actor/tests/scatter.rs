@@ -0,0 +1,234 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::{Result, bail};
+use spalhad_actor::{
+    ActorCall,
+    ActorHandle,
+    ActorOptions,
+    CallSuperset,
+    Hedging,
+    Scatter,
+    TrivialLoopActor,
+    Votes,
+    quorum,
+};
+use spalhad_task::TaskManager;
+use tokio::time;
+
+#[derive(Debug, Clone)]
+struct Read;
+
+#[derive(Debug, CallSuperset)]
+enum ReplicaCall {
+    Read(ActorCall<Read, u32>),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Load {
+    current: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+#[derive(Debug)]
+struct Replica {
+    value: Option<u32>,
+    delay: Duration,
+    load: Load,
+}
+
+impl TrivialLoopActor for Replica {
+    type Call = ReplicaCall;
+
+    async fn on_call(&mut self, call: Self::Call) -> Result<()> {
+        let ReplicaCall::Read(call) = call;
+        let load = self.load.clone();
+        let current = load.current.fetch_add(1, Ordering::SeqCst) + 1;
+        load.peak.fetch_max(current, Ordering::SeqCst);
+        time::sleep(self.delay).await;
+        load.current.fetch_sub(1, Ordering::SeqCst);
+        let output = match self.value {
+            Some(value) => Ok(value),
+            None => Err(anyhow::anyhow!("replica failed")),
+        };
+        call.back.reply(output);
+        Ok(())
+    }
+}
+
+fn spawn_replicas(
+    task_manager: &TaskManager,
+    load: &Load,
+    replicas: &[(Option<u32>, u64)],
+) -> Vec<ActorHandle<ReplicaCall>> {
+    let options = ActorOptions::new(task_manager);
+    replicas
+        .iter()
+        .map(|&(value, delay_ms)| {
+            options.spawn(Replica {
+                value,
+                delay: Duration::from_millis(delay_ms),
+                load: load.clone(),
+            })
+        })
+        .collect()
+}
+
+fn targets(
+    handles: &[ActorHandle<ReplicaCall>],
+) -> impl Iterator<Item = (usize, &ActorHandle<ReplicaCall>)> {
+    handles.iter().enumerate()
+}
+
+fn replied(replies: &[spalhad_actor::Reply<usize, u32>]) -> Vec<usize> {
+    replies.iter().map(|reply| reply.target).collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn gather_stops_once_quorum_agrees() {
+    let task_manager = TaskManager::new();
+    let load = Load::default();
+    let handles = spawn_replicas(
+        &task_manager,
+        &load,
+        &[(Some(7), 10), (Some(7), 20), (Some(7), 30)],
+    );
+
+    let gathered = Scatter::new(targets(&handles))
+        .with_concurrency(3)
+        .gather(Read, quorum(2))
+        .await;
+
+    assert_eq!(replied(&gathered.replies), [0, 1]);
+    let abandoned: Vec<_> =
+        gathered.abandoned.iter().map(|(target, _)| *target).collect();
+    assert_eq!(abandoned, [2]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn gather_respects_concurrency_limit() {
+    let task_manager = TaskManager::new();
+    let load = Load::default();
+    let handles = spawn_replicas(
+        &task_manager,
+        &load,
+        &[(Some(1), 10), (Some(1), 10), (Some(1), 10), (Some(1), 10)],
+    );
+
+    let gathered = Scatter::new(targets(&handles))
+        .with_concurrency(2)
+        .gather(Read, |_| false)
+        .await;
+
+    assert_eq!(gathered.replies.len(), 4);
+    assert!(gathered.abandoned.is_empty());
+    assert_eq!(load.peak.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn gather_keeps_disagreeing_and_failed_replies() {
+    let task_manager = TaskManager::new();
+    let load = Load::default();
+    let handles = spawn_replicas(
+        &task_manager,
+        &load,
+        &[(Some(1), 10), (None, 20), (Some(2), 30)],
+    );
+
+    let gathered = Scatter::new(targets(&handles))
+        .with_concurrency(3)
+        .gather(Read, quorum(2))
+        .await;
+
+    assert_eq!(replied(&gathered.replies), [0, 1, 2]);
+    assert!(gathered.replies[1].output.is_err());
+    let votes = Votes::from_replies(gathered.replies);
+    assert_eq!(votes.total(), 2);
+    assert_eq!(votes.max_count(), 1);
+    assert_eq!(votes.into_winner(2), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn gather_gives_up_at_timeout() {
+    let task_manager = TaskManager::new();
+    let load = Load::default();
+    let handles =
+        spawn_replicas(&task_manager, &load, &[(Some(1), 100), (Some(1), 5)]);
+
+    let gathered = Scatter::new(targets(&handles))
+        .with_concurrency(2)
+        .with_timeout(Some(Duration::from_millis(50)))
+        .gather(Read, quorum(2))
+        .await;
+
+    assert_eq!(replied(&gathered.replies), [1]);
+    let abandoned: Vec<_> =
+        gathered.abandoned.iter().map(|(target, _)| *target).collect();
+    assert_eq!(abandoned, [0]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn hedging_replaces_failed_and_slow_targets() {
+    let task_manager = TaskManager::new();
+    let load = Load::default();
+    let handles = spawn_replicas(
+        &task_manager,
+        &load,
+        &[(None, 1), (Some(3), 100), (Some(3), 5)],
+    );
+
+    let gathered = Scatter::new(targets(&handles))
+        .with_concurrency(3)
+        .with_hedging(Some(Hedging {
+            initial: 1,
+            delay: Some(Duration::from_millis(10)),
+        }))
+        .gather(Read, quorum(1))
+        .await;
+
+    assert_eq!(replied(&gathered.replies), [0, 2]);
+    let abandoned: Vec<_> =
+        gathered.abandoned.iter().map(|(target, _)| *target).collect();
+    assert_eq!(abandoned, [1]);
+}
+
+#[test]
+fn votes_pick_the_value_with_enough_votes() {
+    let mut votes = Votes::new();
+    for value in [1, 2, 1, 3] {
+        votes.add(value);
+    }
+    assert_eq!(votes.count(&1), 2);
+    assert_eq!(votes.count(&4), 0);
+    assert_eq!(votes.total(), 4);
+    assert_eq!(votes.max_count(), 2);
+    assert_eq!(votes.clone().into_winner(2), Some(1));
+    assert_eq!(votes.into_winner(3), None);
+}
+
+#[test]
+fn votes_break_ties_by_first_reply() {
+    let mut votes = Votes::new();
+    for value in [2, 1, 1, 2] {
+        votes.add(value);
+    }
+    assert_eq!(votes.clone().into_winner(2), Some(2));
+    assert_eq!(votes.into_winner(3), None);
+    assert_eq!(Votes::<u32>::new().into_winner(0), None);
+}
+
+#[tokio::test]
+async fn failing_replicas_are_reported_as_errors() -> Result<()> {
+    let task_manager = TaskManager::new();
+    let handles = spawn_replicas(&task_manager, &Load::default(), &[(None, 0)]);
+    match handles[0].send(Read).await {
+        Ok(value) => bail!("unexpected value {value}"),
+        Err(error) => assert_eq!(error.to_string(), "replica failed"),
+    }
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use spalhad_actor::{ActorCall, CallSuperset};
+
+struct Origin;
+
+#[derive(CallSuperset)]
+enum ServiceCall {
+    Ping {
+        #[spalhad(call)]
+        call: ActorCall<(), ()>,
+        #[spalhad(default)]
+        origin: Origin,
+    },
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use spalhad_actor::CallSuperset;
+
+#[derive(CallSuperset)]
+enum ServiceCall {
+    #[spalhad(control)]
+    Stop,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use spalhad_actor::{ActorCall, CallSuperset};
+
+type GetCall = ActorCall<u64, Option<String>>;
+
+#[derive(CallSuperset)]
+enum StorageCall {
+    Get(GetCall),
+}
+
+#[derive(CallSuperset)]
+enum FrontCall {
+    Get(GetCall),
+    #[spalhad(flatten(GetCall))]
+    Storage(StorageCall),
+}
+
+fn main() {}
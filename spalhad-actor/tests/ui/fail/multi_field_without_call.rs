@@ -0,0 +1,8 @@
+use spalhad_actor::{ActorCall, CallSuperset};
+
+#[derive(CallSuperset)]
+enum ServiceCall {
+    Ping(ActorCall<(), ()>, u32),
+}
+
+fn main() {}
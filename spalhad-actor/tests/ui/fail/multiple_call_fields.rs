@@ -0,0 +1,13 @@
+use spalhad_actor::{ActorCall, CallSuperset};
+
+#[derive(CallSuperset)]
+enum ServiceCall {
+    Ping {
+        #[spalhad(call)]
+        first: ActorCall<(), ()>,
+        #[spalhad(call)]
+        second: ActorCall<(), ()>,
+    },
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use spalhad_actor::{ActorCall, CallSuperset};
+
+#[derive(CallSuperset)]
+struct PingCall {
+    call: ActorCall<(), ()>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use spalhad_actor::{ActorCall, CallSuperset};
+
+#[derive(CallSuperset)]
+enum ServiceCall {
+    #[spalhad(skip)]
+    #[spalhad(control)]
+    Ping(ActorCall<(), ()>),
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use spalhad_actor::{ActorCall, CallSuperset};
+
+#[derive(CallSuperset)]
+enum ServiceCall {
+    Ping {
+        #[spalhad(call)]
+        call: ActorCall<(), ()>,
+        hops: u32,
+    },
+}
+
+fn main() {}
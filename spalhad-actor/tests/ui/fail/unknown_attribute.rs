@@ -0,0 +1,9 @@
+use spalhad_actor::{ActorCall, CallSuperset};
+
+#[derive(CallSuperset)]
+enum ServiceCall {
+    #[spalhad(urgent)]
+    Ping(ActorCall<(), ()>),
+}
+
+fn main() {}
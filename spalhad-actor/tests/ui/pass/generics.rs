@@ -0,0 +1,30 @@
+use std::{fmt::Debug, marker::PhantomData};
+
+use spalhad_actor::{ActorCall, CallInjection, CallSuperset};
+
+type GetCall<K> = ActorCall<K, Option<String>>;
+
+type BatchCall<const N: usize> = ActorCall<[u8; N], usize>;
+
+#[derive(CallSuperset)]
+enum StoreCall<'a, K, const N: usize>
+where
+    K: Debug + Clone,
+{
+    Get(GetCall<K>),
+    Batch { call: BatchCall<N> },
+    #[spalhad(skip)]
+    Borrowed(PhantomData<&'a K>),
+}
+
+fn assert_injects<S, C>()
+where
+    S: CallInjection<C> + From<C>,
+    C: spalhad_actor::CallConnectors,
+{
+}
+
+fn main() {
+    assert_injects::<StoreCall<'static, u64, 4>, GetCall<u64>>();
+    assert_injects::<StoreCall<'static, u64, 4>, BatchCall<4>>();
+}
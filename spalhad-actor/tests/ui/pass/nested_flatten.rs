@@ -0,0 +1,45 @@
+use spalhad_actor::{ActorCall, CallInjection, CallSuperset};
+
+type GetCall = ActorCall<u64, Option<String>>;
+
+type PutCall = ActorCall<(u64, String), bool>;
+
+type StatusCall = ActorCall<(), String>;
+
+type PingCall = ActorCall<(), ()>;
+
+#[derive(CallSuperset)]
+enum StorageCall {
+    Get(GetCall),
+    Put(PutCall),
+}
+
+#[derive(CallSuperset)]
+enum CoordinatorCall {
+    #[spalhad(flatten(GetCall, PutCall))]
+    Storage(StorageCall),
+    Status(StatusCall),
+}
+
+#[derive(CallSuperset)]
+enum FrontCall<T> {
+    #[spalhad(flatten[GetCall, PutCall, StatusCall])]
+    Coordinator(CoordinatorCall),
+    Ping { call: PingCall },
+    #[spalhad(skip)]
+    Custom(T),
+}
+
+fn assert_injects<S, C>()
+where
+    S: CallInjection<C> + From<C>,
+    C: spalhad_actor::CallConnectors,
+{
+}
+
+fn main() {
+    assert_injects::<FrontCall<u8>, GetCall>();
+    assert_injects::<FrontCall<u8>, PutCall>();
+    assert_injects::<FrontCall<u8>, StatusCall>();
+    assert_injects::<FrontCall<u8>, PingCall>();
+}
@@ -0,0 +1,45 @@
+use spalhad_actor::{ActorCall, CallInjection, CallSuperset, Lane};
+
+type PingCall = ActorCall<(), ()>;
+
+type StopCall = ActorCall<bool, ()>;
+
+#[derive(CallSuperset)]
+enum ServiceCall {
+    Ping {
+        #[spalhad(call)]
+        call: PingCall,
+        #[spalhad(default)]
+        hops: u32,
+        #[spalhad(default)]
+        origin: Option<String>,
+    },
+    #[spalhad(control)]
+    Stop(#[spalhad(default)] u8, #[spalhad(call)] StopCall),
+    #[spalhad(skip)]
+    Tick,
+    #[spalhad(skip)]
+    Notify(String),
+}
+
+#[derive(CallSuperset)]
+enum OnlySkipped {
+    #[spalhad(skip)]
+    Idle,
+}
+
+#[derive(CallSuperset)]
+enum Empty {}
+
+fn main() {
+    let tick = ServiceCall::Tick;
+    assert!(tick.lane() == Lane::Regular);
+    assert!(!tick.reply_error(anyhow::anyhow!("unanswered")));
+    assert!(!ServiceCall::Notify(String::new()).reply_error(anyhow::anyhow!("x")));
+    assert!(!OnlySkipped::Idle.reply_error(anyhow::anyhow!("unanswered")));
+
+    fn assert_injects<S: CallInjection<PingCall>>() {}
+    assert_injects::<ServiceCall>();
+
+    let _ = |empty: Empty| empty.lane();
+}
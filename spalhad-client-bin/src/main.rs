@@ -1,13 +1,33 @@
-use std::{backtrace::BacktraceStatus, process::exit};
+use std::{
+    backtrace::BacktraceStatus,
+    io::{self, Write},
+    path::PathBuf,
+    process::exit,
+};
 
 use anyhow::{Result, bail};
 use clap::{Parser, Subcommand};
-use spalhad_client::Client;
+use spalhad_client::{Client, ResponseError, RetryPolicy};
+use spalhad_spec::{
+    kv::{BytesValue, Namespace, value::OCTET_STREAM_CONTENT_TYPE},
+    replica::ReplicaOutcome,
+};
+use tokio::fs;
 
 #[derive(Debug, Clone, Parser)]
 struct CliArgs {
     #[clap(short, long, default_value = "http://localhost:5500")]
     base_url: String,
+    #[clap(short, long, default_value_t = 0)]
+    retries: usize,
+    #[clap(long)]
+    legacy_fallback: bool,
+    #[clap(short, long)]
+    namespace: Option<Namespace>,
+    #[clap(long)]
+    store_original_keys: bool,
+    #[clap(short, long)]
+    verbose: bool,
     #[clap(subcommand)]
     cmd: Cmd,
 }
@@ -24,29 +44,143 @@ enum Cmd {
         #[clap(short, long)]
         value: String,
     },
+    GetBytes {
+        #[clap(short, long)]
+        key: String,
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    PutBytes {
+        #[clap(short, long)]
+        key: String,
+        #[clap(short, long)]
+        file: PathBuf,
+        #[clap(short, long, default_value = OCTET_STREAM_CONTENT_TYPE)]
+        content_type: String,
+    },
+    Migrate {
+        #[clap(short, long)]
+        key: String,
+    },
     RunId,
 }
 
 async fn try_main(args: CliArgs) -> Result<()> {
-    let client = Client::new(args.base_url);
-    match args.cmd {
-        Cmd::Get { key } => match client.get(key).await? {
-            Some(value) => {
-                let value: serde_json::Value = value;
-                println!("{}", serde_json::to_string_pretty(&value)?)
-            },
-            None => {
-                bail!("Not found")
-            },
+    let client = Client::new(args.base_url)
+        .with_retry_policy(RetryPolicy::new().with_max_retries(args.retries))
+        .with_legacy_fallback(args.legacy_fallback)
+        .with_store_original_keys(args.store_original_keys)
+        .with_debug_replicas(args.verbose);
+    let mut replicas = Vec::new();
+    let result =
+        run(&client, args.namespace.as_ref(), args.cmd, &mut replicas).await;
+    if args.verbose {
+        if let Err(error) = &result
+            && let Some(error) = error
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<ResponseError>())
+        {
+            replicas = error.replicas().to_vec();
+        }
+        print_replicas(&replicas);
+    }
+    result
+}
+
+fn print_replicas(replicas: &[ReplicaOutcome]) {
+    if replicas.is_empty() {
+        return;
+    }
+    eprintln!("Replicas:");
+    for replica in replicas {
+        eprintln!("  - {replica}");
+    }
+}
+
+async fn run(
+    client: &Client,
+    namespace: Option<&Namespace>,
+    cmd: Cmd,
+    replicas: &mut Vec<ReplicaOutcome>,
+) -> Result<()> {
+    match cmd {
+        Cmd::Get { key } => {
+            let value: Option<serde_json::Value>;
+            (value, *replicas) = match namespace {
+                Some(namespace) => {
+                    client.get_namespaced_with_replicas(namespace, &key).await?
+                },
+                None => client.get_with_replicas(key).await?,
+            };
+            match value {
+                Some(value) => {
+                    println!("{}", serde_json::to_string_pretty(&value)?)
+                },
+                None => {
+                    bail!("Not found")
+                },
+            }
         },
         Cmd::Put { key, value } => {
             let value: serde_json::Value = serde_json::from_str(&value)?;
-            if client.put(key, value).await? {
+            let new;
+            (new, *replicas) = match namespace {
+                Some(namespace) => {
+                    client
+                        .put_namespaced_with_replicas(namespace, &key, value)
+                        .await?
+                },
+                None => client.put_with_replicas(key, value).await?,
+            };
+            if new {
                 println!("Inserted new entry");
             } else {
                 println!("Updated");
             }
         },
+        Cmd::GetBytes { key, output } => {
+            let value;
+            (value, *replicas) = match namespace {
+                Some(namespace) => {
+                    client
+                        .get_namespaced_bytes_with_replicas(namespace, &key)
+                        .await?
+                },
+                None => client.get_bytes_with_replicas(key).await?,
+            };
+            let Some(value) = value else { bail!("Not found") };
+            eprintln!("Content type: {}", value.content_type);
+            match output {
+                Some(path) => fs::write(path, &value.data).await?,
+                None => io::stdout().write_all(&value.data)?,
+            }
+        },
+        Cmd::PutBytes { key, file, content_type } => {
+            let value = BytesValue::new(content_type, fs::read(file).await?);
+            let new;
+            (new, *replicas) = match namespace {
+                Some(namespace) => {
+                    client
+                        .put_namespaced_bytes_with_replicas(
+                            namespace, &key, value,
+                        )
+                        .await?
+                },
+                None => client.put_bytes_with_replicas(key, value).await?,
+            };
+            if new {
+                println!("Inserted new entry");
+            } else {
+                println!("Updated");
+            }
+        },
+        Cmd::Migrate { key } => {
+            if client.migrate_key(&key).await? {
+                println!("Migrated");
+            } else {
+                println!("Nothing to migrate");
+            }
+        },
         Cmd::RunId => {
             let run_id = client.run_id().await?;
             println!("{}", run_id);
@@ -0,0 +1,736 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Result, bail};
+use reqwest::{
+    IntoUrl,
+    StatusCode,
+    blocking::{RequestBuilder, Response},
+    header::{ACCEPT, CONTENT_TYPE},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use spalhad_spec::{
+    cluster::{
+        ActivateRequest,
+        ActivateResponse,
+        IsActiveResponse,
+        RunId,
+        RunIdResponse,
+    },
+    kv::{
+        BytesValue,
+        DeleteResponse,
+        GetResponse,
+        KEY_ORIGIN_HEADER,
+        Key,
+        KeyOrigin,
+        Namespace,
+        PutRequest,
+        PutResponse,
+        Value,
+    },
+    replica::ReplicaOutcome,
+    wire::WireFormat,
+};
+
+use crate::{
+    DEFAULT_TIMEOUT,
+    ErrorResponse,
+    ResponseError,
+    RetryPolicy,
+    binary_content_type,
+    key_origin,
+    legacy_key,
+    loaded_topology_version,
+    namespaced_url,
+    observe_topology_version,
+    request_headers,
+    response_replicas,
+    response_wire_format,
+    retry::{Attempt, Idempotency},
+};
+
+impl ResponseError {
+    fn from_blocking(response: Response) -> Result<Self> {
+        let status_code = response.status();
+        let text_body = response.text()?;
+        Ok(Self::from_response(ErrorResponse::new(status_code, text_body)))
+    }
+
+    fn bail_blocking<T>(response: Response) -> Result<T> {
+        Err(Self::from_blocking(response)?)?
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Inner {
+    base_url: Box<str>,
+    timeout: Duration,
+    http_impl: reqwest::blocking::Client,
+    retry_policy: RetryPolicy,
+    topology_version: Arc<AtomicU64>,
+    legacy_fallback: bool,
+    store_original_keys: bool,
+    wire_format: WireFormat,
+    wire_fallback: Arc<AtomicBool>,
+    debug_replicas: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: Arc<Inner>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new("http://localhost:5000")
+    }
+}
+
+impl Client {
+    pub fn new(base_url: impl AsRef<str>) -> Self {
+        Self::with_timeout(base_url, DEFAULT_TIMEOUT)
+            .expect("bad default timeout")
+    }
+
+    pub fn with_timeout(
+        base_url: impl AsRef<str>,
+        timeout: Duration,
+    ) -> Result<Self> {
+        Ok(Self {
+            inner: Arc::new(Inner {
+                base_url: Box::from(base_url.as_ref()),
+                timeout,
+                http_impl: reqwest::blocking::Client::builder()
+                    .timeout(timeout)
+                    .build()?,
+                retry_policy: RetryPolicy::none(),
+                topology_version: Arc::default(),
+                legacy_fallback: false,
+                store_original_keys: false,
+                wire_format: WireFormat::Json,
+                wire_fallback: Arc::default(),
+                debug_replicas: false,
+            }),
+        })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.inner.base_url
+    }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        Arc::make_mut(&mut self.inner).retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.set_retry_policy(retry_policy);
+        self
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.inner.retry_policy
+    }
+
+    pub fn set_legacy_fallback(&mut self, enabled: bool) -> &mut Self {
+        Arc::make_mut(&mut self.inner).legacy_fallback = enabled;
+        self
+    }
+
+    pub fn with_legacy_fallback(mut self, enabled: bool) -> Self {
+        self.set_legacy_fallback(enabled);
+        self
+    }
+
+    pub fn set_store_original_keys(&mut self, enabled: bool) -> &mut Self {
+        Arc::make_mut(&mut self.inner).store_original_keys = enabled;
+        self
+    }
+
+    pub fn with_store_original_keys(mut self, enabled: bool) -> Self {
+        self.set_store_original_keys(enabled);
+        self
+    }
+
+    pub fn set_wire_format(&mut self, wire_format: WireFormat) -> &mut Self {
+        let inner = Arc::make_mut(&mut self.inner);
+        inner.wire_format = wire_format;
+        inner.wire_fallback = Arc::default();
+        self
+    }
+
+    pub fn with_wire_format(mut self, wire_format: WireFormat) -> Self {
+        self.set_wire_format(wire_format);
+        self
+    }
+
+    pub fn wire_format(&self) -> WireFormat {
+        if self.inner.wire_fallback.load(Ordering::Acquire) {
+            WireFormat::Json
+        } else {
+            self.inner.wire_format
+        }
+    }
+
+    pub fn set_debug_replicas(&mut self, enabled: bool) -> &mut Self {
+        Arc::make_mut(&mut self.inner).debug_replicas = enabled;
+        self
+    }
+
+    pub fn with_debug_replicas(mut self, enabled: bool) -> Self {
+        self.set_debug_replicas(enabled);
+        self
+    }
+
+    pub fn debug_replicas(&self) -> bool {
+        self.inner.debug_replicas
+    }
+
+    fn fall_back_to_json(&self) {
+        if !self.inner.wire_fallback.swap(true, Ordering::AcqRel) {
+            tracing::warn!(
+                base_url = self.base_url(),
+                format = %self.inner.wire_format,
+                "server rejected wire format, falling back to JSON",
+            );
+        }
+    }
+
+    pub fn topology_version(&self) -> Option<u64> {
+        loaded_topology_version(&self.inner.topology_version)
+    }
+
+    fn http_impl(&self) -> &reqwest::blocking::Client {
+        &self.inner.http_impl
+    }
+
+    fn execute<F>(&self, idempotency: Idempotency, build: F) -> Result<Response>
+    where
+        F: Fn(&reqwest::blocking::Client) -> RequestBuilder,
+    {
+        let mut attempts = self.retry_policy().start(idempotency);
+        loop {
+            let mut request = build(self.http_impl())
+                .headers(request_headers(self.debug_replicas()))
+                .build()?;
+            *request.timeout_mut() =
+                Some(attempts.attempt_timeout(self.inner.timeout));
+            let result = self.http_impl().execute(request);
+            if let Ok(response) = &result {
+                observe_topology_version(
+                    &self.inner.topology_version,
+                    response,
+                );
+            }
+            match attempts.complete(result) {
+                Attempt::Retry(backoff) => thread::sleep(backoff),
+                Attempt::Done(result) => return result,
+            }
+        }
+    }
+
+    pub fn run_id(&self) -> Result<RunId> {
+        let url = format!("{}/spalhad/v1/sync/runid", self.base_url());
+        let response =
+            self.execute(Idempotency::Idempotent, |http| http.get(&url))?;
+        if response.status() != StatusCode::OK {
+            ResponseError::bail_blocking(response)
+        } else {
+            let run_id_response: RunIdResponse = response.json()?;
+            Ok(run_id_response.run_id)
+        }
+    }
+
+    pub fn activate(&self, run_id: RunId) -> Result<ActivateResponse> {
+        let url = format!("{}/spalhad/v1/sync/activate", self.base_url());
+        let body = ActivateRequest { run_id };
+        let response = self.execute(Idempotency::NonIdempotent, |http| {
+            http.post(&url).json(&body)
+        })?;
+        if response.status() == StatusCode::OK {
+            let activate_response: ActivateResponse = response.json()?;
+            Ok(activate_response)
+        } else {
+            ResponseError::bail_blocking(response)
+        }
+    }
+
+    pub fn is_active(&self) -> Result<ActivateResponse> {
+        let url = format!("{}/spalhad/v1/sync/active", self.base_url());
+        let response =
+            self.execute(Idempotency::Idempotent, |http| http.get(&url))?;
+        if response.status() == StatusCode::OK {
+            let activate_response: IsActiveResponse = response.json()?;
+            Ok(activate_response)
+        } else {
+            ResponseError::bail_blocking(response)
+        }
+    }
+
+    pub fn get<K, V>(&self, key_data: K) -> Result<Option<V>>
+    where
+        K: AsRef<[u8]>,
+        V: DeserializeOwned,
+    {
+        Ok(self.get_with_replicas(key_data)?.0)
+    }
+
+    pub fn get_with_replicas<K, V>(
+        &self,
+        key_data: K,
+    ) -> Result<(Option<V>, Vec<ReplicaOutcome>)>
+    where
+        K: AsRef<[u8]>,
+        V: DeserializeOwned,
+    {
+        let key_data = key_data.as_ref();
+        let key = Key::from_bytes_key(key_data);
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        let found = self.get_at(url.as_str())?;
+        if found.0.is_some() || !self.inner.legacy_fallback {
+            return Ok(found);
+        }
+        let Some(legacy_key) = legacy_key(key_data) else { return Ok(found) };
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), legacy_key);
+        self.get_at(url.as_str())
+    }
+
+    pub fn put<K, V>(&self, key_data: K, value: V) -> Result<bool>
+    where
+        K: AsRef<[u8]>,
+        V: Serialize,
+    {
+        Ok(self.put_with_replicas(key_data, value)?.0)
+    }
+
+    pub fn put_with_replicas<K, V>(
+        &self,
+        key_data: K,
+        value: V,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)>
+    where
+        K: AsRef<[u8]>,
+        V: Serialize,
+    {
+        let key_data = key_data.as_ref();
+        let key = Key::from_bytes_key(key_data);
+        let origin = self
+            .inner
+            .store_original_keys
+            .then(|| key_origin(key_data))
+            .flatten();
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        self.put_at(url.as_str(), value, origin)
+    }
+
+    pub fn get_bytes<K>(&self, key_data: K) -> Result<Option<BytesValue>>
+    where
+        K: AsRef<[u8]>,
+    {
+        Ok(self.get_bytes_with_replicas(key_data)?.0)
+    }
+
+    pub fn get_bytes_with_replicas<K>(
+        &self,
+        key_data: K,
+    ) -> Result<(Option<BytesValue>, Vec<ReplicaOutcome>)>
+    where
+        K: AsRef<[u8]>,
+    {
+        let key_data = key_data.as_ref();
+        let key = Key::from_bytes_key(key_data);
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        let found = self.get_bytes_at(url.as_str())?;
+        if found.0.is_some() || !self.inner.legacy_fallback {
+            return Ok(found);
+        }
+        let Some(legacy_key) = legacy_key(key_data) else { return Ok(found) };
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), legacy_key);
+        self.get_bytes_at(url.as_str())
+    }
+
+    pub fn put_bytes<K>(
+        &self,
+        key_data: K,
+        value: impl Into<BytesValue>,
+    ) -> Result<bool>
+    where
+        K: AsRef<[u8]>,
+    {
+        Ok(self.put_bytes_with_replicas(key_data, value)?.0)
+    }
+
+    pub fn put_bytes_with_replicas<K>(
+        &self,
+        key_data: K,
+        value: impl Into<BytesValue>,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)>
+    where
+        K: AsRef<[u8]>,
+    {
+        let key_data = key_data.as_ref();
+        let key = Key::from_bytes_key(key_data);
+        let origin = self
+            .inner
+            .store_original_keys
+            .then(|| key_origin(key_data))
+            .flatten();
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        self.put_bytes_at(url.as_str(), value.into(), origin)
+    }
+
+    pub fn get_namespaced<V>(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+    ) -> Result<Option<V>>
+    where
+        V: DeserializeOwned,
+    {
+        Ok(self.get_namespaced_with_replicas(namespace, key_data)?.0)
+    }
+
+    pub fn get_namespaced_with_replicas<V>(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+    ) -> Result<(Option<V>, Vec<ReplicaOutcome>)>
+    where
+        V: DeserializeOwned,
+    {
+        self.get_at(namespaced_url(self.base_url(), namespace, key_data)?)
+    }
+
+    pub fn put_namespaced<V>(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+        value: V,
+    ) -> Result<bool>
+    where
+        V: Serialize,
+    {
+        Ok(self.put_namespaced_with_replicas(namespace, key_data, value)?.0)
+    }
+
+    pub fn put_namespaced_with_replicas<V>(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+        value: V,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)>
+    where
+        V: Serialize,
+    {
+        let url = namespaced_url(self.base_url(), namespace, key_data)?;
+        self.put_at(url, value, None)
+    }
+
+    pub fn get_namespaced_bytes(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+    ) -> Result<Option<BytesValue>> {
+        Ok(self.get_namespaced_bytes_with_replicas(namespace, key_data)?.0)
+    }
+
+    pub fn get_namespaced_bytes_with_replicas(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+    ) -> Result<(Option<BytesValue>, Vec<ReplicaOutcome>)> {
+        let url = namespaced_url(self.base_url(), namespace, key_data)?;
+        self.get_bytes_at(url)
+    }
+
+    pub fn put_namespaced_bytes(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+        value: impl Into<BytesValue>,
+    ) -> Result<bool> {
+        let put =
+            self.put_namespaced_bytes_with_replicas(namespace, key_data, value);
+        Ok(put?.0)
+    }
+
+    pub fn put_namespaced_bytes_with_replicas(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+        value: impl Into<BytesValue>,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)> {
+        let url = namespaced_url(self.base_url(), namespace, key_data)?;
+        self.put_bytes_at(url, value.into(), None)
+    }
+
+    pub fn migrate_key(&self, key_data: &str) -> Result<bool> {
+        let url = format!(
+            "{}/spalhad/v1/kv/{}",
+            self.base_url(),
+            Key::from_str_key(key_data),
+        );
+        if self.get_value_at(url.as_str())?.is_some() {
+            return Ok(false);
+        }
+        let legacy_url = format!(
+            "{}/spalhad/v1/kv/{}",
+            self.base_url(),
+            Key::legacy_str_key(key_data),
+        );
+        let legacy = self.get_value_at(legacy_url.as_str())?;
+        let Some(value) = legacy else { return Ok(false) };
+        match value {
+            Value::Json(value) => self.put_at(url.as_str(), value, None)?,
+            Value::Bytes(value) => {
+                self.put_bytes_at(url.as_str(), value, None)?
+            },
+        };
+        Ok(true)
+    }
+
+    pub fn get_raw<V>(&self, key: Key) -> Result<Option<V>>
+    where
+        V: DeserializeOwned,
+    {
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        Ok(self.get_at(url.as_str())?.0)
+    }
+
+    pub fn put_raw<V>(&self, key: Key, value: V) -> Result<bool>
+    where
+        V: Serialize,
+    {
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        Ok(self.put_at(url.as_str(), value, None)?.0)
+    }
+
+    pub fn get_raw_bytes(&self, key: Key) -> Result<Option<BytesValue>> {
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        Ok(self.get_bytes_at(url.as_str())?.0)
+    }
+
+    pub fn put_raw_bytes(
+        &self,
+        key: Key,
+        value: impl Into<BytesValue>,
+    ) -> Result<bool> {
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        Ok(self.put_bytes_at(url.as_str(), value.into(), None)?.0)
+    }
+
+    pub fn get_internal(&self, key: Key) -> Result<Option<Value>> {
+        let url = format!("{}/spalhad/v1/internal/kv/{}", self.base_url(), key);
+        let format = self.wire_format();
+        let response = self.execute(Idempotency::Idempotent, |http| {
+            http.get(&url).header(ACCEPT, format.content_type())
+        })?;
+        let (Some(response), _) = self.found(response)? else {
+            return Ok(None);
+        };
+        let format = response_wire_format(response.headers());
+        let get_response: GetResponse<Value> =
+            format.decode(&response.bytes()?)?;
+        Ok(Some(get_response.value))
+    }
+
+    pub fn put_internal(
+        &self,
+        key: Key,
+        value: Value,
+        origin: Option<KeyOrigin>,
+    ) -> Result<bool> {
+        let url = format!("{}/spalhad/v1/internal/kv/{}", self.base_url(), key);
+        let body = PutRequest { value, origin };
+        let format = self.wire_format();
+        let mut response = self.put_encoded(&url, format, &body)?;
+        if response.status() == StatusCode::UNSUPPORTED_MEDIA_TYPE
+            && format != WireFormat::Json
+        {
+            self.fall_back_to_json();
+            response = self.put_encoded(&url, WireFormat::Json, &body)?;
+        }
+        Ok(self.put_response(response)?.0)
+    }
+
+    pub fn delete_internal(&self, key: Key) -> Result<bool> {
+        let url = format!("{}/spalhad/v1/internal/kv/{}", self.base_url(), key);
+        let format = self.wire_format();
+        let response = self.execute(Idempotency::Idempotent, |http| {
+            http.delete(&url).header(ACCEPT, format.content_type())
+        })?;
+        if response.status() == StatusCode::OK {
+            let format = response_wire_format(response.headers());
+            let delete_response: DeleteResponse =
+                format.decode(&response.bytes()?)?;
+            Ok(delete_response.existed)
+        } else {
+            ResponseError::bail_blocking(response)
+        }
+    }
+
+    fn put_encoded<T>(
+        &self,
+        url: &str,
+        format: WireFormat,
+        body: &T,
+    ) -> Result<Response>
+    where
+        T: Serialize,
+    {
+        let body = format.encode(body)?;
+        self.execute(Idempotency::Idempotent, |http| {
+            http.post(url)
+                .header(CONTENT_TYPE, format.content_type())
+                .header(ACCEPT, format.content_type())
+                .body(body.clone())
+        })
+    }
+
+    fn fetch_at<U>(
+        &self,
+        url: U,
+    ) -> Result<(Option<Response>, Vec<ReplicaOutcome>)>
+    where
+        U: IntoUrl + Clone,
+    {
+        let response = self
+            .execute(Idempotency::Idempotent, |http| http.get(url.clone()))?;
+        self.found(response)
+    }
+
+    fn found(
+        &self,
+        response: Response,
+    ) -> Result<(Option<Response>, Vec<ReplicaOutcome>)> {
+        if response.status() == StatusCode::NOT_FOUND {
+            match ResponseError::from_blocking(response)? {
+                ResponseError::NotFound(not_found) => {
+                    Ok((None, not_found.replicas().to_vec()))
+                },
+                error => Err(error.into()),
+            }
+        } else if response.status() == StatusCode::OK {
+            let replicas = response_replicas(response.headers());
+            Ok((Some(response), replicas.unwrap_or_default()))
+        } else {
+            ResponseError::bail_blocking(response)
+        }
+    }
+
+    fn get_at<U, V>(&self, url: U) -> Result<(Option<V>, Vec<ReplicaOutcome>)>
+    where
+        U: IntoUrl + Clone,
+        V: DeserializeOwned,
+    {
+        let (Some(response), replicas) = self.fetch_at(url)? else {
+            return Ok((None, Vec::new()));
+        };
+        if let Some(content_type) = binary_content_type(response.headers()) {
+            bail!("value is binary ({content_type}), not JSON");
+        }
+        let get_response: GetResponse<V> = response.json()?;
+        Ok((Some(get_response.value), replicas))
+    }
+
+    fn get_bytes_at<U>(
+        &self,
+        url: U,
+    ) -> Result<(Option<BytesValue>, Vec<ReplicaOutcome>)>
+    where
+        U: IntoUrl + Clone,
+    {
+        let (Some(response), replicas) = self.fetch_at(url)? else {
+            return Ok((None, Vec::new()));
+        };
+        let Some(content_type) = binary_content_type(response.headers()) else {
+            bail!("value is JSON, not binary");
+        };
+        let content_type = content_type.to_owned();
+        let data = response.bytes()?;
+        Ok((Some(BytesValue::new(content_type, data)), replicas))
+    }
+
+    fn get_value_at<U>(&self, url: U) -> Result<Option<Value>>
+    where
+        U: IntoUrl + Clone,
+    {
+        let (Some(response), _) = self.fetch_at(url)? else { return Ok(None) };
+        let value = match binary_content_type(response.headers()) {
+            Some(content_type) => {
+                let content_type = content_type.to_owned();
+                let data = response.bytes()?;
+                Value::Bytes(BytesValue::new(content_type, data))
+            },
+            None => {
+                let get_response: GetResponse<serde_json::Value> =
+                    response.json()?;
+                Value::Json(get_response.value)
+            },
+        };
+        Ok(Some(value))
+    }
+
+    fn put_at<U, V>(
+        &self,
+        url: U,
+        value: V,
+        origin: Option<KeyOrigin>,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)>
+    where
+        U: IntoUrl + Clone,
+        V: Serialize,
+    {
+        let body = PutRequest { value, origin };
+        let response = self.execute(Idempotency::Idempotent, |http| {
+            http.post(url.clone()).json(&body)
+        })?;
+        self.put_response(response)
+    }
+
+    fn put_bytes_at<U>(
+        &self,
+        url: U,
+        value: BytesValue,
+        origin: Option<KeyOrigin>,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)>
+    where
+        U: IntoUrl + Clone,
+    {
+        let response = self.execute(Idempotency::Idempotent, |http| {
+            let request = http
+                .post(url.clone())
+                .header(CONTENT_TYPE, &value.content_type)
+                .body(value.data.clone());
+            match &origin {
+                Some(origin) => {
+                    request.header(KEY_ORIGIN_HEADER, origin.to_header_value())
+                },
+                None => request,
+            }
+        })?;
+        self.put_response(response)
+    }
+
+    fn put_response(
+        &self,
+        response: Response,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)> {
+        if response.status() == StatusCode::OK {
+            let replicas = response_replicas(response.headers());
+            let format = response_wire_format(response.headers());
+            let put_response: PutResponse =
+                format.decode(&response.bytes()?)?;
+            Ok((put_response.new, replicas.unwrap_or_default()))
+        } else {
+            ResponseError::bail_blocking(response)
+        }
+    }
+}
@@ -0,0 +1,702 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use reqwest::StatusCode;
+use serde::{Serialize, de::DeserializeOwned};
+use spalhad_spec::{
+    ErrorCode,
+    bucket::Bucket,
+    cluster::{PeerStatus, TopologyResponse},
+    kv::{BytesValue, Key, Namespace},
+};
+use tokio::time::Instant;
+
+use crate::{Client, DEFAULT_TIMEOUT, ResponseError, RetryPolicy, legacy_key};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Balancing {
+    #[default]
+    RoundRobin,
+    LeastLatency,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failover {
+    Always,
+    Unapplied,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeStats {
+    pub base_url: String,
+    pub latency: Option<Duration>,
+    pub available: bool,
+}
+
+#[derive(Debug)]
+struct Node {
+    client: Client,
+    latency: Option<Duration>,
+    down_until: Option<Instant>,
+    topology_version: Option<u64>,
+}
+
+impl Node {
+    fn new(client: Client) -> Self {
+        Self { client, latency: None, down_until: None, topology_version: None }
+    }
+
+    fn is_available(&self, now: Instant) -> bool {
+        self.down_until.is_none_or(|until| until <= now)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Route<'a> {
+    key: &'a Key,
+    namespace: Option<&'a Namespace>,
+}
+
+impl<'a> Route<'a> {
+    fn key(key: &'a Key) -> Option<Self> {
+        Some(Self { key, namespace: None })
+    }
+
+    fn namespaced(namespace: &'a Namespace, key: &'a Key) -> Option<Self> {
+        Some(Self { key, namespace: Some(namespace) })
+    }
+}
+
+#[derive(Debug)]
+struct Partitioning {
+    replication: usize,
+    leaving: Vec<bool>,
+    buckets: HashMap<Namespace, usize>,
+}
+
+impl Partitioning {
+    fn new(topology: &TopologyResponse, buckets: &[Bucket]) -> Self {
+        let leaving = topology
+            .nodes
+            .iter()
+            .map(|node| node.status == PeerStatus::Leaving)
+            .collect();
+        let buckets = buckets
+            .iter()
+            .map(|bucket| (bucket.name.clone(), bucket.config.replication))
+            .collect();
+        Self { replication: topology.replication, leaving, buckets }
+    }
+
+    fn is_replica(&self, route: Route<'_>, index: usize) -> bool {
+        let replication = route
+            .namespace
+            .and_then(|namespace| self.buckets.get(namespace))
+            .copied()
+            .unwrap_or(self.replication);
+        let nodes = self.leaving.len();
+        let first = route.key.partition(nodes);
+        let offset = (index + nodes - first) % nodes;
+        offset < replication && !self.leaving[index]
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    nodes: Vec<Node>,
+    next: usize,
+    partitioning: Option<Partitioning>,
+}
+
+#[derive(Debug, Clone)]
+struct Config {
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+    balancing: Balancing,
+    failure_cooldown: Duration,
+    replica_routing: bool,
+    legacy_fallback: bool,
+    store_original_keys: bool,
+}
+
+impl Config {
+    fn client(&self, base_url: &str) -> Result<Client> {
+        let client = Client::with_timeout(base_url, self.timeout)?
+            .with_retry_policy(self.retry_policy)
+            .with_store_original_keys(self.store_original_keys);
+        Ok(client)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClusterClient {
+    config: Config,
+    state: Arc<Mutex<State>>,
+}
+
+impl ClusterClient {
+    pub fn new<I>(seeds: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Self::with_timeout(seeds, DEFAULT_TIMEOUT).expect("bad default timeout")
+    }
+
+    pub fn with_timeout<I>(seeds: I, timeout: Duration) -> Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let config = Config {
+            timeout,
+            retry_policy: RetryPolicy::none(),
+            balancing: Balancing::default(),
+            failure_cooldown: Duration::from_secs(5),
+            replica_routing: false,
+            legacy_fallback: false,
+            store_original_keys: false,
+        };
+        let mut nodes = Vec::new();
+        for seed in seeds {
+            nodes.push(Node::new(config.client(seed.as_ref())?));
+        }
+        let state = State { nodes, next: 0, partitioning: None };
+        Ok(Self { config, state: Arc::new(Mutex::new(state)) })
+    }
+
+    pub async fn discover<I>(seeds: I) -> Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let client = Self::new(seeds);
+        client.refresh().await?;
+        Ok(client)
+    }
+
+    fn with_state<F, T>(&self, visitor: F) -> T
+    where
+        F: FnOnce(&mut State) -> T,
+    {
+        visitor(&mut self.state.lock().expect("poisoned lock"))
+    }
+
+    pub fn set_balancing(&mut self, balancing: Balancing) -> &mut Self {
+        self.config.balancing = balancing;
+        self
+    }
+
+    pub fn with_balancing(mut self, balancing: Balancing) -> Self {
+        self.set_balancing(balancing);
+        self
+    }
+
+    pub fn set_failure_cooldown(&mut self, cooldown: Duration) -> &mut Self {
+        self.config.failure_cooldown = cooldown;
+        self
+    }
+
+    pub fn with_failure_cooldown(mut self, cooldown: Duration) -> Self {
+        self.set_failure_cooldown(cooldown);
+        self
+    }
+
+    pub fn set_replica_routing(&mut self, enabled: bool) -> &mut Self {
+        self.config.replica_routing = enabled;
+        self
+    }
+
+    pub fn with_replica_routing(mut self, enabled: bool) -> Self {
+        self.set_replica_routing(enabled);
+        self
+    }
+
+    pub fn set_legacy_fallback(&mut self, enabled: bool) -> &mut Self {
+        self.config.legacy_fallback = enabled;
+        self
+    }
+
+    pub fn with_legacy_fallback(mut self, enabled: bool) -> Self {
+        self.set_legacy_fallback(enabled);
+        self
+    }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        self.config.retry_policy = retry_policy;
+        self.with_state(|state| {
+            for node in &mut state.nodes {
+                node.client.set_retry_policy(retry_policy);
+            }
+        });
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.set_retry_policy(retry_policy);
+        self
+    }
+
+    pub fn set_store_original_keys(&mut self, enabled: bool) -> &mut Self {
+        self.config.store_original_keys = enabled;
+        self.with_state(|state| {
+            for node in &mut state.nodes {
+                node.client.set_store_original_keys(enabled);
+            }
+        });
+        self
+    }
+
+    pub fn with_store_original_keys(mut self, enabled: bool) -> Self {
+        self.set_store_original_keys(enabled);
+        self
+    }
+
+    pub fn nodes(&self) -> Vec<NodeStats> {
+        let now = Instant::now();
+        self.with_state(|state| {
+            state
+                .nodes
+                .iter()
+                .map(|node| NodeStats {
+                    base_url: node.client.base_url().to_owned(),
+                    latency: node.latency,
+                    available: node.is_available(now),
+                })
+                .collect()
+        })
+    }
+
+    pub async fn refresh(&self) -> Result<()> {
+        let (source, topology, buckets) = self
+            .dispatch(None, Failover::Always, |client| async move {
+                let topology = client.topology().await?;
+                let buckets = fetch_buckets(&client).await;
+                Ok((client.base_url().to_owned(), topology, buckets))
+            })
+            .await?;
+        self.apply_topology(&source, &topology, &buckets)
+    }
+
+    fn apply_topology(
+        &self,
+        source: &str,
+        topology: &TopologyResponse,
+        buckets: &[Bucket],
+    ) -> Result<()> {
+        let mut clients = Vec::with_capacity(topology.nodes.len());
+        for node in &topology.nodes {
+            clients.push(self.config.client(&node.address)?);
+        }
+        self.with_state(|state| {
+            let mut known = std::mem::take(&mut state.nodes);
+            for client in clients {
+                let position = known.iter().position(|node| {
+                    node.client.base_url() == client.base_url()
+                });
+                let mut node = match position {
+                    Some(position) => known.swap_remove(position),
+                    None => Node::new(client),
+                };
+                node.topology_version = if node.client.base_url() == source {
+                    Some(topology.version)
+                } else {
+                    node.client.topology_version()
+                };
+                state.nodes.push(node);
+            }
+            state.next = 0;
+            state.partitioning = Some(Partitioning::new(topology, buckets));
+        });
+        tracing::debug!(
+            source,
+            version = topology.version,
+            nodes = topology.nodes.len(),
+            "refreshed topology",
+        );
+        Ok(())
+    }
+
+    async fn sync_topology(&self) {
+        let (stale, changed) = self.with_state(|state| {
+            let stale =
+                self.config.replica_routing && state.partitioning.is_none();
+            let mut changed = None;
+            for node in &mut state.nodes {
+                let seen = node.client.topology_version();
+                match (node.topology_version, seen) {
+                    (Some(known), Some(seen)) if known != seen => {
+                        changed.get_or_insert_with(|| node.client.clone());
+                    },
+                    (None, Some(_)) => node.topology_version = seen,
+                    _ => {},
+                }
+            }
+            (stale, changed)
+        });
+        let result = match changed {
+            Some(client) => match client.topology().await {
+                Ok(topology) => {
+                    let buckets = fetch_buckets(&client).await;
+                    self.apply_topology(client.base_url(), &topology, &buckets)
+                },
+                Err(_) => self.refresh().await,
+            },
+            None if stale => self.refresh().await,
+            None => Ok(()),
+        };
+        if let Err(error) = result {
+            tracing::warn!(
+                error = format!("{error:#}"),
+                "failed to refresh topology",
+            );
+        }
+    }
+
+    pub async fn get<K, V>(&self, key_data: K) -> Result<Option<V>>
+    where
+        K: AsRef<[u8]>,
+        V: DeserializeOwned,
+    {
+        let key_data = key_data.as_ref();
+        let value = self.get_raw(Key::from_bytes_key(key_data)).await?;
+        if value.is_some() || !self.config.legacy_fallback {
+            return Ok(value);
+        }
+        let Some(legacy_key) = legacy_key(key_data) else { return Ok(None) };
+        self.get_raw(legacy_key).await
+    }
+
+    pub async fn put<K, V>(&self, key_data: K, value: V) -> Result<bool>
+    where
+        K: AsRef<[u8]>,
+        V: Serialize,
+    {
+        let key_data = key_data.as_ref();
+        let key = Key::from_bytes_key(key_data);
+        let value = &value;
+        self.sync_topology().await;
+        self.dispatch(
+            Route::key(&key),
+            Failover::Unapplied,
+            |client| async move { client.put(key_data, value).await },
+        )
+        .await
+    }
+
+    pub async fn get_namespaced<V>(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+    ) -> Result<Option<V>>
+    where
+        V: DeserializeOwned,
+    {
+        let key = Key::from_namespaced_key(namespace, key_data);
+        self.sync_topology().await;
+        let route = Route::namespaced(namespace, &key);
+        self.dispatch(route, Failover::Always, |client| async move {
+            client.get_namespaced(namespace, key_data).await
+        })
+        .await
+    }
+
+    pub async fn put_namespaced<V>(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+        value: V,
+    ) -> Result<bool>
+    where
+        V: Serialize,
+    {
+        let key = Key::from_namespaced_key(namespace, key_data);
+        let value = &value;
+        self.sync_topology().await;
+        let route = Route::namespaced(namespace, &key);
+        self.dispatch(route, Failover::Unapplied, |client| async move {
+            client.put_namespaced(namespace, key_data, value).await
+        })
+        .await
+    }
+
+    pub async fn get_bytes<K>(&self, key_data: K) -> Result<Option<BytesValue>>
+    where
+        K: AsRef<[u8]>,
+    {
+        let key_data = key_data.as_ref();
+        let value = self.get_raw_bytes(Key::from_bytes_key(key_data)).await?;
+        if value.is_some() || !self.config.legacy_fallback {
+            return Ok(value);
+        }
+        let Some(legacy_key) = legacy_key(key_data) else { return Ok(None) };
+        self.get_raw_bytes(legacy_key).await
+    }
+
+    pub async fn put_bytes<K>(
+        &self,
+        key_data: K,
+        value: impl Into<BytesValue>,
+    ) -> Result<bool>
+    where
+        K: AsRef<[u8]>,
+    {
+        let key_data = key_data.as_ref();
+        let key = Key::from_bytes_key(key_data);
+        let value = &value.into();
+        self.sync_topology().await;
+        self.dispatch(Route::key(&key), Failover::Unapplied, |client| {
+            async move { client.put_bytes(key_data, value.clone()).await }
+        })
+        .await
+    }
+
+    pub async fn get_namespaced_bytes(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+    ) -> Result<Option<BytesValue>> {
+        let key = Key::from_namespaced_key(namespace, key_data);
+        self.sync_topology().await;
+        let route = Route::namespaced(namespace, &key);
+        self.dispatch(route, Failover::Always, |client| async move {
+            client.get_namespaced_bytes(namespace, key_data).await
+        })
+        .await
+    }
+
+    pub async fn put_namespaced_bytes(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+        value: impl Into<BytesValue>,
+    ) -> Result<bool> {
+        let key = Key::from_namespaced_key(namespace, key_data);
+        let value = &value.into();
+        self.sync_topology().await;
+        let route = Route::namespaced(namespace, &key);
+        self.dispatch(route, Failover::Unapplied, |client| async move {
+            client
+                .put_namespaced_bytes(namespace, key_data, value.clone())
+                .await
+        })
+        .await
+    }
+
+    pub async fn migrate_key(&self, key_data: &str) -> Result<bool> {
+        let key = Key::from_str_key(key_data);
+        self.sync_topology().await;
+        self.dispatch(
+            Route::key(&key),
+            Failover::Unapplied,
+            |client| async move { client.migrate_key(key_data).await },
+        )
+        .await
+    }
+
+    pub async fn get_raw<V>(&self, key: Key) -> Result<Option<V>>
+    where
+        V: DeserializeOwned,
+    {
+        self.sync_topology().await;
+        self.dispatch(Route::key(&key), Failover::Always, |client| {
+            let key = key.clone();
+            async move { client.get_raw(key).await }
+        })
+        .await
+    }
+
+    pub async fn put_raw<V>(&self, key: Key, value: V) -> Result<bool>
+    where
+        V: Serialize,
+    {
+        let value = &value;
+        self.sync_topology().await;
+        self.dispatch(Route::key(&key), Failover::Unapplied, |client| {
+            let key = key.clone();
+            async move { client.put_raw(key, value).await }
+        })
+        .await
+    }
+
+    pub async fn get_raw_bytes(&self, key: Key) -> Result<Option<BytesValue>> {
+        self.sync_topology().await;
+        self.dispatch(Route::key(&key), Failover::Always, |client| {
+            let key = key.clone();
+            async move { client.get_raw_bytes(key).await }
+        })
+        .await
+    }
+
+    pub async fn put_raw_bytes(
+        &self,
+        key: Key,
+        value: impl Into<BytesValue>,
+    ) -> Result<bool> {
+        let value = &value.into();
+        self.sync_topology().await;
+        self.dispatch(Route::key(&key), Failover::Unapplied, |client| {
+            let key = key.clone();
+            async move { client.put_raw_bytes(key, value.clone()).await }
+        })
+        .await
+    }
+
+    async fn dispatch<F, A, T>(
+        &self,
+        route: Option<Route<'_>>,
+        failover: Failover,
+        request: F,
+    ) -> Result<T>
+    where
+        F: Fn(Client) -> A,
+        A: Future<Output = Result<T>>,
+    {
+        let mut last_error = None;
+        for client in self.candidates(route) {
+            let started = Instant::now();
+            match request(client.clone()).await {
+                Ok(output) => {
+                    self.record_success(client.base_url(), started.elapsed());
+                    return Ok(output);
+                },
+                Err(error) => {
+                    if !is_node_failure(&client, &error, failover).await {
+                        return Err(error);
+                    }
+                    tracing::warn!(
+                        base_url = client.base_url(),
+                        error = format!("{error:#}"),
+                        "node failed, failing over",
+                    );
+                    self.record_failure(client.base_url());
+                    last_error = Some(error);
+                },
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("no cluster nodes available")))
+    }
+
+    fn candidates(&self, route: Option<Route<'_>>) -> Vec<Client> {
+        let now = Instant::now();
+        self.with_state(|state| {
+            let len = state.nodes.len();
+            let mut order: Vec<usize> = match self.config.balancing {
+                Balancing::RoundRobin => {
+                    let start = state.next;
+                    state.next = (start + 1) % len.max(1);
+                    (0 .. len).map(|offset| (start + offset) % len).collect()
+                },
+                Balancing::LeastLatency => {
+                    let mut order: Vec<usize> = (0 .. len).collect();
+                    order.sort_by_key(|&index| {
+                        state.nodes[index].latency.unwrap_or(Duration::ZERO)
+                    });
+                    order
+                },
+            };
+            let partitioning = state
+                .partitioning
+                .as_ref()
+                .filter(|_| self.config.replica_routing)
+                .filter(|partitioning| partitioning.leaving.len() == len);
+            order.sort_by_key(|&index| {
+                let is_replica = route.zip(partitioning).is_some_and(
+                    |(route, partitioning)| {
+                        partitioning.is_replica(route, index)
+                    },
+                );
+                (!state.nodes[index].is_available(now), !is_replica)
+            });
+            order
+                .into_iter()
+                .map(|index| state.nodes[index].client.clone())
+                .collect()
+        })
+    }
+
+    fn record_success(&self, base_url: &str, elapsed: Duration) {
+        self.with_node(base_url, |node| {
+            let latency = match node.latency {
+                Some(latency) => latency.mul_f64(0.8) + elapsed.mul_f64(0.2),
+                None => elapsed,
+            };
+            node.latency = Some(latency);
+            node.down_until = None;
+        });
+    }
+
+    fn record_failure(&self, base_url: &str) {
+        let until = Instant::now() + self.config.failure_cooldown;
+        self.with_node(base_url, |node| node.down_until = Some(until));
+    }
+
+    fn with_node<F>(&self, base_url: &str, visitor: F)
+    where
+        F: FnOnce(&mut Node),
+    {
+        self.with_state(|state| {
+            let node = state
+                .nodes
+                .iter_mut()
+                .find(|node| node.client.base_url() == base_url);
+            if let Some(node) = node {
+                visitor(node);
+            }
+        });
+    }
+}
+
+async fn is_node_failure(
+    client: &Client,
+    error: &anyhow::Error,
+    failover: Failover,
+) -> bool {
+    if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        return error.is_connect() || failover == Failover::Always;
+    }
+    let Some(error) = error.downcast_ref::<ResponseError>() else {
+        return false;
+    };
+    match error.code() {
+        ErrorCode::NotActive | ErrorCode::Unavailable => true,
+        ErrorCode::QuorumFailed | ErrorCode::Timeout | ErrorCode::Internal => {
+            failover == Failover::Always
+        },
+        ErrorCode::Unknown
+            if error.status_code() == StatusCode::BAD_REQUEST =>
+        {
+            let is_active = client.is_active().await;
+            is_active.is_ok_and(|response| !response.is_active)
+        },
+        ErrorCode::Unknown => {
+            error.status_code().is_server_error()
+                && failover == Failover::Always
+        },
+        ErrorCode::NotFound
+        | ErrorCode::BadRunId
+        | ErrorCode::InvalidKey
+        | ErrorCode::InvalidRequest
+        | ErrorCode::Forbidden
+        | ErrorCode::Conflict
+        | ErrorCode::UnsupportedMediaType => false,
+    }
+}
+
+async fn fetch_buckets(client: &Client) -> Vec<Bucket> {
+    match client.buckets().await {
+        Ok(response) => response.buckets,
+        Err(error) => {
+            tracing::debug!(
+                base_url = client.base_url(),
+                error = format!("{error:#}"),
+                "failed to fetch buckets",
+            );
+            Vec::new()
+        },
+    }
+}
@@ -1,49 +1,326 @@
-use std::{hash::Hash, sync::Arc, time::Duration};
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
 
-use anyhow::Result;
-use reqwest::StatusCode;
+use anyhow::{Result, anyhow, bail};
+#[cfg(feature = "otel")]
+use opentelemetry::global;
+#[cfg(feature = "otel")]
+use opentelemetry_http::HeaderInjector;
+use reqwest::{
+    IntoUrl,
+    StatusCode,
+    Url,
+    header::{ACCEPT, CONTENT_TYPE, HeaderMap, HeaderValue},
+};
 use serde::{Serialize, de::DeserializeOwned};
 use spalhad_spec::{
+    ErrorCode,
+    QuorumDetails,
+    admin::{BreakersResponse, ReplacePeerRequest, ReplacePeerResponse},
+    bucket::{
+        BucketsResponse,
+        PutBucketRequest,
+        PutBucketResponse,
+        SyncBucketRequest,
+        SyncBucketResponse,
+    },
     cluster::{
         ActivateRequest,
         ActivateResponse,
         IsActiveResponse,
+        PeerStatus,
+        PeerStatusRequest,
+        PeerStatusResponse,
         RunId,
         RunIdResponse,
+        TOPOLOGY_VERSION_HEADER,
+        TopologyResponse,
+    },
+    kv::{
+        BytesValue,
+        DeleteResponse,
+        GetResponse,
+        KEY_ORIGIN_HEADER,
+        Key,
+        KeyOrigin,
+        Namespace,
+        PutRequest,
+        PutResponse,
+        Value,
+        value,
     },
-    kv::{GetResponse, Key, PutRequest, PutResponse},
+    replica::{DEBUG_HEADER, DEBUG_REPLICAS, REPLICAS_HEADER, ReplicaOutcome},
+    wire::WireFormat,
 };
 use thiserror::Error;
+use tokio::time;
+#[cfg(feature = "otel")]
+use tracing::Span;
+#[cfg(feature = "otel")]
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+mod retry;
+mod cluster;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+pub use cluster::{Balancing, ClusterClient, NodeStats};
+pub use retry::{Attempts, Idempotency, RetryPolicy};
+
+use retry::{Attempt, HttpResponse};
 
-#[derive(Debug)]
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(90);
+
+#[derive(Debug, Clone)]
 struct Inner {
     base_url: Box<str>,
+    timeout: Duration,
     http_impl: reqwest::Client,
+    retry_policy: RetryPolicy,
+    topology_version: Arc<AtomicU64>,
+    legacy_fallback: bool,
+    store_original_keys: bool,
+    wire_format: WireFormat,
+    wire_fallback: Arc<AtomicBool>,
+    debug_replicas: bool,
 }
 
-#[derive(Debug, Clone, Error)]
-#[error(
-    "Request failed with status {} and body {}",
-    .status_code.as_u16(),
-    .text_body,
-)]
-pub struct ResponseError {
+#[derive(Debug, Clone)]
+pub struct ErrorResponse {
     pub status_code: StatusCode,
     pub text_body: String,
     pub json_body: Option<spalhad_spec::Error>,
 }
 
+impl ErrorResponse {
+    pub fn new(status_code: StatusCode, text_body: String) -> Self {
+        let json_body = serde_json::from_str(&text_body).ok();
+        Self { status_code, text_body, json_body }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match &self.json_body {
+            Some(body) if body.code != ErrorCode::Unknown => body.code,
+            Some(_) if self.status_code == StatusCode::NOT_FOUND => {
+                ErrorCode::NotFound
+            },
+            _ => ErrorCode::Unknown,
+        }
+    }
+
+    pub fn quorum(&self) -> Option<QuorumDetails> {
+        self.json_body.as_ref().and_then(|body| body.quorum)
+    }
+
+    pub fn replicas(&self) -> &[ReplicaOutcome] {
+        self.json_body.as_ref().map_or(&[], |body| &body.replicas)
+    }
+}
+
+impl fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Request failed with status {} ({}) and body {}",
+            self.status_code.as_u16(),
+            self.code(),
+            self.text_body,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum ResponseError {
+    #[error("{0}")]
+    NotFound(ErrorResponse),
+    #[error("{0}")]
+    NotActive(ErrorResponse),
+    #[error("{0}")]
+    QuorumFailed(ErrorResponse),
+    #[error("{0}")]
+    Timeout(ErrorResponse),
+    #[error("{0}")]
+    BadRunId(ErrorResponse),
+    #[error("{0}")]
+    InvalidKey(ErrorResponse),
+    #[error("{0}")]
+    InvalidRequest(ErrorResponse),
+    #[error("{0}")]
+    Conflict(ErrorResponse),
+    #[error("{0}")]
+    Unavailable(ErrorResponse),
+    #[error("{0}")]
+    Other(ErrorResponse),
+}
+
 impl ResponseError {
+    pub fn from_response(response: ErrorResponse) -> Self {
+        match response.code() {
+            ErrorCode::NotFound => Self::NotFound(response),
+            ErrorCode::NotActive => Self::NotActive(response),
+            ErrorCode::QuorumFailed => Self::QuorumFailed(response),
+            ErrorCode::Timeout => Self::Timeout(response),
+            ErrorCode::BadRunId => Self::BadRunId(response),
+            ErrorCode::InvalidKey => Self::InvalidKey(response),
+            ErrorCode::InvalidRequest => Self::InvalidRequest(response),
+            ErrorCode::Conflict => Self::Conflict(response),
+            ErrorCode::Unavailable => Self::Unavailable(response),
+            ErrorCode::Forbidden
+            | ErrorCode::UnsupportedMediaType
+            | ErrorCode::Internal
+            | ErrorCode::Unknown => Self::Other(response),
+        }
+    }
+
     async fn new(response: reqwest::Response) -> Result<Self> {
         let status_code = response.status();
         let text_body = response.text().await?;
-        let json_body = serde_json::from_str(&text_body).ok();
-        Ok(Self { status_code, text_body, json_body })
+        Ok(Self::from_response(ErrorResponse::new(status_code, text_body)))
     }
 
     async fn bail<T>(response: reqwest::Response) -> Result<T> {
         Err(Self::new(response).await?)?
     }
+
+    pub fn response(&self) -> &ErrorResponse {
+        match self {
+            Self::NotFound(response)
+            | Self::NotActive(response)
+            | Self::QuorumFailed(response)
+            | Self::Timeout(response)
+            | Self::BadRunId(response)
+            | Self::InvalidKey(response)
+            | Self::InvalidRequest(response)
+            | Self::Conflict(response)
+            | Self::Unavailable(response)
+            | Self::Other(response) => response,
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        self.response().status_code
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.response().code()
+    }
+
+    pub fn quorum(&self) -> Option<QuorumDetails> {
+        self.response().quorum()
+    }
+
+    pub fn replicas(&self) -> &[ReplicaOutcome] {
+        self.response().replicas()
+    }
+}
+
+pub fn is_timeout(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(error) = cause.downcast_ref::<reqwest::Error>() {
+            error.is_timeout()
+        } else {
+            matches!(cause.downcast_ref(), Some(ResponseError::Timeout(_)))
+        }
+    })
+}
+
+pub fn is_transport_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.downcast_ref::<reqwest::Error>().is_some_and(|error| {
+            error.is_connect()
+                || error.is_timeout()
+                || error.is_request()
+                || error.is_body()
+        })
+    })
+}
+
+fn legacy_key(key_data: &[u8]) -> Option<Key> {
+    str::from_utf8(key_data).ok().map(Key::legacy_str_key)
+}
+
+fn key_origin(key_data: &[u8]) -> Option<KeyOrigin> {
+    str::from_utf8(key_data).ok().map(KeyOrigin::new)
+}
+
+fn namespaced_url(
+    base_url: &str,
+    namespace: &Namespace,
+    key_data: &str,
+) -> Result<Url> {
+    let mut url = Url::parse(base_url)?;
+    url.path_segments_mut()
+        .map_err(|()| anyhow!("base URL {base_url} cannot have a path"))?
+        .pop_if_empty()
+        .extend(["spalhad", "v1", "ns", namespace.as_str(), "kv", key_data]);
+    Ok(url)
+}
+
+fn binary_content_type(headers: &HeaderMap) -> Option<&str> {
+    let content_type = headers.get(CONTENT_TYPE)?.to_str().unwrap_or_default();
+    (!value::is_json_content_type(content_type)).then_some(content_type)
+}
+
+fn response_wire_format(headers: &HeaderMap) -> WireFormat {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(WireFormat::from_content_type)
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "otel")]
+fn trace_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let context = Span::current().context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(&mut headers))
+    });
+    headers
+}
+
+#[cfg(not(feature = "otel"))]
+fn trace_headers() -> HeaderMap {
+    HeaderMap::new()
+}
+
+fn request_headers(debug_replicas: bool) -> HeaderMap {
+    let mut headers = trace_headers();
+    if debug_replicas {
+        headers.insert(DEBUG_HEADER, HeaderValue::from_static(DEBUG_REPLICAS));
+    }
+    headers
+}
+
+fn observe_topology_version<R>(topology_version: &AtomicU64, response: &R)
+where
+    R: HttpResponse,
+{
+    let version = response
+        .headers()
+        .get(TOPOLOGY_VERSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    if let Some(version) = version {
+        topology_version.store(version, Ordering::Release);
+    }
+}
+
+fn loaded_topology_version(topology_version: &AtomicU64) -> Option<u64> {
+    let version = topology_version.load(Ordering::Acquire);
+    (version != 0).then_some(version)
+}
+
+fn response_replicas(headers: &HeaderMap) -> Option<Vec<ReplicaOutcome>> {
+    let replicas = headers.get(REPLICAS_HEADER)?.to_str().ok()?;
+    serde_json::from_str(replicas).ok()
 }
 
 #[derive(Debug, Clone)]
@@ -59,7 +336,7 @@ impl Default for Client {
 
 impl Client {
     pub fn new(base_url: impl AsRef<str>) -> Self {
-        Self::with_timeout(base_url, Duration::from_secs(90))
+        Self::with_timeout(base_url, DEFAULT_TIMEOUT)
             .expect("bad default timeout")
     }
 
@@ -70,9 +347,17 @@ impl Client {
         Ok(Self {
             inner: Arc::new(Inner {
                 base_url: Box::from(base_url.as_ref()),
+                timeout,
                 http_impl: reqwest::Client::builder()
                     .timeout(timeout)
                     .build()?,
+                retry_policy: RetryPolicy::none(),
+                topology_version: Arc::default(),
+                legacy_fallback: false,
+                store_original_keys: false,
+                wire_format: WireFormat::Json,
+                wire_fallback: Arc::default(),
+                debug_replicas: false,
             }),
         })
     }
@@ -81,14 +366,126 @@ impl Client {
         &self.inner.base_url
     }
 
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+        Arc::make_mut(&mut self.inner).retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.set_retry_policy(retry_policy);
+        self
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.inner.retry_policy
+    }
+
+    pub fn set_legacy_fallback(&mut self, enabled: bool) -> &mut Self {
+        Arc::make_mut(&mut self.inner).legacy_fallback = enabled;
+        self
+    }
+
+    pub fn with_legacy_fallback(mut self, enabled: bool) -> Self {
+        self.set_legacy_fallback(enabled);
+        self
+    }
+
+    pub fn set_store_original_keys(&mut self, enabled: bool) -> &mut Self {
+        Arc::make_mut(&mut self.inner).store_original_keys = enabled;
+        self
+    }
+
+    pub fn with_store_original_keys(mut self, enabled: bool) -> Self {
+        self.set_store_original_keys(enabled);
+        self
+    }
+
+    pub fn set_wire_format(&mut self, wire_format: WireFormat) -> &mut Self {
+        let inner = Arc::make_mut(&mut self.inner);
+        inner.wire_format = wire_format;
+        inner.wire_fallback = Arc::default();
+        self
+    }
+
+    pub fn with_wire_format(mut self, wire_format: WireFormat) -> Self {
+        self.set_wire_format(wire_format);
+        self
+    }
+
+    pub fn wire_format(&self) -> WireFormat {
+        if self.inner.wire_fallback.load(Ordering::Acquire) {
+            WireFormat::Json
+        } else {
+            self.inner.wire_format
+        }
+    }
+
+    pub fn set_debug_replicas(&mut self, enabled: bool) -> &mut Self {
+        Arc::make_mut(&mut self.inner).debug_replicas = enabled;
+        self
+    }
+
+    pub fn with_debug_replicas(mut self, enabled: bool) -> Self {
+        self.set_debug_replicas(enabled);
+        self
+    }
+
+    pub fn debug_replicas(&self) -> bool {
+        self.inner.debug_replicas
+    }
+
+    fn fall_back_to_json(&self) {
+        if !self.inner.wire_fallback.swap(true, Ordering::AcqRel) {
+            tracing::warn!(
+                base_url = self.base_url(),
+                format = %self.inner.wire_format,
+                "server rejected wire format, falling back to JSON",
+            );
+        }
+    }
+
+    pub fn topology_version(&self) -> Option<u64> {
+        loaded_topology_version(&self.inner.topology_version)
+    }
+
     fn http_impl(&self) -> &reqwest::Client {
         &self.inner.http_impl
     }
 
+    async fn execute<F>(
+        &self,
+        idempotency: Idempotency,
+        build: F,
+    ) -> Result<reqwest::Response>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    {
+        let mut attempts = self.retry_policy().start(idempotency);
+        loop {
+            let mut request = build(self.http_impl())
+                .headers(request_headers(self.debug_replicas()))
+                .build()?;
+            *request.timeout_mut() =
+                Some(attempts.attempt_timeout(self.inner.timeout));
+            let result = self.http_impl().execute(request).await;
+            if let Ok(response) = &result {
+                observe_topology_version(
+                    &self.inner.topology_version,
+                    response,
+                );
+            }
+            match attempts.complete(result) {
+                Attempt::Retry(backoff) => time::sleep(backoff).await,
+                Attempt::Done(result) => return result,
+            }
+        }
+    }
+
     pub async fn run_id(&self) -> Result<RunId> {
         let url = format!("{}/spalhad/v1/sync/runid", self.base_url());
-        let request = self.http_impl().get(url).build()?;
-        let response = self.http_impl().execute(request).await?;
+        let response = self
+            .execute(Idempotency::Idempotent, |http| http.get(&url))
+            .await?;
         if response.status() != StatusCode::OK {
             ResponseError::bail(response).await
         } else {
@@ -100,8 +497,11 @@ impl Client {
     pub async fn activate(&self, run_id: RunId) -> Result<ActivateResponse> {
         let url = format!("{}/spalhad/v1/sync/activate", self.base_url());
         let body = ActivateRequest { run_id };
-        let request = self.http_impl().post(url).json(&body).build()?;
-        let response = self.http_impl().execute(request).await?;
+        let response = self
+            .execute(Idempotency::NonIdempotent, |http| {
+                http.post(&url).json(&body)
+            })
+            .await?;
         if response.status() == StatusCode::OK {
             let activate_response: ActivateResponse = response.json().await?;
             Ok(activate_response)
@@ -112,8 +512,9 @@ impl Client {
 
     pub async fn is_active(&self) -> Result<ActivateResponse> {
         let url = format!("{}/spalhad/v1/sync/active", self.base_url(),);
-        let request = self.http_impl().get(url).build()?;
-        let response = self.http_impl().execute(request).await?;
+        let response = self
+            .execute(Idempotency::Idempotent, |http| http.get(&url))
+            .await?;
         if response.status() == StatusCode::OK {
             let activate_response: IsActiveResponse = response.json().await?;
             Ok(activate_response)
@@ -122,20 +523,374 @@ impl Client {
         }
     }
 
+    pub async fn topology(&self) -> Result<TopologyResponse> {
+        let url = format!("{}/spalhad/v1/sync/topology", self.base_url());
+        let response = self
+            .execute(Idempotency::Idempotent, |http| http.get(&url))
+            .await?;
+        if response.status() == StatusCode::OK {
+            let topology_response: TopologyResponse = response.json().await?;
+            Ok(topology_response)
+        } else {
+            ResponseError::bail(response).await
+        }
+    }
+
+    pub async fn set_peer_status(
+        &self,
+        node_id: usize,
+        status: PeerStatus,
+    ) -> Result<PeerStatusResponse> {
+        let url = format!("{}/spalhad/v1/sync/peer", self.base_url());
+        let body = PeerStatusRequest { node_id, status };
+        let response = self
+            .execute(Idempotency::Idempotent, |http| {
+                http.post(&url).json(&body)
+            })
+            .await?;
+        if response.status() == StatusCode::OK {
+            let peer_status_response: PeerStatusResponse =
+                response.json().await?;
+            Ok(peer_status_response)
+        } else {
+            ResponseError::bail(response).await
+        }
+    }
+
+    pub async fn replace_peer(
+        &self,
+        node_id: usize,
+        address: impl Into<String>,
+    ) -> Result<ReplacePeerResponse> {
+        let address = address.into();
+        let body = ReplacePeerRequest { node_id, address, rpc_address: None };
+        self.send_replace_peer(&body).await
+    }
+
+    pub async fn replace_rpc_peer(
+        &self,
+        node_id: usize,
+        address: impl Into<String>,
+        rpc_address: impl Into<String>,
+    ) -> Result<ReplacePeerResponse> {
+        let body = ReplacePeerRequest {
+            node_id,
+            address: address.into(),
+            rpc_address: Some(rpc_address.into()),
+        };
+        self.send_replace_peer(&body).await
+    }
+
+    async fn send_replace_peer(
+        &self,
+        body: &ReplacePeerRequest,
+    ) -> Result<ReplacePeerResponse> {
+        let url = format!("{}/spalhad/v1/admin/peers", self.base_url());
+        let response = self
+            .execute(Idempotency::NonIdempotent, |http| {
+                http.put(&url).json(body)
+            })
+            .await?;
+        if response.status() == StatusCode::OK {
+            let replace_peer_response: ReplacePeerResponse =
+                response.json().await?;
+            Ok(replace_peer_response)
+        } else {
+            ResponseError::bail(response).await
+        }
+    }
+
+    pub async fn breakers(&self) -> Result<BreakersResponse> {
+        let url = format!("{}/spalhad/v1/admin/breakers", self.base_url());
+        let response = self
+            .execute(Idempotency::Idempotent, |http| http.get(&url))
+            .await?;
+        if response.status() == StatusCode::OK {
+            let breakers_response: BreakersResponse = response.json().await?;
+            Ok(breakers_response)
+        } else {
+            ResponseError::bail(response).await
+        }
+    }
+
+    pub async fn buckets(&self) -> Result<BucketsResponse> {
+        let url = format!("{}/spalhad/v1/admin/buckets", self.base_url());
+        let response = self
+            .execute(Idempotency::Idempotent, |http| http.get(&url))
+            .await?;
+        if response.status() == StatusCode::OK {
+            let buckets_response: BucketsResponse = response.json().await?;
+            Ok(buckets_response)
+        } else {
+            ResponseError::bail(response).await
+        }
+    }
+
+    pub async fn put_bucket(
+        &self,
+        name: &Namespace,
+        config: PutBucketRequest,
+    ) -> Result<PutBucketResponse> {
+        let url =
+            format!("{}/spalhad/v1/admin/buckets/{}", self.base_url(), name);
+        let response = self
+            .execute(Idempotency::NonIdempotent, |http| {
+                http.put(&url).json(&config)
+            })
+            .await?;
+        if response.status() == StatusCode::OK {
+            let put_bucket_response: PutBucketResponse =
+                response.json().await?;
+            Ok(put_bucket_response)
+        } else {
+            ResponseError::bail(response).await
+        }
+    }
+
+    pub async fn sync_bucket(
+        &self,
+        bucket: &SyncBucketRequest,
+    ) -> Result<SyncBucketResponse> {
+        let url = format!("{}/spalhad/v1/sync/buckets", self.base_url());
+        let response = self
+            .execute(Idempotency::Idempotent, |http| {
+                http.post(&url).json(bucket)
+            })
+            .await?;
+        if response.status() == StatusCode::OK {
+            let sync_bucket_response: SyncBucketResponse =
+                response.json().await?;
+            Ok(sync_bucket_response)
+        } else {
+            ResponseError::bail(response).await
+        }
+    }
+
     pub async fn get<K, V>(&self, key_data: K) -> Result<Option<V>>
     where
-        K: Hash + Eq,
+        K: AsRef<[u8]>,
         V: DeserializeOwned,
     {
-        self.get_raw(Key::hashing(key_data)).await
+        Ok(self.get_with_replicas(key_data).await?.0)
+    }
+
+    pub async fn get_with_replicas<K, V>(
+        &self,
+        key_data: K,
+    ) -> Result<(Option<V>, Vec<ReplicaOutcome>)>
+    where
+        K: AsRef<[u8]>,
+        V: DeserializeOwned,
+    {
+        let key_data = key_data.as_ref();
+        let key = Key::from_bytes_key(key_data);
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        let found = self.get_at(url.as_str()).await?;
+        if found.0.is_some() || !self.inner.legacy_fallback {
+            return Ok(found);
+        }
+        let Some(legacy_key) = legacy_key(key_data) else { return Ok(found) };
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), legacy_key);
+        self.get_at(url.as_str()).await
     }
 
     pub async fn put<K, V>(&self, key_data: K, value: V) -> Result<bool>
     where
-        K: Hash + Eq,
+        K: AsRef<[u8]>,
         V: Serialize,
     {
-        self.put_raw(Key::hashing(key_data), value).await
+        Ok(self.put_with_replicas(key_data, value).await?.0)
+    }
+
+    pub async fn put_with_replicas<K, V>(
+        &self,
+        key_data: K,
+        value: V,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)>
+    where
+        K: AsRef<[u8]>,
+        V: Serialize,
+    {
+        let key_data = key_data.as_ref();
+        let key = Key::from_bytes_key(key_data);
+        let origin = self
+            .inner
+            .store_original_keys
+            .then(|| key_origin(key_data))
+            .flatten();
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        self.put_at(url.as_str(), value, origin).await
+    }
+
+    pub async fn get_bytes<K>(&self, key_data: K) -> Result<Option<BytesValue>>
+    where
+        K: AsRef<[u8]>,
+    {
+        Ok(self.get_bytes_with_replicas(key_data).await?.0)
+    }
+
+    pub async fn get_bytes_with_replicas<K>(
+        &self,
+        key_data: K,
+    ) -> Result<(Option<BytesValue>, Vec<ReplicaOutcome>)>
+    where
+        K: AsRef<[u8]>,
+    {
+        let key_data = key_data.as_ref();
+        let key = Key::from_bytes_key(key_data);
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        let found = self.get_bytes_at(url.as_str()).await?;
+        if found.0.is_some() || !self.inner.legacy_fallback {
+            return Ok(found);
+        }
+        let Some(legacy_key) = legacy_key(key_data) else { return Ok(found) };
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), legacy_key);
+        self.get_bytes_at(url.as_str()).await
+    }
+
+    pub async fn put_bytes<K>(
+        &self,
+        key_data: K,
+        value: impl Into<BytesValue>,
+    ) -> Result<bool>
+    where
+        K: AsRef<[u8]>,
+    {
+        Ok(self.put_bytes_with_replicas(key_data, value).await?.0)
+    }
+
+    pub async fn put_bytes_with_replicas<K>(
+        &self,
+        key_data: K,
+        value: impl Into<BytesValue>,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)>
+    where
+        K: AsRef<[u8]>,
+    {
+        let key_data = key_data.as_ref();
+        let key = Key::from_bytes_key(key_data);
+        let origin = self
+            .inner
+            .store_original_keys
+            .then(|| key_origin(key_data))
+            .flatten();
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        self.put_bytes_at(url.as_str(), value.into(), origin).await
+    }
+
+    pub async fn get_namespaced<V>(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+    ) -> Result<Option<V>>
+    where
+        V: DeserializeOwned,
+    {
+        Ok(self.get_namespaced_with_replicas(namespace, key_data).await?.0)
+    }
+
+    pub async fn get_namespaced_with_replicas<V>(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+    ) -> Result<(Option<V>, Vec<ReplicaOutcome>)>
+    where
+        V: DeserializeOwned,
+    {
+        let url = namespaced_url(self.base_url(), namespace, key_data)?;
+        self.get_at(url).await
+    }
+
+    pub async fn put_namespaced<V>(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+        value: V,
+    ) -> Result<bool>
+    where
+        V: Serialize,
+    {
+        let put = self.put_namespaced_with_replicas(namespace, key_data, value);
+        Ok(put.await?.0)
+    }
+
+    pub async fn put_namespaced_with_replicas<V>(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+        value: V,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)>
+    where
+        V: Serialize,
+    {
+        let url = namespaced_url(self.base_url(), namespace, key_data)?;
+        self.put_at(url, value, None).await
+    }
+
+    pub async fn get_namespaced_bytes(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+    ) -> Result<Option<BytesValue>> {
+        let get = self.get_namespaced_bytes_with_replicas(namespace, key_data);
+        Ok(get.await?.0)
+    }
+
+    pub async fn get_namespaced_bytes_with_replicas(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+    ) -> Result<(Option<BytesValue>, Vec<ReplicaOutcome>)> {
+        let url = namespaced_url(self.base_url(), namespace, key_data)?;
+        self.get_bytes_at(url).await
+    }
+
+    pub async fn put_namespaced_bytes(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+        value: impl Into<BytesValue>,
+    ) -> Result<bool> {
+        let put =
+            self.put_namespaced_bytes_with_replicas(namespace, key_data, value);
+        Ok(put.await?.0)
+    }
+
+    pub async fn put_namespaced_bytes_with_replicas(
+        &self,
+        namespace: &Namespace,
+        key_data: &str,
+        value: impl Into<BytesValue>,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)> {
+        let url = namespaced_url(self.base_url(), namespace, key_data)?;
+        self.put_bytes_at(url, value.into(), None).await
+    }
+
+    pub async fn migrate_key(&self, key_data: &str) -> Result<bool> {
+        let url = format!(
+            "{}/spalhad/v1/kv/{}",
+            self.base_url(),
+            Key::from_str_key(key_data),
+        );
+        if self.get_value_at(url.as_str()).await?.is_some() {
+            return Ok(false);
+        }
+        let legacy_url = format!(
+            "{}/spalhad/v1/kv/{}",
+            self.base_url(),
+            Key::legacy_str_key(key_data),
+        );
+        let legacy = self.get_value_at(legacy_url.as_str()).await?;
+        let Some(value) = legacy else { return Ok(false) };
+        match value {
+            Value::Json(value) => {
+                self.put_at(url.as_str(), value, None).await?
+            },
+            Value::Bytes(value) => {
+                self.put_bytes_at(url.as_str(), value, None).await?
+            },
+        };
+        Ok(true)
     }
 
     pub async fn get_raw<V>(&self, key: Key) -> Result<Option<V>>
@@ -143,17 +898,7 @@ impl Client {
         V: DeserializeOwned,
     {
         let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
-        let request = self.http_impl().get(url).build()?;
-        let response = self.http_impl().execute(request).await?;
-        if response.status() == StatusCode::NOT_FOUND {
-            let error = ResponseError::new(response).await?;
-            if error.json_body.is_some() { Ok(None) } else { Err(error.into()) }
-        } else if response.status() == StatusCode::OK {
-            let get_response: GetResponse<V> = response.json().await?;
-            Ok(Some(get_response.value))
-        } else {
-            ResponseError::bail(response).await
-        }
+        Ok(self.get_at(url.as_str()).await?.0)
     }
 
     pub async fn put_raw<V>(&self, key: Key, value: V) -> Result<bool>
@@ -161,46 +906,240 @@ impl Client {
         V: Serialize,
     {
         let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
-        let body = PutRequest { value };
-        let request = self.http_impl().post(url).json(&body).build()?;
-        let response = self.http_impl().execute(request).await?;
+        Ok(self.put_at(url.as_str(), value, None).await?.0)
+    }
+
+    pub async fn get_raw_bytes(&self, key: Key) -> Result<Option<BytesValue>> {
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        Ok(self.get_bytes_at(url.as_str()).await?.0)
+    }
+
+    pub async fn put_raw_bytes(
+        &self,
+        key: Key,
+        value: impl Into<BytesValue>,
+    ) -> Result<bool> {
+        let url = format!("{}/spalhad/v1/kv/{}", self.base_url(), key);
+        Ok(self.put_bytes_at(url.as_str(), value.into(), None).await?.0)
+    }
+
+    pub async fn get_internal(&self, key: Key) -> Result<Option<Value>> {
+        let url = format!("{}/spalhad/v1/internal/kv/{}", self.base_url(), key);
+        let format = self.wire_format();
+        let response = self
+            .execute(Idempotency::Idempotent, |http| {
+                http.get(&url).header(ACCEPT, format.content_type())
+            })
+            .await?;
+        let (Some(response), _) = self.found(response).await? else {
+            return Ok(None);
+        };
+        let format = response_wire_format(response.headers());
+        let get_response: GetResponse<Value> =
+            format.decode(&response.bytes().await?)?;
+        Ok(Some(get_response.value))
+    }
+
+    pub async fn put_internal(
+        &self,
+        key: Key,
+        value: Value,
+        origin: Option<KeyOrigin>,
+    ) -> Result<bool> {
+        let url = format!("{}/spalhad/v1/internal/kv/{}", self.base_url(), key);
+        let body = PutRequest { value, origin };
+        let format = self.wire_format();
+        let mut response = self.put_encoded(&url, format, &body).await?;
+        if response.status() == StatusCode::UNSUPPORTED_MEDIA_TYPE
+            && format != WireFormat::Json
+        {
+            self.fall_back_to_json();
+            response = self.put_encoded(&url, WireFormat::Json, &body).await?;
+        }
+        Ok(self.put_response(response).await?.0)
+    }
+
+    pub async fn delete_internal(&self, key: Key) -> Result<bool> {
+        let url = format!("{}/spalhad/v1/internal/kv/{}", self.base_url(), key);
+        let format = self.wire_format();
+        let response = self
+            .execute(Idempotency::Idempotent, |http| {
+                http.delete(&url).header(ACCEPT, format.content_type())
+            })
+            .await?;
         if response.status() == StatusCode::OK {
-            let put_response: PutResponse = response.json().await?;
-            Ok(put_response.new)
+            let format = response_wire_format(response.headers());
+            let delete_response: DeleteResponse =
+                format.decode(&response.bytes().await?)?;
+            Ok(delete_response.existed)
         } else {
             ResponseError::bail(response).await
         }
     }
 
-    pub async fn get_internal<V>(&self, key: Key) -> Result<Option<V>>
+    async fn put_encoded<T>(
+        &self,
+        url: &str,
+        format: WireFormat,
+        body: &T,
+    ) -> Result<reqwest::Response>
     where
-        V: DeserializeOwned,
+        T: Serialize,
     {
-        let url = format!("{}/spalhad/v1/internal/kv/{}", self.base_url(), key);
-        let request = self.http_impl().get(url).build()?;
-        let response = self.http_impl().execute(request).await?;
+        let body = format.encode(body)?;
+        self.execute(Idempotency::Idempotent, |http| {
+            http.post(url)
+                .header(CONTENT_TYPE, format.content_type())
+                .header(ACCEPT, format.content_type())
+                .body(body.clone())
+        })
+        .await
+    }
+
+    async fn fetch_at<U>(
+        &self,
+        url: U,
+    ) -> Result<(Option<reqwest::Response>, Vec<ReplicaOutcome>)>
+    where
+        U: IntoUrl + Clone,
+    {
+        let response = self
+            .execute(Idempotency::Idempotent, |http| http.get(url.clone()))
+            .await?;
+        self.found(response).await
+    }
+
+    async fn found(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<(Option<reqwest::Response>, Vec<ReplicaOutcome>)> {
         if response.status() == StatusCode::NOT_FOUND {
-            let error = ResponseError::new(response).await?;
-            if error.json_body.is_some() { Ok(None) } else { Err(error.into()) }
+            match ResponseError::new(response).await? {
+                ResponseError::NotFound(not_found) => {
+                    Ok((None, not_found.replicas().to_vec()))
+                },
+                error => Err(error.into()),
+            }
         } else if response.status() == StatusCode::OK {
-            let get_response: GetResponse<V> = response.json().await?;
-            Ok(Some(get_response.value))
+            let replicas = response_replicas(response.headers());
+            Ok((Some(response), replicas.unwrap_or_default()))
         } else {
             ResponseError::bail(response).await
         }
     }
 
-    pub async fn put_internal<V>(&self, key: Key, value: V) -> Result<bool>
+    async fn get_at<U, V>(
+        &self,
+        url: U,
+    ) -> Result<(Option<V>, Vec<ReplicaOutcome>)>
+    where
+        U: IntoUrl + Clone,
+        V: DeserializeOwned,
+    {
+        let (Some(response), replicas) = self.fetch_at(url).await? else {
+            return Ok((None, Vec::new()));
+        };
+        if let Some(content_type) = binary_content_type(response.headers()) {
+            bail!("value is binary ({content_type}), not JSON");
+        }
+        let get_response: GetResponse<V> = response.json().await?;
+        Ok((Some(get_response.value), replicas))
+    }
+
+    async fn get_bytes_at<U>(
+        &self,
+        url: U,
+    ) -> Result<(Option<BytesValue>, Vec<ReplicaOutcome>)>
     where
+        U: IntoUrl + Clone,
+    {
+        let (Some(response), replicas) = self.fetch_at(url).await? else {
+            return Ok((None, Vec::new()));
+        };
+        let Some(content_type) = binary_content_type(response.headers()) else {
+            bail!("value is JSON, not binary");
+        };
+        let content_type = content_type.to_owned();
+        let data = response.bytes().await?;
+        Ok((Some(BytesValue::new(content_type, data)), replicas))
+    }
+
+    async fn get_value_at<U>(&self, url: U) -> Result<Option<Value>>
+    where
+        U: IntoUrl + Clone,
+    {
+        let (Some(response), _) = self.fetch_at(url).await? else {
+            return Ok(None);
+        };
+        let value = match binary_content_type(response.headers()) {
+            Some(content_type) => {
+                let content_type = content_type.to_owned();
+                let data = response.bytes().await?;
+                Value::Bytes(BytesValue::new(content_type, data))
+            },
+            None => {
+                let get_response: GetResponse<serde_json::Value> =
+                    response.json().await?;
+                Value::Json(get_response.value)
+            },
+        };
+        Ok(Some(value))
+    }
+
+    async fn put_at<U, V>(
+        &self,
+        url: U,
+        value: V,
+        origin: Option<KeyOrigin>,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)>
+    where
+        U: IntoUrl + Clone,
         V: Serialize,
     {
-        let url = format!("{}/spalhad/v1/internal/kv/{}", self.base_url(), key);
-        let body = PutRequest { value };
-        let request = self.http_impl().post(url).json(&body).build()?;
-        let response = self.http_impl().execute(request).await?;
+        let body = PutRequest { value, origin };
+        let response = self
+            .execute(Idempotency::Idempotent, |http| {
+                http.post(url.clone()).json(&body)
+            })
+            .await?;
+        self.put_response(response).await
+    }
+
+    async fn put_bytes_at<U>(
+        &self,
+        url: U,
+        value: BytesValue,
+        origin: Option<KeyOrigin>,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)>
+    where
+        U: IntoUrl + Clone,
+    {
+        let response = self
+            .execute(Idempotency::Idempotent, |http| {
+                let request = http
+                    .post(url.clone())
+                    .header(CONTENT_TYPE, &value.content_type)
+                    .body(value.data.clone());
+                match &origin {
+                    Some(origin) => request
+                        .header(KEY_ORIGIN_HEADER, origin.to_header_value()),
+                    None => request,
+                }
+            })
+            .await?;
+        self.put_response(response).await
+    }
+
+    async fn put_response(
+        &self,
+        response: reqwest::Response,
+    ) -> Result<(bool, Vec<ReplicaOutcome>)> {
         if response.status() == StatusCode::OK {
-            let put_response: PutResponse = response.json().await?;
-            Ok(put_response.new)
+            let replicas = response_replicas(response.headers());
+            let format = response_wire_format(response.headers());
+            let put_response: PutResponse =
+                format.decode(&response.bytes().await?)?;
+            Ok((put_response.new, replicas.unwrap_or_default()))
         } else {
             ResponseError::bail(response).await
         }
@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use rand::Rng;
+use reqwest::{StatusCode, header::HeaderMap};
+use tokio::time::Instant;
+
+pub(crate) trait HttpResponse {
+    fn status(&self) -> StatusCode;
+
+    fn headers(&self) -> &HeaderMap;
+}
+
+impl HttpResponse for reqwest::Response {
+    fn status(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        self.headers()
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl HttpResponse for reqwest::blocking::Response {
+    fn status(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        self.headers()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Attempt<R> {
+    Retry(Duration),
+    Done(Result<R>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Idempotency {
+    Idempotent,
+    NonIdempotent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    max_retries: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    total_timeout: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+            multiplier: 2.0,
+            jitter: 0.5,
+            total_timeout: Some(Duration::from_secs(10)),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn none() -> Self {
+        Self { max_retries: 0, ..Self::default() }
+    }
+
+    pub fn set_max_retries(&mut self, max_retries: usize) -> &mut Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.set_max_retries(max_retries);
+        self
+    }
+
+    pub fn set_backoff(
+        &mut self,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> &mut Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff.max(initial_backoff);
+        self
+    }
+
+    pub fn with_backoff(
+        mut self,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        self.set_backoff(initial_backoff, max_backoff);
+        self
+    }
+
+    pub fn set_multiplier(&mut self, multiplier: f64) -> &mut Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.set_multiplier(multiplier);
+        self
+    }
+
+    pub fn set_jitter(&mut self, jitter: f64) -> &mut Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.set_jitter(jitter);
+        self
+    }
+
+    pub fn set_total_timeout(
+        &mut self,
+        total_timeout: Option<Duration>,
+    ) -> &mut Self {
+        self.total_timeout = total_timeout;
+        self
+    }
+
+    pub fn with_total_timeout(
+        mut self,
+        total_timeout: Option<Duration>,
+    ) -> Self {
+        self.set_total_timeout(total_timeout);
+        self
+    }
+
+    pub fn max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    pub fn total_timeout(&self) -> Option<Duration> {
+        self.total_timeout
+    }
+
+    pub fn backoff(&self, retry: usize) -> Duration {
+        let exponent = i32::try_from(retry).unwrap_or(i32::MAX);
+        let scale = self.multiplier.powi(exponent);
+        let backoff = self
+            .initial_backoff
+            .mul_f64(scale.min(u32::MAX.into()))
+            .min(self.max_backoff);
+        let jitter = rand::rng().random_range(0.0 ..= self.jitter);
+        backoff.mul_f64(1.0 - jitter)
+    }
+
+    pub fn start(&self, idempotency: Idempotency) -> Attempts {
+        let max_retries = match idempotency {
+            Idempotency::Idempotent => self.max_retries,
+            Idempotency::NonIdempotent => 0,
+        };
+        Attempts {
+            policy: *self,
+            max_retries,
+            retries: 0,
+            started: Instant::now(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Attempts {
+    policy: RetryPolicy,
+    max_retries: usize,
+    retries: usize,
+    started: Instant,
+}
+
+impl Attempts {
+    pub fn next_backoff(&mut self) -> Option<Duration> {
+        if self.retries >= self.max_retries {
+            return None;
+        }
+        let backoff = self.policy.backoff(self.retries);
+        if let Some(total_timeout) = self.policy.total_timeout
+            && self.started.elapsed() + backoff >= total_timeout
+        {
+            return None;
+        }
+        self.retries += 1;
+        Some(backoff)
+    }
+
+    pub fn retries(&self) -> usize {
+        self.retries
+    }
+
+    pub(crate) fn complete<R>(
+        &mut self,
+        result: reqwest::Result<R>,
+    ) -> Attempt<R>
+    where
+        R: HttpResponse,
+    {
+        let retryable = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(error) => error.is_connect(),
+        };
+        if retryable && let Some(backoff) = self.next_backoff() {
+            tracing::debug!(retry = self.retries, ?backoff, "retrying request");
+            return Attempt::Retry(backoff);
+        }
+        Attempt::Done(result.map_err(Into::into))
+    }
+
+    pub fn attempt_timeout(&self, timeout: Duration) -> Duration {
+        match self.policy.total_timeout {
+            Some(total_timeout) => timeout
+                .min(total_timeout.saturating_sub(self.started.elapsed())),
+            None => timeout,
+        }
+    }
+}
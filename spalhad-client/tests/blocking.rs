@@ -0,0 +1,140 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        Arc,
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use serde_json::json;
+use spalhad_client::{RetryPolicy, blocking::Client};
+use spalhad_spec::cluster::TOPOLOGY_VERSION_HEADER;
+
+#[derive(Debug, Default)]
+struct FakeNode {
+    values: Mutex<HashMap<String, serde_json::Value>>,
+    unavailable_once: AtomicBool,
+}
+
+impl FakeNode {
+    fn spawn(self) -> String {
+        let node = Arc::new(self);
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let address = listener.local_addr().expect("local address");
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = stream.expect("accept");
+                let node = node.clone();
+                thread::spawn(move || node.serve(stream));
+            }
+        });
+        format!("http://{address}")
+    }
+
+    fn serve(&self, stream: TcpStream) {
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).expect("request line");
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).expect("header");
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().expect("content length");
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).expect("body");
+
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default();
+        let path = parts.next().unwrap_or_default();
+        let (status, body) = self.respond(method, path, &body);
+        let head = [
+            format!("HTTP/1.1 {status} Fake"),
+            "content-type: application/json".to_owned(),
+            format!("content-length: {}", body.len()),
+            format!("{TOPOLOGY_VERSION_HEADER}: 7"),
+            "connection: close".to_owned(),
+        ];
+        let response = format!("{}\r\n\r\n{body}", head.join("\r\n"));
+        let mut stream = reader.into_inner();
+        stream.write_all(response.as_bytes()).expect("response");
+    }
+
+    fn respond(&self, method: &str, path: &str, body: &[u8]) -> (u16, String) {
+        if self.unavailable_once.swap(false, Ordering::AcqRel) {
+            let body = json!({"code": "unavailable", "trace": ["draining"]});
+            return (503, body.to_string());
+        }
+        let mut values = self.values.lock().expect("poisoned lock");
+        match (method, path) {
+            ("GET", "/spalhad/v1/sync/active") => {
+                (200, json!({"is_active": true}).to_string())
+            },
+            ("GET", path) => match values.get(path) {
+                Some(value) => (200, json!({"value": value}).to_string()),
+                None => {
+                    let body = json!({"code": "not_found", "trace": []});
+                    (404, body.to_string())
+                },
+            },
+            ("POST", path) => {
+                let request: serde_json::Value =
+                    serde_json::from_slice(body).expect("put body");
+                let old =
+                    values.insert(path.to_owned(), request["value"].clone());
+                (200, json!({"new": old.is_none()}).to_string())
+            },
+            _ => (405, json!({"trace": []}).to_string()),
+        }
+    }
+}
+
+#[test]
+fn blocking_client_round_trips() {
+    let base_url = FakeNode::default().spawn();
+    let client = Client::new(base_url);
+
+    assert_eq!(client.topology_version(), None);
+    assert!(client.is_active().expect("is active").is_active);
+    assert_eq!(client.topology_version(), Some(7));
+
+    let missing: Option<u32> = client.get("answer").expect("get missing");
+    assert_eq!(missing, None);
+    assert!(client.put("answer", 41).expect("first put"));
+    assert!(!client.put("answer", 42).expect("second put"));
+    let found: Option<u32> = client.get("answer").expect("get");
+    assert_eq!(found, Some(42));
+}
+
+#[test]
+fn blocking_client_retries_server_errors() {
+    let node = FakeNode::default();
+    node.unavailable_once.store(true, Ordering::Release);
+    let base_url = node.spawn();
+
+    let client = Client::new(&base_url);
+    let error = client.is_active().expect_err("no retries by default");
+    assert!(error.to_string().contains("unavailable"), "{error}");
+
+    let node = FakeNode::default();
+    node.unavailable_once.store(true, Ordering::Release);
+    let base_url = node.spawn();
+    let retry_policy = RetryPolicy::new()
+        .with_max_retries(1)
+        .with_backoff(Duration::from_millis(1), Duration::from_millis(1));
+    let client = Client::new(base_url).with_retry_policy(retry_policy);
+    assert!(client.is_active().expect("retried").is_active);
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::json;
+use spalhad_client::{ErrorResponse, ResponseError};
+use spalhad_spec::{
+    ErrorCode,
+    QuorumDetails,
+    replica::{ReplicaOutcome, ReplicaResult},
+};
+
+fn response_error(status: u16, body: &str) -> ResponseError {
+    let status = StatusCode::from_u16(status).expect("valid status code");
+    ResponseError::from_response(ErrorResponse::new(status, body.to_owned()))
+}
+
+fn coded(status: u16, code: &str) -> ResponseError {
+    response_error(status, &json!({"code": code, "trace": []}).to_string())
+}
+
+fn variant(error: &ResponseError) -> &'static str {
+    match error {
+        ResponseError::NotFound(_) => "NotFound",
+        ResponseError::NotActive(_) => "NotActive",
+        ResponseError::QuorumFailed(_) => "QuorumFailed",
+        ResponseError::Timeout(_) => "Timeout",
+        ResponseError::BadRunId(_) => "BadRunId",
+        ResponseError::InvalidKey(_) => "InvalidKey",
+        ResponseError::InvalidRequest(_) => "InvalidRequest",
+        ResponseError::Conflict(_) => "Conflict",
+        ResponseError::Unavailable(_) => "Unavailable",
+        ResponseError::Other(_) => "Other",
+    }
+}
+
+#[test]
+fn error_codes_pick_the_matching_variant() {
+    let cases = [
+        (404, "not_found", "NotFound"),
+        (503, "not_active", "NotActive"),
+        (500, "quorum_failed", "QuorumFailed"),
+        (504, "timeout", "Timeout"),
+        (400, "bad_run_id", "BadRunId"),
+        (400, "invalid_key", "InvalidKey"),
+        (400, "invalid_request", "InvalidRequest"),
+        (409, "conflict", "Conflict"),
+        (503, "unavailable", "Unavailable"),
+        (403, "forbidden", "Other"),
+        (415, "unsupported_media_type", "Other"),
+        (500, "internal", "Other"),
+        (500, "from_the_future", "Other"),
+    ];
+    for (status, code, expected) in cases {
+        let error = coded(status, code);
+        assert_eq!(variant(&error), expected, "{code}");
+        assert_eq!(error.status_code().as_u16(), status);
+    }
+    assert_eq!(coded(500, "from_the_future").code(), ErrorCode::Unknown);
+}
+
+#[test]
+fn codes_win_over_status_codes() {
+    let error = coded(404, "invalid_key");
+    assert_eq!(variant(&error), "InvalidKey");
+    let error = coded(500, "not_found");
+    assert_eq!(variant(&error), "NotFound");
+}
+
+#[test]
+fn uncoded_not_found_responses_stay_not_found() {
+    let error = response_error(404, &json!({"trace": ["gone"]}).to_string());
+    assert_eq!(variant(&error), "NotFound");
+    assert_eq!(error.code(), ErrorCode::NotFound);
+
+    let error = response_error(404, "not json");
+    assert_eq!(variant(&error), "Other");
+    assert_eq!(error.code(), ErrorCode::Unknown);
+    let error = response_error(500, &json!({"trace": []}).to_string());
+    assert_eq!(variant(&error), "Other");
+}
+
+#[test]
+fn quorum_details_and_replicas_are_exposed() {
+    let body = json!({
+        "code": "quorum_failed",
+        "trace": ["Failed to get consensus"],
+        "quorum": {"asked": 3, "answered": 1, "agreeing": 1, "required": 2},
+        "replicas": [
+            {"node": 1, "result": "failed", "latency_ms": 1.5},
+            {"node": 2, "result": "skipped"},
+        ],
+    });
+    let error = response_error(500, &body.to_string());
+    assert_eq!(variant(&error), "QuorumFailed");
+    assert_eq!(
+        error.quorum(),
+        Some(QuorumDetails { asked: 3, answered: 1, agreeing: 1, required: 2 }),
+    );
+    assert_eq!(
+        error.replicas(),
+        [
+            ReplicaOutcome::new(
+                1,
+                ReplicaResult::Failed,
+                Some(Duration::from_micros(1500)),
+            ),
+            ReplicaOutcome::new(2, ReplicaResult::Skipped, None),
+        ],
+    );
+
+    let error = coded(404, "not_found");
+    assert_eq!(error.quorum(), None);
+    assert!(error.replicas().is_empty());
+}
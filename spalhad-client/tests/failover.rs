@@ -0,0 +1,300 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use spalhad_client::{Balancing, ClusterClient, ResponseError};
+use spalhad_spec::{
+    ErrorCode,
+    bucket::{Bucket, BucketConfig, BucketsResponse},
+    cluster::{PeerStatus, TopologyNode, TopologyResponse},
+    kv::{Key, Namespace},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    time,
+};
+
+type Respond = Arc<dyn Fn(&str, &str) -> (u16, String) + Send + Sync>;
+
+#[derive(Debug, Clone)]
+struct FakeNode {
+    base_url: String,
+    hits: Arc<Mutex<Vec<String>>>,
+}
+
+impl FakeNode {
+    async fn spawn<F>(respond: F) -> Self
+    where
+        F: Fn(&str, &str) -> (u16, String) + Send + Sync + 'static,
+    {
+        Self::spawn_with_delay(respond, Duration::ZERO).await
+    }
+
+    async fn spawn_with_delay<F>(respond: F, delay: Duration) -> Self
+    where
+        F: Fn(&str, &str) -> (u16, String) + Send + Sync + 'static,
+    {
+        let respond: Respond = Arc::new(respond);
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let address = listener.local_addr().expect("local address");
+        let hits = Arc::new(Mutex::new(Vec::new()));
+        let recorded = hits.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.expect("accept");
+                let hits = recorded.clone();
+                tokio::spawn(serve(stream, respond.clone(), delay, hits));
+            }
+        });
+        Self { base_url: format!("http://{address}"), hits }
+    }
+
+    async fn refused() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let address = listener.local_addr().expect("local address");
+        drop(listener);
+        Self { base_url: format!("http://{address}"), hits: Arc::default() }
+    }
+
+    fn hits(&self) -> Vec<String> {
+        self.hits.lock().expect("poisoned lock").clone()
+    }
+}
+
+async fn serve(
+    stream: TcpStream,
+    respond: Respond,
+    delay: Duration,
+    hits: Arc<Mutex<Vec<String>>>,
+) {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await.expect("request line");
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).await.expect("header");
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().expect("content length");
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await.expect("body");
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    hits.lock().expect("poisoned lock").push(format!("{method} {path}"));
+    let (status, body) = respond(method, path);
+    time::sleep(delay).await;
+    let head = [
+        format!("HTTP/1.1 {status} Fake"),
+        "content-type: application/json".to_owned(),
+        format!("content-length: {}", body.len()),
+        "connection: close".to_owned(),
+    ];
+    let response = format!("{}\r\n\r\n{body}", head.join("\r\n"));
+    let mut stream = reader.into_inner();
+    stream.write_all(response.as_bytes()).await.expect("response");
+}
+
+fn healthy(method: &str, _: &str) -> (u16, String) {
+    match method {
+        "GET" => (200, r#"{"value": 1}"#.into()),
+        _ => (200, r#"{"new": true}"#.into()),
+    }
+}
+
+fn unavailable(_: &str, _: &str) -> (u16, String) {
+    (503, r#"{"code": "unavailable", "trace": ["draining"]}"#.into())
+}
+
+fn internal(_: &str, _: &str) -> (u16, String) {
+    (500, r#"{"code": "internal", "trace": ["disk full"]}"#.into())
+}
+
+fn invalid_request(_: &str, _: &str) -> (u16, String) {
+    (400, r#"{"code": "invalid_request", "trace": ["bad value"]}"#.into())
+}
+
+fn legacy_inactive(_: &str, path: &str) -> (u16, String) {
+    match path {
+        "/spalhad/v1/sync/active" => (200, r#"{"is_active": false}"#.into()),
+        _ => (400, r#"{"trace": ["node is not active"]}"#.into()),
+    }
+}
+
+fn cluster(nodes: &[&FakeNode]) -> ClusterClient {
+    ClusterClient::new(nodes.iter().map(|node| node.base_url.as_str()))
+}
+
+fn kv_hit(method: &str) -> String {
+    format!("{method} /spalhad/v1/kv/{}", Key::from_bytes_key("foo"))
+}
+
+#[tokio::test]
+async fn unavailable_nodes_are_skipped_and_tried_last() {
+    let refused = FakeNode::refused().await;
+    let draining = FakeNode::spawn(unavailable).await;
+    let healthy = FakeNode::spawn(healthy).await;
+    let client = cluster(&[&refused, &draining, &healthy]);
+
+    assert!(client.put("foo", 1).await.expect("put should fail over"));
+    assert_eq!(draining.hits(), [kv_hit("POST")]);
+    assert_eq!(healthy.hits(), [kv_hit("POST")]);
+    let available: Vec<_> =
+        client.nodes().iter().map(|node| node.available).collect();
+    assert_eq!(available, [false, false, true]);
+
+    assert!(client.put("foo", 1).await.expect("second put"));
+    assert_eq!(draining.hits().len(), 1);
+    assert_eq!(healthy.hits().len(), 2);
+}
+
+#[tokio::test]
+async fn ambiguous_errors_fail_over_reads_but_not_writes() {
+    let broken = FakeNode::spawn(internal).await;
+    let healthy = FakeNode::spawn(healthy).await;
+
+    let error = cluster(&[&broken, &healthy])
+        .put("foo", 1)
+        .await
+        .expect_err("write may have been applied");
+    let error = error.downcast::<ResponseError>().expect("response error");
+    assert_eq!(error.code(), ErrorCode::Internal);
+    assert!(healthy.hits().is_empty());
+
+    let value: Option<u32> = cluster(&[&broken, &healthy])
+        .get("foo")
+        .await
+        .expect("read should fail over");
+    assert_eq!(value, Some(1));
+    assert_eq!(healthy.hits(), [kv_hit("GET")]);
+}
+
+#[tokio::test]
+async fn client_errors_are_returned_without_probing() {
+    let rejecting = FakeNode::spawn(invalid_request).await;
+    let healthy = FakeNode::spawn(healthy).await;
+
+    let error = cluster(&[&rejecting, &healthy])
+        .put("foo", 1)
+        .await
+        .expect_err("request is invalid everywhere");
+    let error = error.downcast::<ResponseError>().expect("response error");
+    assert_eq!(error.code(), ErrorCode::InvalidRequest);
+    assert_eq!(rejecting.hits(), [kv_hit("POST")]);
+    assert!(healthy.hits().is_empty());
+}
+
+#[tokio::test]
+async fn uncoded_bad_requests_probe_activity() {
+    let legacy = FakeNode::spawn(legacy_inactive).await;
+    let healthy = FakeNode::spawn(healthy).await;
+
+    assert!(
+        cluster(&[&legacy, &healthy])
+            .put("foo", 1)
+            .await
+            .expect("inactive node should be failed over")
+    );
+    assert_eq!(
+        legacy.hits(),
+        [kv_hit("POST"), "GET /spalhad/v1/sync/active".to_owned()]
+    );
+    assert_eq!(healthy.hits(), [kv_hit("POST")]);
+}
+
+#[tokio::test]
+async fn least_latency_prefers_faster_nodes() {
+    let slow =
+        FakeNode::spawn_with_delay(healthy, Duration::from_millis(50)).await;
+    let fast = FakeNode::spawn(healthy).await;
+    let client =
+        cluster(&[&slow, &fast]).with_balancing(Balancing::LeastLatency);
+
+    for _ in 0 .. 3 {
+        client.put("foo", 1).await.expect("put");
+    }
+    assert_eq!(slow.hits().len(), 1);
+    assert_eq!(fast.hits().len(), 2);
+}
+
+#[tokio::test]
+async fn round_robin_rotates_the_first_node() {
+    let first = FakeNode::spawn(healthy).await;
+    let second = FakeNode::spawn(healthy).await;
+    let client = cluster(&[&first, &second]);
+
+    for _ in 0 .. 4 {
+        client.put("foo", 1).await.expect("put");
+    }
+    assert_eq!(first.hits().len(), 2);
+    assert_eq!(second.hits().len(), 2);
+}
+
+#[tokio::test]
+async fn replica_routing_follows_bucket_replication() {
+    let mut workers = Vec::new();
+    for _ in 0 .. 3 {
+        workers.push(FakeNode::spawn(healthy).await);
+    }
+    let billing: Namespace = "billing".parse().expect("namespace");
+    let topology = TopologyResponse {
+        version: 7,
+        self_id: 0,
+        replication: 3,
+        nodes: workers
+            .iter()
+            .map(|worker| TopologyNode {
+                address: worker.base_url.clone(),
+                status: PeerStatus::Joined,
+            })
+            .collect(),
+    };
+    let buckets = BucketsResponse {
+        buckets: vec![Bucket {
+            name: billing.clone(),
+            revision: 1,
+            updated_by: 0,
+            config: BucketConfig {
+                replication: 1,
+                min_correct_reads: 1,
+                min_correct_writes: 1,
+            },
+        }],
+        written: Vec::new(),
+    };
+    let topology = serde_json::to_string(&topology).expect("topology");
+    let buckets = serde_json::to_string(&buckets).expect("buckets");
+    let seed = FakeNode::spawn(move |_, path| match path {
+        "/spalhad/v1/sync/topology" => (200, topology.clone()),
+        "/spalhad/v1/admin/buckets" => (200, buckets.clone()),
+        _ => (404, r#"{"code": "not_found", "trace": []}"#.into()),
+    })
+    .await;
+
+    let client = cluster(&[&seed]).with_replica_routing(true);
+    client.refresh().await.expect("refresh");
+    for _ in 0 .. 3 {
+        client.put_namespaced(&billing, "foo", 1).await.expect("put");
+        client.put("foo", 1).await.expect("put");
+    }
+
+    let owner = Key::from_namespaced_key(&billing, "foo").partition(3);
+    let namespaced = "POST /spalhad/v1/ns/billing/kv/foo".to_owned();
+    for (index, worker) in workers.iter().enumerate() {
+        let hits = worker.hits();
+        let owned = hits.iter().filter(|hit| **hit == namespaced).count();
+        assert_eq!(owned, if index == owner { 3 } else { 0 }, "{hits:?}");
+        assert_eq!(hits.len() - owned, 1, "{hits:?}");
+    }
+}
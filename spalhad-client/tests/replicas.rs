@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use spalhad_client::Client;
+use spalhad_spec::{
+    kv::Key,
+    replica::{DEBUG_HEADER, REPLICAS_HEADER, ReplicaOutcome, ReplicaResult},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    time,
+};
+
+async fn spawn_node(slow_key: Key) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let address = listener.local_addr().expect("local address");
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.expect("accept");
+            tokio::spawn(serve(stream, slow_key.clone()));
+        }
+    });
+    format!("http://{address}")
+}
+
+async fn serve(stream: TcpStream, slow_key: Key) {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await.expect("request line");
+    let mut debug = false;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).await.expect("header");
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, _)) = header.split_once(':') {
+            debug |= name.eq_ignore_ascii_case(DEBUG_HEADER);
+        }
+    }
+
+    let slow = request_line.contains(&slow_key.to_string());
+    let node = if slow {
+        time::sleep(Duration::from_millis(200)).await;
+        1
+    } else {
+        2
+    };
+    let outcomes = [ReplicaOutcome::new(node, ReplicaResult::Agreed, None)];
+    let mut head = vec![
+        "HTTP/1.1 200 Fake".to_owned(),
+        "content-type: application/json".to_owned(),
+        "connection: close".to_owned(),
+    ];
+    if debug {
+        let outcomes = serde_json::to_string(&outcomes).expect("outcomes");
+        head.push(format!("{REPLICAS_HEADER}: {outcomes}"));
+    }
+    let body = r#"{"value": 1}"#;
+    head.push(format!("content-length: {}", body.len()));
+    let response = format!("{}\r\n\r\n{body}", head.join("\r\n"));
+    let mut stream = reader.into_inner();
+    stream.write_all(response.as_bytes()).await.expect("response");
+}
+
+#[tokio::test]
+async fn concurrent_calls_get_their_own_replicas() {
+    let base_url = spawn_node(Key::from_bytes_key(b"slow")).await;
+    let client = Client::new(base_url).with_debug_replicas(true);
+
+    let (slow, fast) = tokio::join!(
+        client.get_with_replicas::<_, u32>("slow"),
+        client.get_with_replicas::<_, u32>("fast"),
+    );
+    let (value, replicas) = slow.expect("slow get");
+    assert_eq!(value, Some(1));
+    assert_eq!(replicas, [ReplicaOutcome::new(1, ReplicaResult::Agreed, None)]);
+    let (value, replicas) = fast.expect("fast get");
+    assert_eq!(value, Some(1));
+    assert_eq!(replicas, [ReplicaOutcome::new(2, ReplicaResult::Agreed, None)]);
+
+    let client = client.with_debug_replicas(false);
+    let (_, replicas) = client
+        .get_with_replicas::<_, u32>("fast")
+        .await
+        .expect("get without debugging");
+    assert!(replicas.is_empty());
+}
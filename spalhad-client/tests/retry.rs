@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use spalhad_client::{Client, Idempotency, RetryPolicy, is_timeout};
+use tokio::{
+    net::TcpListener,
+    time::{self, Instant},
+};
+
+fn steady(backoff: Duration) -> RetryPolicy {
+    RetryPolicy::new()
+        .with_backoff(backoff, backoff)
+        .with_multiplier(1.0)
+        .with_jitter(0.0)
+}
+
+#[test]
+fn backoff_grows_up_to_the_maximum() {
+    let policy = RetryPolicy::new()
+        .with_backoff(Duration::from_millis(50), Duration::from_secs(2))
+        .with_multiplier(2.0)
+        .with_jitter(0.0);
+    assert_eq!(policy.backoff(0), Duration::from_millis(50));
+    assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff(3), Duration::from_millis(400));
+    assert_eq!(policy.backoff(10), Duration::from_secs(2));
+    assert_eq!(policy.backoff(usize::MAX), Duration::from_secs(2));
+}
+
+#[test]
+fn jitter_only_shortens_the_backoff() {
+    let policy = RetryPolicy::new()
+        .with_backoff(Duration::from_millis(100), Duration::from_secs(1))
+        .with_jitter(0.5);
+    for retry in 0 .. 100 {
+        let full = Duration::from_millis(100)
+            .mul_f64(2f64.powi(retry.min(10)))
+            .min(Duration::from_secs(1));
+        let backoff = policy.backoff(retry as usize);
+        assert!(backoff <= full, "{backoff:?} > {full:?}");
+        assert!(backoff >= full / 2, "{backoff:?} < {full:?} / 2");
+    }
+}
+
+#[test]
+fn settings_are_kept_in_range() {
+    let policy = RetryPolicy::new()
+        .with_backoff(Duration::from_secs(1), Duration::from_millis(1))
+        .with_multiplier(0.5)
+        .with_jitter(2.0);
+    for retry in 0 .. 4 {
+        assert!(policy.backoff(retry) <= Duration::from_secs(1));
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn only_idempotent_requests_are_retried() {
+    let policy = steady(Duration::from_millis(10))
+        .with_max_retries(2)
+        .with_total_timeout(None);
+
+    let mut attempts = policy.start(Idempotency::NonIdempotent);
+    assert_eq!(attempts.next_backoff(), None);
+    assert_eq!(attempts.retries(), 0);
+
+    let mut attempts = policy.start(Idempotency::Idempotent);
+    assert_eq!(attempts.next_backoff(), Some(Duration::from_millis(10)));
+    assert_eq!(attempts.next_backoff(), Some(Duration::from_millis(10)));
+    assert_eq!(attempts.next_backoff(), None);
+    assert_eq!(attempts.retries(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn total_timeout_bounds_backoffs_and_attempts() {
+    let backoff = Duration::from_millis(400);
+    let total_timeout = Duration::from_secs(1);
+    let policy = steady(backoff)
+        .with_max_retries(10)
+        .with_total_timeout(Some(total_timeout));
+    let mut attempts = policy.start(Idempotency::Idempotent);
+    let timeout = Duration::from_secs(90);
+    assert_eq!(attempts.attempt_timeout(timeout), total_timeout);
+    assert_eq!(
+        attempts.attempt_timeout(Duration::from_millis(100)),
+        Duration::from_millis(100),
+    );
+
+    assert_eq!(attempts.next_backoff(), Some(backoff));
+    time::advance(backoff).await;
+    assert_eq!(attempts.next_backoff(), Some(backoff));
+    time::advance(backoff).await;
+    assert_eq!(attempts.attempt_timeout(timeout), Duration::from_millis(200));
+    assert_eq!(attempts.next_backoff(), None);
+
+    time::advance(total_timeout).await;
+    assert_eq!(attempts.attempt_timeout(timeout), Duration::ZERO);
+    let unbounded =
+        policy.with_total_timeout(None).start(Idempotency::Idempotent);
+    assert_eq!(unbounded.attempt_timeout(timeout), timeout);
+}
+
+#[tokio::test]
+async fn total_timeout_cuts_in_flight_requests() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let address = listener.local_addr().expect("local address");
+    let server = tokio::spawn(async move {
+        let mut connections = Vec::new();
+        loop {
+            let (stream, _) = listener.accept().await.expect("accept");
+            connections.push(stream);
+        }
+    });
+
+    let total_timeout = Duration::from_millis(200);
+    let client = Client::new(format!("http://{address}")).with_retry_policy(
+        steady(Duration::from_millis(10))
+            .with_max_retries(5)
+            .with_total_timeout(Some(total_timeout)),
+    );
+    let started = Instant::now();
+    let error = client.put("foo", 1).await.expect_err("server never answers");
+    let elapsed = started.elapsed();
+    assert!(is_timeout(&error), "{error:?}");
+    assert!(elapsed < Duration::from_secs(5), "request took {elapsed:?}");
+
+    server.abort();
+}
@@ -2,18 +2,51 @@ use std::{backtrace::BacktraceStatus, path::PathBuf, time::Duration};
 
 use anyhow::{Result, bail};
 use clap::Parser;
-use spalhad_actor::ActorOptions;
+use opentelemetry::{KeyValue, global, trace::TracerProvider};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    Resource,
+    propagation::TraceContextPropagator,
+    trace::SdkTracerProvider,
+};
+use spalhad_actor::{ActorMetricsRegistry, ActorOptions, ActorRegistry};
+use spalhad_client::RetryPolicy;
 use spalhad_server::{
     actor::{
         coordinator::Coordinator,
-        storage::{ClientStorage, DirStorage, MemoryStorage},
+        storage::{
+            self,
+            BreakerConfig,
+            ClientStorage,
+            DirStorage,
+            MemoryStorage,
+            PeerBreakers,
+            RpcStorage,
+        },
     },
+    bucket::Buckets,
+    grpc::{self, KvService},
     http::{self, App},
+    resp,
+    rpc,
     sync,
+    topology::Topology,
+};
+use spalhad_spec::{
+    cluster::{ClusterConfig, PeerStatus, PeerTransport},
+    wire::WireFormat,
+};
+use spalhad_task::{Criticality, RestartPolicy, TaskManager};
+use tokio::{
+    fs,
+    select,
+    signal::{
+        self,
+        unix::{SignalKind, signal},
+    },
+    sync::watch,
+    time,
 };
-use spalhad_spec::cluster::ClusterConfig;
-use spalhad_task::TaskManager;
-use tokio::fs;
 use tracing::Level;
 use tracing_subscriber::{
     EnvFilter,
@@ -24,12 +57,16 @@ use tracing_subscriber::{
 
 mod util;
 
+const BUCKETS_FILE: &str = "buckets.json";
+
 #[derive(Debug, Clone, Parser)]
 struct CliArgs {
     #[clap(short, long, default_value = "0.0.0.0:5000")]
     bind: String,
     #[clap(short, long, default_value_t = 10)]
     kv_channel_size: usize,
+    #[clap(long, default_value_t = 10)]
+    control_channel_size: usize,
     #[clap(short, long)]
     persistence_dir: Option<PathBuf>,
     #[clap(short, long, default_value = "cluster.config.json")]
@@ -45,27 +82,99 @@ struct CliArgs {
         value_parser = util::parse_duration,
     )]
     communication_timeout: Duration,
+    #[clap(long)]
+    actor_metrics: bool,
+    #[clap(long, default_value = "30s", value_parser = util::parse_duration)]
+    shutdown_timeout: Duration,
+    #[clap(long, default_value_t = 5)]
+    activation_retries: usize,
+    #[clap(long, default_value = "1s", value_parser = util::parse_duration)]
+    activation_backoff: Duration,
+    #[clap(long)]
+    otlp_endpoint: Option<String>,
+    #[clap(long, value_parser = util::parse_percentile)]
+    hedge_percentile: Option<f64>,
+    #[clap(long, default_value_t = 5)]
+    breaker_failures: usize,
+    #[clap(long, default_value = "5s", value_parser = util::parse_duration)]
+    breaker_cooldown: Duration,
+    #[clap(long, default_value_t = 0)]
+    peer_retries: usize,
+    #[clap(long, default_value = "20ms", value_parser = util::parse_duration)]
+    peer_retry_backoff: Duration,
+    #[clap(long, default_value_t = WireFormat::MessagePack)]
+    peer_wire_format: WireFormat,
+    #[clap(long)]
+    rpc_bind: Option<String>,
+    #[clap(long)]
+    resp_bind: Option<String>,
+    #[clap(long, default_value = "30s", value_parser = util::parse_duration)]
+    bucket_sync_interval: Duration,
 }
 
-fn setup_logging() -> Result<()> {
+fn setup_logging(
+    self_id: usize,
+    otlp_endpoint: Option<&str>,
+) -> Result<SdkTracerProvider> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let resource = Resource::builder()
+        .with_service_name("spalhad-server")
+        .with_attribute(KeyValue::new("service.instance.id", self_id as i64))
+        .build();
+    let mut tracer_provider =
+        SdkTracerProvider::builder().with_resource(resource);
+    if let Some(endpoint) = otlp_endpoint {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()?;
+        tracer_provider = tracer_provider.with_batch_exporter(exporter);
+    }
+    let tracer_provider = tracer_provider.build();
+    let tracer = tracer_provider.tracer("spalhad-server");
+
     let env_filter = EnvFilter::builder()
         .with_default_directive(Level::INFO.into())
         .with_env_var("SPALHAD_LOG_LEVEL")
         .from_env()?;
     let fmt = fmt::layer().with_target(true);
-    tracing_subscriber::registry().with(fmt).with(env_filter).init();
+    let otel = tracing_opentelemetry::layer().with_tracer(tracer);
+    tracing_subscriber::registry().with(fmt).with(otel).with(env_filter).init();
+    Ok(tracer_provider)
+}
+
+async fn wait_for_signal() -> Result<()> {
+    let mut terminate = signal(SignalKind::terminate())?;
+    select! {
+        result = signal::ctrl_c() => result?,
+        _ = terminate.recv() => (),
+    }
     Ok(())
 }
 
 async fn try_main(args: CliArgs) -> Result<()> {
-    setup_logging()?;
+    let tracer_provider =
+        setup_logging(args.self_id, args.otlp_endpoint.as_deref())?;
+    let result = run(args).await;
+    if let Err(error) = tracer_provider.shutdown() {
+        tracing::warn!(%error, "failed to flush traces");
+    }
+    result
+}
 
+async fn run(args: CliArgs) -> Result<()> {
     let task_manager = TaskManager::new();
+    let registry = ActorRegistry::new();
 
     let storage_options = ActorOptions::new(&task_manager)
-        .with_channel_size(args.kv_channel_size);
+        .with_channel_size(args.kv_channel_size)
+        .with_control_channel_size(args.control_channel_size)
+        .with_metrics(args.actor_metrics.then(ActorMetricsRegistry::new))
+        .with_registry(Some(registry.clone()));
+
+    registry.clear_on_shutdown(&task_manager);
 
-    let self_kv = match args.persistence_dir {
+    let self_kv = match &args.persistence_dir {
         Some(dir_path) => storage_options.spawn(DirStorage::open(dir_path)),
         None => storage_options.spawn(MemoryStorage::open()),
     };
@@ -78,43 +187,229 @@ async fn try_main(args: CliArgs) -> Result<()> {
         bail!("self-id is too big")
     }
 
+    if cluster_config.transport == PeerTransport::Rpc
+        && cluster_config.rpc_addresses.len() != cluster_config.addresses.len()
+    {
+        bail!("rpc transport needs one rpc address per node")
+    }
+
     tracing::info!("self-id is {}", args.self_id);
 
+    let breakers = PeerBreakers::new(BreakerConfig::new(
+        args.breaker_failures,
+        args.breaker_cooldown,
+    ));
+
+    let peer_retry_policy = RetryPolicy::new()
+        .with_max_retries(args.peer_retries)
+        .with_backoff(args.peer_retry_backoff, args.communication_timeout)
+        .with_total_timeout(Some(args.communication_timeout));
+
     let mut nodes = Vec::with_capacity(cluster_config.addresses.len());
+    let mut peer_names = Vec::with_capacity(cluster_config.addresses.len());
     for (i, address) in cluster_config.addresses.iter().enumerate() {
         if i == args.self_id {
             nodes.push(self_kv.clone());
         } else {
-            let client_storage_actor = ClientStorage::open_with_timeout(
-                address,
-                args.communication_timeout,
-            )?;
-            nodes.push(storage_options.spawn(client_storage_actor));
+            let name = storage::peer_name(i);
+            let peer = match cluster_config.transport {
+                PeerTransport::Http => {
+                    let client_storage_actor =
+                        ClientStorage::open_with_timeout(
+                            address,
+                            args.communication_timeout,
+                        )?
+                        .with_retry_policy(peer_retry_policy)
+                        .with_wire_format(args.peer_wire_format)
+                        .with_circuit_breaker(breakers.breaker(i));
+                    storage_options
+                        .spawn_named(name.clone(), client_storage_actor)
+                },
+                PeerTransport::Rpc => {
+                    let rpc_storage_actor = RpcStorage::open(
+                        &cluster_config.rpc_addresses[i],
+                        args.communication_timeout,
+                    )
+                    .with_circuit_breaker(breakers.breaker(i));
+                    storage_options.spawn_named(name.clone(), rpc_storage_actor)
+                },
+            };
+            nodes.push(peer);
+            peer_names.push((i, name));
         }
     }
 
-    let coordinator = storage_options.spawn(Coordinator::new(
+    let nodes_len = cluster_config.addresses.len();
+    let buckets = match &args.persistence_dir {
+        Some(dir_path) => Buckets::open(
+            args.self_id,
+            nodes_len,
+            cluster_config.replication,
+            dir_path.join(BUCKETS_FILE),
+        )?,
+        None => {
+            Buckets::new(args.self_id, nodes_len, cluster_config.replication)
+        },
+    };
+
+    let coordinator = Coordinator::new(
         cluster_config.replication,
         cluster_config.min_correct_reads,
         cluster_config.min_correct_writes,
         args.concurrency_level,
         nodes,
-    ));
+    )
+    .with_registry(registry, peer_names)
+    .with_hedging(args.hedge_percentile)
+    .with_breakers(Some(breakers.clone()))
+    .with_buckets(Some(buckets.clone()));
+    let coordinator = storage_options.spawn(coordinator);
 
-    let app = App::new(&storage_options, self_kv, coordinator);
+    let app = App::new(&storage_options, self_kv, coordinator)
+        .with_peer_timeout(Some(args.communication_timeout))
+        .with_peer_breakers(Some(breakers))
+        .with_peer_retry_policy(Some(peer_retry_policy))
+        .with_peer_wire_format(Some(args.peer_wire_format))
+        .with_peer_transport(cluster_config.transport)
+        .with_topology(Some(Topology::new(args.self_id, &cluster_config)))
+        .with_buckets(Some(buckets.clone()));
 
     let self_run_id = app.self_run_id();
-    let self_base_url = cluster_config.addresses[args.self_id].clone();
+    let self_id = args.self_id;
+    let self_base_url = cluster_config.addresses[self_id].clone();
+    let communication_timeout = args.communication_timeout;
+
+    if cluster_config.transport == PeerTransport::Rpc {
+        let rpc_bind_address = args
+            .rpc_bind
+            .unwrap_or_else(|| cluster_config.rpc_addresses[self_id].clone());
+        let bouncer = app.bouncer().clone();
+        let cancellation_token = task_manager.cancellation_token();
+        task_manager.spawn_named(
+            "rpc-server",
+            Criticality::Critical,
+            async move {
+                rpc::serve(&rpc_bind_address, bouncer, cancellation_token).await
+            },
+        );
+    }
+
+    if let Some(resp_bind_address) = args.resp_bind {
+        let bouncer = app.bouncer().clone();
+        let cancellation_token = task_manager.cancellation_token();
+        task_manager.spawn_named(
+            "resp-server",
+            Criticality::Critical,
+            async move {
+                resp::serve(&resp_bind_address, bouncer, cancellation_token)
+                    .await
+            },
+        );
+    }
 
-    let router = http::router().with_state(app);
     let bind_address = args.bind;
-    task_manager.spawn(async move { http::serve(&bind_address, router).await });
+    let cancellation_token = task_manager.cancellation_token();
+    let kv_service =
+        KvService::new(app.bouncer().clone(), cancellation_token.clone());
+    let router = http::app_router(app).merge(grpc::router(kv_service));
+    task_manager.spawn_named("http-server", Criticality::Critical, async move {
+        http::serve(&bind_address, router, cancellation_token).await
+    });
+
+    let activation_policy =
+        RestartPolicy::new(args.activation_retries, args.activation_backoff);
+    let (pulled_sender, pulled) = watch::channel(false);
+    let addresses = cluster_config.addresses.clone();
+    let pulled_buckets = buckets.clone();
+    task_manager.spawn_named(
+        "bucket-pull",
+        Criticality::NonCritical,
+        async move {
+            sync::pull_buckets_at_start(
+                self_id,
+                &pulled_buckets,
+                &addresses,
+                communication_timeout,
+                activation_policy,
+            )
+            .await;
+            pulled_sender.send_replace(true);
+            Ok(())
+        },
+    );
 
-    task_manager.spawn(async move {
-        sync::activate(self_run_id, &self_base_url).await
+    let addresses = cluster_config.addresses.clone();
+    let cancellation_token = task_manager.cancellation_token();
+    let bucket_sync_interval = args.bucket_sync_interval;
+    let synced_buckets = buckets.clone();
+    task_manager.spawn_named(
+        "bucket-sync",
+        Criticality::NonCritical,
+        async move {
+            sync::resync_buckets(
+                self_id,
+                &synced_buckets,
+                &addresses,
+                communication_timeout,
+                bucket_sync_interval,
+                cancellation_token,
+            )
+            .await
+        },
+    );
+
+    let addresses = cluster_config.addresses.clone();
+    task_manager.spawn_with_restart(
+        "activation",
+        activation_policy,
+        move || {
+            let self_base_url = self_base_url.clone();
+            let addresses = addresses.clone();
+            let mut pulled = pulled.clone();
+            async move {
+                pulled.wait_for(|pulled| *pulled).await?;
+                sync::activate(self_run_id, &self_base_url).await?;
+                sync::announce(
+                    self_id,
+                    PeerStatus::Joined,
+                    &addresses,
+                    communication_timeout,
+                )
+                .await
+            }
+        },
+    );
+
+    let addresses = cluster_config.addresses;
+    let shutdown_manager = task_manager.clone();
+    task_manager.spawn_named("shutdown", Criticality::Critical, async move {
+        let cancellation_token = shutdown_manager.cancellation_token();
+        select! {
+            result = wait_for_signal() => result?,
+            _ = cancellation_token.cancelled() => return Ok(()),
+        }
+        tracing::info!("Shutdown requested, leaving cluster...");
+        sync::announce(
+            self_id,
+            PeerStatus::Leaving,
+            &addresses,
+            communication_timeout,
+        )
+        .await?;
+        shutdown_manager.cancel();
+        Ok(())
     });
 
-    task_manager.wait_all().await?;
+    let cancellation_token = task_manager.cancellation_token();
+    let shutdown_timeout = args.shutdown_timeout;
+    select! {
+        result = task_manager.wait_all() => result?,
+        _ = async {
+            cancellation_token.cancelled().await;
+            time::sleep(shutdown_timeout).await;
+        } => bail!("timed out waiting for in-flight work to drain"),
+    }
+    tracing::info!("Shut down cleanly.");
     Ok(())
 }
 
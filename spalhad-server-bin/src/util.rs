@@ -39,3 +39,12 @@ pub fn parse_duration(input: &str) -> Result<Duration> {
     };
     maybe_duration.context("invalid duration scalar")
 }
+
+pub fn parse_percentile(input: &str) -> Result<f64> {
+    let percentile: f64 =
+        input.trim().parse().context("invalid percentile scalar")?;
+    if !(0.0 ..= 1.0).contains(&percentile) {
+        bail!("percentile must be between 0.0 and 1.0, got {percentile}");
+    }
+    Ok(percentile)
+}
@@ -0,0 +1,22 @@
+use std::error::Error;
+
+use prost::Message;
+
+const PROTO_DIR: &str = "../proto";
+
+const PROTOS: &[&str] = &["../proto/spalhad/v1/kv.proto"];
+
+fn main() -> Result<(), Box<dyn Error>> {
+    println!("cargo:rerun-if-changed={PROTO_DIR}");
+    let descriptors = protobuf_parse::Parser::new()
+        .pure()
+        .include(PROTO_DIR)
+        .inputs(PROTOS)
+        .file_descriptor_set()?;
+    let descriptors = protobuf::Message::write_to_bytes(&descriptors)?;
+    let descriptors = prost_types::FileDescriptorSet::decode(&*descriptors)?;
+    tonic_prost_build::configure()
+        .build_client(false)
+        .compile_fds(descriptors)?;
+    Ok(())
+}
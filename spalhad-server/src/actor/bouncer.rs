@@ -72,11 +72,22 @@ pub type BouncerHandle = ActorHandle<BouncerCall>;
 
 #[derive(Debug, CallSuperset)]
 pub enum BouncerCall {
+    #[spalhad(control)]
     Activate(ActivateCall),
+    #[spalhad(control)]
     IsActive(IsActiveCall),
-    #[spalhad(flatten { storage::GetCall, storage::PutCall })]
+    #[spalhad(flatten {
+        storage::GetCall,
+        storage::PutCall,
+        storage::DeleteCall,
+    })]
     Storage(StorageCall),
-    #[spalhad(flatten { coordinator::GetCall, coordinator::PutCall })]
+    #[spalhad(flatten {
+        coordinator::GetCall,
+        coordinator::PutCall,
+        coordinator::DeleteCall,
+        coordinator::SetPeerStatusCall,
+    })]
     Coordinator(CoordinatorCall),
 }
 
@@ -1,20 +1,233 @@
-use std::collections::HashMap;
+use std::{collections::VecDeque, time::Duration};
 
-use anyhow::{Result, anyhow, bail};
-use futures::{StreamExt, stream};
-use spalhad_actor::{ActorCall, ActorHandle, CallSuperset, TrivialLoopActor};
-use spalhad_spec::kv::Key;
-use tokio::pin;
+use anyhow::{Result, anyhow};
+use spalhad_actor::{
+    ActorCall,
+    ActorHandle,
+    ActorRegistry,
+    CallSuperset,
+    Gathered,
+    Hedging,
+    Reply,
+    Scatter,
+    TrivialLoopActor,
+    Votes,
+    quorum,
+};
+use spalhad_spec::{
+    QuorumDetails,
+    bucket::BucketConfig,
+    cluster::PeerStatus,
+    kv::{Key, KeyOrigin, Namespace, Value},
+    replica::{ReplicaOutcome, ReplicaResult},
+};
 
-use super::storage::{self, StorageHandle};
+use thiserror::Error;
+
+use super::storage::{self, PeerBreakers, StorageHandle};
+use crate::bucket::Buckets;
+
+const LATENCY_WINDOW: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("Failed to get consensus: {details}")]
+pub struct QuorumError {
+    pub details: QuorumDetails,
+    pub replicas: Vec<ReplicaOutcome>,
+}
+
+fn decide<O>(
+    gathered: Gathered<usize, O>,
+    preference: &[usize],
+    asked: &[usize],
+    required: usize,
+) -> Result<Replicated<O>, QuorumError>
+where
+    O: PartialEq,
+{
+    let replicas = outcomes(&gathered, preference, asked, required);
+    let votes = Votes::from_replies(gathered.replies);
+    let details = QuorumDetails {
+        asked: asked.len(),
+        answered: votes.total(),
+        agreeing: votes.max_count(),
+        required,
+    };
+    match votes.into_winner(required) {
+        Some(value) => Ok(Replicated { value, replicas }),
+        None => Err(QuorumError { details, replicas }),
+    }
+}
+
+fn decide_acks<F>(
+    gathered: Gathered<usize, bool>,
+    preference: &[usize],
+    asked: &[usize],
+    required: usize,
+    merge: F,
+) -> Result<Replicated<bool>, QuorumError>
+where
+    F: FnOnce(&[bool]) -> bool,
+{
+    let acked: Vec<_> = gathered
+        .replies
+        .iter()
+        .filter_map(|reply| reply.output.as_ref().ok().copied())
+        .collect();
+    let replies = gathered
+        .replies
+        .into_iter()
+        .map(|reply| Reply {
+            target: reply.target,
+            output: reply.output.map(drop),
+            elapsed: reply.elapsed,
+        })
+        .collect();
+    let acks = Gathered { replies, abandoned: gathered.abandoned };
+    let Replicated { replicas, .. } =
+        decide(acks, preference, asked, required)?;
+    Ok(Replicated { value: merge(&acked), replicas })
+}
+
+fn outcomes<O>(
+    gathered: &Gathered<usize, O>,
+    preference: &[usize],
+    asked: &[usize],
+    required: usize,
+) -> Vec<ReplicaOutcome>
+where
+    O: PartialEq,
+{
+    let winner = winner(&gathered.replies, required);
+    preference
+        .iter()
+        .map(|&node| {
+            let reply =
+                gathered.replies.iter().find(|reply| reply.target == node);
+            if let Some(reply) = reply {
+                let result = match &reply.output {
+                    Ok(output) if Some(output) == winner => {
+                        ReplicaResult::Agreed
+                    },
+                    Ok(_) => ReplicaResult::Disagreed,
+                    Err(error) if storage::is_timeout(error) => {
+                        ReplicaResult::TimedOut
+                    },
+                    Err(_) => ReplicaResult::Failed,
+                };
+                return ReplicaOutcome::new(node, result, Some(reply.elapsed));
+            }
+            let abandoned =
+                gathered.abandoned.iter().find(|(target, _)| *target == node);
+            if let Some((_, elapsed)) = abandoned {
+                ReplicaOutcome::new(
+                    node,
+                    ReplicaResult::Abandoned,
+                    Some(*elapsed),
+                )
+            } else if asked.contains(&node) {
+                ReplicaOutcome::new(node, ReplicaResult::NotContacted, None)
+            } else {
+                ReplicaOutcome::new(node, ReplicaResult::Skipped, None)
+            }
+        })
+        .collect()
+}
+
+fn winner<O>(replies: &[Reply<usize, O>], required: usize) -> Option<&O>
+where
+    O: PartialEq,
+{
+    let outputs = replies.iter().filter_map(|reply| reply.output.as_ref().ok());
+    let mut best: Option<(&O, usize)> = None;
+    for output in outputs.clone() {
+        let count = outputs.clone().filter(|other| *other == output).count();
+        if best.is_none_or(|(_, best)| count > best) {
+            best = Some((output, count));
+        }
+    }
+    best.filter(|(_, count)| *count >= required).map(|(output, _)| output)
+}
 
 #[derive(Debug)]
 pub struct Coordinator {
-    replication: usize,
-    min_correct_reads: usize,
-    min_correct_writes: usize,
+    defaults: BucketConfig,
     concurrency_level: usize,
     storage_table: Box<[StorageHandle]>,
+    leaving: Box<[bool]>,
+    peers: Option<PeerRegistry>,
+    hedging: Option<ReadHedging>,
+    breakers: Option<PeerBreakers>,
+    buckets: Option<Buckets>,
+}
+
+#[derive(Debug)]
+struct PeerRegistry {
+    registry: ActorRegistry,
+    names: Box<[(usize, String)]>,
+    generation: u64,
+}
+
+#[derive(Debug)]
+struct ReadHedging {
+    percentile: f64,
+    latencies: Box<[LatencyWindow]>,
+}
+
+impl ReadHedging {
+    fn plan(
+        &self,
+        replicas: &mut [(usize, &StorageHandle)],
+        primaries: usize,
+    ) -> Hedging {
+        replicas.sort_by_key(|(index, _)| {
+            let median = self.latencies[*index].percentile(0.5);
+            (median.is_none(), median)
+        });
+        let delay = replicas
+            .iter()
+            .take(primaries)
+            .map(|(index, _)| {
+                self.latencies[*index].percentile(self.percentile)
+            })
+            .collect::<Option<Vec<_>>>()
+            .and_then(|delays| delays.into_iter().max());
+        Hedging { initial: primaries, delay }
+    }
+
+    fn record<O>(&mut self, gathered: &Gathered<usize, O>) {
+        for reply in &gathered.replies {
+            self.latencies[reply.target].record(reply.elapsed);
+        }
+        for (target, elapsed) in &gathered.abandoned {
+            let latencies = &mut self.latencies[*target];
+            let floor = latencies.percentile(self.percentile);
+            latencies
+                .record(floor.map_or(*elapsed, |floor| floor.max(*elapsed)));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct LatencyWindow {
+    samples: VecDeque<Duration>,
+}
+
+impl LatencyWindow {
+    fn record(&mut self, elapsed: Duration) {
+        if self.samples.len() == LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(elapsed);
+    }
+
+    fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut samples: Vec<_> = self.samples.iter().copied().collect();
+        samples.sort_unstable();
+        let last = samples.len().checked_sub(1)?;
+        let rank = (percentile * last as f64).round() as usize;
+        samples.get(rank.min(last)).copied()
+    }
 }
 
 impl Coordinator {
@@ -25,13 +238,170 @@ impl Coordinator {
         concurrency_level: usize,
         nodes: impl IntoIterator<Item = StorageHandle>,
     ) -> Self {
+        let storage_table: Box<[_]> = nodes.into_iter().collect();
         Self {
-            replication,
-            min_correct_reads,
-            min_correct_writes,
+            defaults: BucketConfig {
+                replication,
+                min_correct_reads,
+                min_correct_writes,
+            },
             concurrency_level,
-            storage_table: nodes.into_iter().collect(),
+            leaving: vec![false; storage_table.len()].into(),
+            storage_table,
+            peers: None,
+            hedging: None,
+            breakers: None,
+            buckets: None,
+        }
+    }
+
+    pub fn with_buckets(mut self, buckets: Option<Buckets>) -> Self {
+        self.buckets = buckets;
+        self
+    }
+
+    pub fn with_breakers(mut self, breakers: Option<PeerBreakers>) -> Self {
+        self.breakers = breakers;
+        self
+    }
+
+    pub fn with_hedging(mut self, percentile: Option<f64>) -> Self {
+        self.hedging = percentile.map(|percentile| ReadHedging {
+            percentile,
+            latencies: vec![LatencyWindow::default(); self.storage_table.len()]
+                .into(),
+        });
+        self
+    }
+
+    pub fn with_registry(
+        mut self,
+        registry: ActorRegistry,
+        names: impl IntoIterator<Item = (usize, String)>,
+    ) -> Self {
+        let names = names.into_iter().collect();
+        self.peers = Some(PeerRegistry { registry, names, generation: 0 });
+        self.refresh_storage_table();
+        self
+    }
+
+    fn refresh_storage_table(&mut self) {
+        let Some(peers) = &mut self.peers else { return };
+        let generation = peers.registry.generation();
+        if generation == peers.generation {
+            return;
         }
+        peers.generation = generation;
+        for (index, name) in &peers.names {
+            let Some(slot) = self.storage_table.get_mut(*index) else {
+                continue;
+            };
+            if let Some(handle) = peers.registry.lookup(name) {
+                tracing::debug!(node = index, name, "resolved storage peer");
+                *slot = handle;
+            }
+        }
+    }
+
+    fn bucket_config(&self, namespace: Option<&Namespace>) -> BucketConfig {
+        namespace
+            .zip(self.buckets.as_ref())
+            .and_then(|(namespace, buckets)| buckets.get(namespace))
+            .unwrap_or(self.defaults)
+    }
+
+    fn preference_list(&self, key: &Key, replication: usize) -> Vec<usize> {
+        let i = key.partition(self.storage_table.len());
+        (0 .. replication).map(|j| (i + j) % self.storage_table.len()).collect()
+    }
+
+    fn replicas(&self, preference: &[usize]) -> Vec<(usize, &StorageHandle)> {
+        preference
+            .iter()
+            .copied()
+            .filter(|index| {
+                if self.leaving[*index] {
+                    tracing::trace!(node = index, "skipping leaving node");
+                    return false;
+                }
+                if self.is_breaker_open(*index) {
+                    tracing::trace!(node = index, "skipping open breaker");
+                    return false;
+                }
+                true
+            })
+            .map(|index| (index, &self.storage_table[index]))
+            .collect()
+    }
+
+    fn is_breaker_open(&self, index: usize) -> bool {
+        let Some(breakers) = &self.breakers else { return false };
+        breakers.get(index).is_some_and(|breaker| breaker.is_open())
+    }
+
+    async fn read(&mut self, input: Get) -> Result<GetOutput> {
+        let Get { key, namespace } = input;
+        let config = self.bucket_config(namespace.as_ref());
+        let preference = self.preference_list(&key, config.replication);
+        let mut replicas = self.replicas(&preference);
+        let asked: Vec<_> = replicas.iter().map(|(index, _)| *index).collect();
+        let hedging = self.hedging.as_ref().map(|hedging| {
+            hedging.plan(&mut replicas, config.min_correct_reads)
+        });
+        let gathered = Scatter::new(replicas)
+            .with_concurrency(self.concurrency_level)
+            .with_hedging(hedging)
+            .gather(storage::Get { key }, quorum(config.min_correct_reads))
+            .await;
+        if let Some(hedging) = &mut self.hedging {
+            hedging.record(&gathered);
+        }
+        Ok(decide(gathered, &preference, &asked, config.min_correct_reads)?)
+    }
+
+    async fn write(&mut self, input: Put) -> Result<PutOutput> {
+        let Put { key, value, namespace, origin } = input;
+        let config = self.bucket_config(namespace.as_ref());
+        let preference = self.preference_list(&key, config.replication);
+        let replicas = self.replicas(&preference);
+        let asked: Vec<_> = replicas.iter().map(|(index, _)| *index).collect();
+        let gathered = Scatter::new(replicas)
+            .with_concurrency(self.concurrency_level)
+            .gather(storage::Put { key, value, origin }, |_| false)
+            .await;
+        let required = config.min_correct_writes;
+        let output =
+            decide_acks(gathered, &preference, &asked, required, |new| {
+                new.iter().all(|&new| new)
+            })?;
+        if let Some((namespace, buckets)) =
+            namespace.as_ref().zip(self.buckets.as_ref())
+            && buckets.mark_written(namespace)
+        {
+            let buckets = buckets.clone();
+            tokio::spawn(async move {
+                if let Err(error) = buckets.persist().await {
+                    tracing::warn!(%error, "failed to persist buckets");
+                }
+            });
+        }
+        Ok(output)
+    }
+
+    async fn delete(&mut self, input: Delete) -> Result<DeleteOutput> {
+        let Delete { key, namespace } = input;
+        let config = self.bucket_config(namespace.as_ref());
+        let preference = self.preference_list(&key, config.replication);
+        let replicas = self.replicas(&preference);
+        let asked: Vec<_> = replicas.iter().map(|(index, _)| *index).collect();
+        let gathered = Scatter::new(replicas)
+            .with_concurrency(self.concurrency_level)
+            .gather(storage::Delete { key }, |_| false)
+            .await;
+        let required = config.min_correct_writes;
+        Ok(decide_acks(gathered, &preference, &asked, required, |existed| {
+            existed.iter().any(|&existed| existed)
+        })?)
     }
 }
 
@@ -39,44 +409,16 @@ impl TrivialLoopActor for Coordinator {
     type Call = CoordinatorCall;
 
     async fn on_call(&mut self, call: Self::Call) -> Result<()> {
+        self.refresh_storage_table();
         match call {
             CoordinatorCall::Get(call) => {
                 tracing::trace!(
                     key = call.input.key.to_string(),
                     "handling get coordinator request",
                 );
-                let i = call.input.key.partition(self.storage_table.len());
-                let replicators = 0 .. self.replication;
-                let mut reads = HashMap::new();
-
-                for j in replicators {
-                    let get_message =
-                        storage::Get { key: call.input.key.clone() };
-                    let index = (i + j) % self.storage_table.len();
-                    tracing::trace!(node = index, "asking node");
-                    let output =
-                        self.storage_table[index].send(get_message).await;
-                    if let Ok(data) = output {
-                        let count: &mut usize = reads.entry(data).or_default();
-                        *count += 1;
-                    }
-                }
-
-                let mut answer = None;
-                for (data, count) in reads {
-                    let has_more_votes =
-                        answer.as_ref().is_none_or(|(_, best)| count > *best);
-                    if has_more_votes && count >= self.min_correct_reads {
-                        answer = Some((data, count));
-                    }
-                }
-
-                match answer {
-                    Some((data, _)) => call.back.reply_ok(data),
-                    None => {
-                        call.reply_error(anyhow!("Failed to get consensus"))
-                    },
-                };
+                let ActorCall { input, back, .. } = call;
+                let output = self.read(input).await;
+                back.reply(output);
             },
 
             CoordinatorCall::Put(call) => {
@@ -84,49 +426,34 @@ impl TrivialLoopActor for Coordinator {
                     key = call.input.key.to_string(),
                     "handling put coordinator request",
                 );
-                let i = call.input.key.partition(self.storage_table.len());
-                let nodes = &self.storage_table;
-                let replication = self.replication;
-                let min_correct_writes = self.min_correct_writes;
-                let concurrency_level = self.concurrency_level;
-
-                call.handle(|input| async move {
-                    let input = &input;
-                    let task_stream = stream::iter(0 .. replication)
-                        .map(|j| async move {
-                            let index = (i + j) % nodes.len();
-                            tracing::trace!(node = index, "sending to node");
-                            let put_message = storage::Put {
-                                key: input.key.clone(),
-                                value: input.value.clone(),
-                            };
-                            nodes[index].send(put_message).await
-                        })
-                        .buffer_unordered(concurrency_level);
-
-                    pin!(task_stream);
-                    let mut answers = [0; 2];
-                    while let Some(result) = task_stream.next().await {
-                        if let Ok(new) = result {
-                            answers[usize::from(new)] += 1;
-                        }
-                    }
-
-                    let mut answer = None;
-                    for (i, candidate) in answers.into_iter().enumerate() {
-                        let has_more_votes =
-                            answer.is_none_or(|best| candidate > answers[best]);
-                        if has_more_votes && candidate >= min_correct_writes {
-                            answer = Some(i);
-                        }
-                    }
-
-                    match answer {
-                        Some(i) => Ok(i != 0),
-                        None => bail!("Failed to get consensus"),
-                    }
-                })
-                .await;
+                let ActorCall { input, back, .. } = call;
+                let output = self.write(input).await;
+                back.reply(output);
+            },
+
+            CoordinatorCall::Delete(call) => {
+                tracing::trace!(
+                    key = call.input.key.to_string(),
+                    "handling delete coordinator request",
+                );
+                let ActorCall { input, back, .. } = call;
+                let output = self.delete(input).await;
+                back.reply(output);
+            },
+
+            CoordinatorCall::SetPeerStatus(call) => {
+                let node_id = call.input.node_id;
+                let status = call.input.status;
+                match self.leaving.get_mut(node_id) {
+                    Some(leaving) => {
+                        tracing::info!(node = node_id, ?status, "peer status");
+                        *leaving = status == PeerStatus::Leaving;
+                        call.back.reply_ok(PeerStatusSet);
+                    },
+                    None => {
+                        call.reply_error(anyhow!("unknown node {node_id}"));
+                    },
+                }
             },
         }
 
@@ -140,23 +467,56 @@ pub type CoordinatorHandle = ActorHandle<CoordinatorCall>;
 pub enum CoordinatorCall {
     Get(GetCall),
     Put(PutCall),
+    Delete(DeleteCall),
+    #[spalhad(control)]
+    SetPeerStatus(SetPeerStatusCall),
 }
 
 #[derive(Debug, Clone)]
 pub struct Get {
     pub key: Key,
+    pub namespace: Option<Namespace>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replicated<T> {
+    pub value: T,
+    pub replicas: Vec<ReplicaOutcome>,
 }
 
-pub type GetOutput = Option<serde_json::Value>;
+pub type GetOutput = Replicated<Option<Value>>;
 
 pub type GetCall = ActorCall<Get, GetOutput>;
 
 #[derive(Debug, Clone)]
 pub struct Put {
     pub key: Key,
-    pub value: serde_json::Value,
+    pub value: Value,
+    pub namespace: Option<Namespace>,
+    pub origin: Option<KeyOrigin>,
 }
 
-pub type PutOutput = bool;
+pub type PutOutput = Replicated<bool>;
 
 pub type PutCall = ActorCall<Put, PutOutput>;
+
+#[derive(Debug, Clone)]
+pub struct Delete {
+    pub key: Key,
+    pub namespace: Option<Namespace>,
+}
+
+pub type DeleteOutput = Replicated<bool>;
+
+pub type DeleteCall = ActorCall<Delete, DeleteOutput>;
+
+#[derive(Debug, Clone)]
+pub struct SetPeerStatus {
+    pub node_id: usize,
+    pub status: PeerStatus,
+}
+
+#[derive(Debug)]
+pub struct PeerStatusSet;
+
+pub type SetPeerStatusCall = ActorCall<SetPeerStatus, PeerStatusSet>;
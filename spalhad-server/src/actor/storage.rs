@@ -1,20 +1,49 @@
 use spalhad_actor::{ActorCall, ActorHandle, CallSuperset};
-use spalhad_spec::kv::Key;
+use spalhad_spec::kv::{Key, KeyOrigin, Value};
+use tokio::time;
 
+pub use breaker::{
+    BreakerConfig,
+    BreakerSnapshot,
+    BreakerState,
+    CircuitBreaker,
+    CircuitOpen,
+    PeerBreakers,
+    is_peer_failure,
+};
 pub use client::ClientStorage;
 pub use dir::DirStorage;
 pub use memory::MemoryStorage;
+pub use rpc::RpcStorage;
 
 mod memory;
 mod dir;
 mod client;
+mod breaker;
+mod rpc;
 
 pub type StorageHandle = ActorHandle<StorageCall>;
 
+pub fn peer_name(node_id: usize) -> String {
+    format!("ClientStorage[{node_id}]")
+}
+
+pub fn is_timeout(error: &anyhow::Error) -> bool {
+    spalhad_client::is_timeout(error)
+        || error.chain().any(|cause| {
+            cause.is::<time::error::Elapsed>()
+                || cause.is::<crate::rpc::TimedOut>()
+                || cause.downcast_ref::<crate::rpc::RemoteError>().is_some_and(
+                    |error| error.0.code == spalhad_spec::ErrorCode::Timeout,
+                )
+        })
+}
+
 #[derive(Debug, CallSuperset)]
 pub enum StorageCall {
     Get(GetCall),
     Put(PutCall),
+    Delete(DeleteCall),
 }
 
 #[derive(Debug, Clone)]
@@ -22,16 +51,26 @@ pub struct Get {
     pub key: Key,
 }
 
-pub type GetOutput = Option<serde_json::Value>;
+pub type GetOutput = Option<Value>;
 
 pub type GetCall = ActorCall<Get, GetOutput>;
 
 #[derive(Debug, Clone)]
 pub struct Put {
     pub key: Key,
-    pub value: serde_json::Value,
+    pub value: Value,
+    pub origin: Option<KeyOrigin>,
 }
 
 pub type PutOutput = bool;
 
 pub type PutCall = ActorCall<Put, PutOutput>;
+
+#[derive(Debug, Clone)]
+pub struct Delete {
+    pub key: Key,
+}
+
+pub type DeleteOutput = bool;
+
+pub type DeleteCall = ActorCall<Delete, DeleteOutput>;
@@ -0,0 +1,208 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use thiserror::Error;
+use tokio::time::Instant;
+
+use super::is_timeout;
+
+pub fn is_peer_failure(error: &anyhow::Error) -> bool {
+    is_timeout(error)
+        || spalhad_client::is_transport_error(error)
+        || error.chain().any(|cause| cause.is::<io::Error>())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("circuit breaker is open")]
+pub struct CircuitOpen;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerConfig {
+    pub failure_threshold: usize,
+    pub cooldown: Duration,
+}
+
+impl BreakerConfig {
+    pub fn new(failure_threshold: usize, cooldown: Duration) -> Self {
+        Self { failure_threshold: failure_threshold.max(1), cooldown }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    Closed,
+    Open { until: Instant },
+    HalfOpen { until: Instant },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerSnapshot {
+    pub state: BreakerState,
+    pub consecutive_failures: usize,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: BreakerState,
+    consecutive_failures: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: BreakerConfig) -> Self {
+        let inner =
+            Inner { state: BreakerState::Closed, consecutive_failures: 0 };
+        Self { config, inner: Arc::new(Mutex::new(inner)) }
+    }
+
+    fn with_inner<F, T>(&self, visitor: F) -> T
+    where
+        F: FnOnce(&mut Inner) -> T,
+    {
+        visitor(&mut self.inner.lock().expect("poisoned lock"))
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.with_inner(|inner| match inner.state {
+            BreakerState::Closed => false,
+            BreakerState::Open { until } | BreakerState::HalfOpen { until } => {
+                Instant::now() < until
+            },
+        })
+    }
+
+    pub fn try_acquire(&self) -> Result<(), CircuitOpen> {
+        self.with_inner(|inner| match inner.state {
+            BreakerState::Closed => Ok(()),
+            BreakerState::Open { until } | BreakerState::HalfOpen { until }
+                if Instant::now() < until =>
+            {
+                Err(CircuitOpen)
+            },
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => {
+                tracing::debug!("circuit breaker letting a probe through");
+                let until = Instant::now() + self.config.cooldown;
+                inner.state = BreakerState::HalfOpen { until };
+                Ok(())
+            },
+        })
+    }
+
+    pub fn record_success(&self) {
+        self.with_inner(|inner| {
+            if inner.state != BreakerState::Closed {
+                tracing::info!("circuit breaker closed");
+            }
+            inner.state = BreakerState::Closed;
+            inner.consecutive_failures = 0;
+        });
+    }
+
+    pub fn record_failure(&self) {
+        self.with_inner(|inner| {
+            inner.consecutive_failures += 1;
+            let trips = match inner.state {
+                BreakerState::Closed => {
+                    inner.consecutive_failures >= self.config.failure_threshold
+                },
+                BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => {
+                    true
+                },
+            };
+            if trips {
+                tracing::warn!(
+                    failures = inner.consecutive_failures,
+                    cooldown = ?self.config.cooldown,
+                    "circuit breaker opened",
+                );
+                let until = Instant::now() + self.config.cooldown;
+                inner.state = BreakerState::Open { until };
+            }
+        });
+    }
+
+    pub fn record<T, E>(&self, result: &Result<T, E>) {
+        match result {
+            Ok(_) => self.record_success(),
+            Err(_) => self.record_failure(),
+        }
+    }
+
+    pub async fn guard<F, T>(&self, request: F) -> anyhow::Result<T>
+    where
+        F: Future<Output = anyhow::Result<T>>,
+    {
+        self.try_acquire()?;
+        let result = request.await;
+        match &result {
+            Err(error) if is_peer_failure(error) => self.record_failure(),
+            _ => self.record_success(),
+        }
+        result
+    }
+
+    pub fn snapshot(&self) -> BreakerSnapshot {
+        self.with_inner(|inner| BreakerSnapshot {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PeerBreakers {
+    config: BreakerConfig,
+    breakers: Arc<Mutex<BTreeMap<usize, CircuitBreaker>>>,
+}
+
+impl PeerBreakers {
+    pub fn new(config: BreakerConfig) -> Self {
+        Self { config, breakers: Arc::default() }
+    }
+
+    fn with_breakers<F, T>(&self, visitor: F) -> T
+    where
+        F: FnOnce(&mut BTreeMap<usize, CircuitBreaker>) -> T,
+    {
+        visitor(&mut self.breakers.lock().expect("poisoned lock"))
+    }
+
+    pub fn breaker(&self, node_id: usize) -> CircuitBreaker {
+        self.with_breakers(|breakers| {
+            breakers
+                .entry(node_id)
+                .or_insert_with(|| CircuitBreaker::new(self.config))
+                .clone()
+        })
+    }
+
+    pub fn reset(&self, node_id: usize) -> CircuitBreaker {
+        let breaker = CircuitBreaker::new(self.config);
+        self.with_breakers(|breakers| {
+            breakers.insert(node_id, breaker.clone());
+        });
+        breaker
+    }
+
+    pub fn get(&self, node_id: usize) -> Option<CircuitBreaker> {
+        self.with_breakers(|breakers| breakers.get(&node_id).cloned())
+    }
+
+    pub fn snapshot(&self) -> Vec<(usize, BreakerSnapshot)> {
+        self.with_breakers(|breakers| {
+            breakers
+                .iter()
+                .map(|(node_id, breaker)| (*node_id, breaker.snapshot()))
+                .collect()
+        })
+    }
+}
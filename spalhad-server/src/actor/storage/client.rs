@@ -2,25 +2,53 @@ use std::time::Duration;
 
 use anyhow::Result;
 use spalhad_actor::TrivialLoopActor;
-use spalhad_client::Client;
+use spalhad_client::{Client, RetryPolicy};
+use spalhad_spec::wire::WireFormat;
 
-use super::StorageCall;
+use super::{CircuitBreaker, StorageCall};
 
 #[derive(Debug, Clone)]
 pub struct ClientStorage {
     client: Client,
+    breaker: Option<CircuitBreaker>,
 }
 
 impl ClientStorage {
     pub fn open(base_url: impl Into<String>) -> Self {
-        Self { client: Client::new(base_url.into()) }
+        Self { client: Client::new(base_url.into()), breaker: None }
     }
 
     pub fn open_with_timeout(
         base_url: impl Into<String>,
         timeout: Duration,
     ) -> Result<Self> {
-        Ok(Self { client: Client::with_timeout(base_url.into(), timeout)? })
+        let client = Client::with_timeout(base_url.into(), timeout)?;
+        Ok(Self { client, breaker: None })
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.client.set_retry_policy(retry_policy);
+        self
+    }
+
+    pub fn with_wire_format(mut self, wire_format: WireFormat) -> Self {
+        self.client.set_wire_format(wire_format);
+        self
+    }
+
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    async fn guarded<F, T>(&self, request: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        match &self.breaker {
+            Some(breaker) => breaker.guard(request).await,
+            None => request.await,
+        }
     }
 }
 
@@ -35,7 +63,7 @@ impl TrivialLoopActor for ClientStorage {
                         key = input.key.to_string(),
                         "handling get client storage request",
                     );
-                    self.client.get_internal(input.key).await
+                    self.guarded(self.client.get_internal(input.key)).await
                 })
                 .await;
             },
@@ -46,7 +74,23 @@ impl TrivialLoopActor for ClientStorage {
                         key = input.key.to_string(),
                         "handling put client storage request",
                     );
-                    self.client.put_internal(input.key, input.value).await
+                    self.guarded(self.client.put_internal(
+                        input.key,
+                        input.value,
+                        input.origin,
+                    ))
+                    .await
+                })
+                .await;
+            },
+
+            StorageCall::Delete(call) => {
+                call.handle(|input| async {
+                    tracing::trace!(
+                        key = input.key.to_string(),
+                        "handling delete client storage request",
+                    );
+                    self.guarded(self.client.delete_internal(input.key)).await
                 })
                 .await;
             },
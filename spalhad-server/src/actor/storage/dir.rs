@@ -1,7 +1,14 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
+use bytes::Bytes;
 use spalhad_actor::TrivialLoopActor;
+use spalhad_spec::kv::{
+    BytesValue,
+    Key,
+    Value,
+    value::OCTET_STREAM_CONTENT_TYPE,
+};
 use tokio::{
     fs,
     io::{self, AsyncWriteExt},
@@ -9,6 +16,15 @@ use tokio::{
 
 use super::StorageCall;
 
+const VALUE_EXTENSION: &str = "value";
+const JSON_EXTENSION: &str = "json";
+const BYTES_EXTENSION: &str = "bin";
+const CONTENT_TYPE_EXTENSION: &str = "type";
+const ORIGIN_EXTENSION: &str = "origin.json";
+const TEMP_SUFFIX: &str = ".tmp";
+const JSON_HEADER: &str = "json";
+const BYTES_HEADER: &str = "bytes";
+
 #[derive(Debug, Clone)]
 pub struct DirStorage {
     dir_path: PathBuf,
@@ -33,13 +49,7 @@ impl TrivialLoopActor for DirStorage {
                         key = input.key.to_string(),
                         "handling get directory storage request",
                     );
-                    let path = dir_path.join(format!("{}.json", input.key));
-                    let value = match fs::read_to_string(&path).await {
-                        Ok(contents) => Some(serde_json::from_str(&contents)?),
-                        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
-                        Err(e) => Err(e)?,
-                    };
-                    Ok(value)
+                    read_value(dir_path, &input.key).await
                 })
                 .await;
             },
@@ -50,43 +60,161 @@ impl TrivialLoopActor for DirStorage {
                         key = input.key.to_string(),
                         "handling put directory storage request",
                     );
-                    let path = dir_path.join(format!("{}.json", input.key));
-                    let contents = serde_json::to_vec(&input.value)?;
-                    let (mut file, new) = loop {
-                        match fs::OpenOptions::new()
-                            .write(true)
-                            .create_new(true)
-                            .open(&path)
-                            .await
-                        {
-                            Ok(file) => break (file, true),
-                            Err(e)
-                                if e.kind() != io::ErrorKind::AlreadyExists =>
-                            {
-                                Err(e)?
-                            },
-                            _ => (),
-                        }
-                        match fs::OpenOptions::new()
-                            .write(true)
-                            .create(false)
-                            .open(&path)
-                            .await
-                        {
-                            Ok(file) => break (file, false),
-                            Err(e) if e.kind() != io::ErrorKind::NotFound => {
-                                Err(e)?
-                            },
-                            _ => (),
-                        }
-                    };
-                    file.write(&contents).await?;
+                    let key = &input.key;
+                    let path = entry_path(dir_path, key, VALUE_EXTENSION);
+                    let new = !entry_exists(&path).await?
+                        && !legacy_exists(dir_path, key).await?;
+                    write_entry(&path, &encode_value(&input.value)?).await?;
+                    remove_legacy(dir_path, key).await?;
+                    if let Some(origin) = &input.origin {
+                        let path = entry_path(dir_path, key, ORIGIN_EXTENSION);
+                        write_entry(&path, &serde_json::to_vec(origin)?)
+                            .await?;
+                    }
                     Ok(new)
                 })
                 .await;
             },
+
+            StorageCall::Delete(call) => {
+                call.handle(|input| async move {
+                    tracing::trace!(
+                        key = input.key.to_string(),
+                        "handling delete directory storage request",
+                    );
+                    let key = &input.key;
+                    let legacy = remove_legacy(dir_path, key).await?;
+                    let path = entry_path(dir_path, key, VALUE_EXTENSION);
+                    let existed = remove_entry(&path).await?;
+                    let path = entry_path(dir_path, key, ORIGIN_EXTENSION);
+                    remove_entry(&path).await?;
+                    Ok(existed || legacy)
+                })
+                .await;
+            },
         }
 
         Ok(())
     }
+
+    async fn on_stop(&mut self) -> Result<()> {
+        tracing::debug!("syncing storage directory");
+        fs::File::open(&self.dir_path).await?.sync_all().await?;
+        Ok(())
+    }
+}
+
+fn entry_path(dir_path: &Path, key: &Key, extension: &str) -> PathBuf {
+    dir_path.join(format!("{key}.{extension}"))
+}
+
+fn encode_value(value: &Value) -> Result<Vec<u8>> {
+    let (header, payload) = match value {
+        Value::Json(value) => {
+            (JSON_HEADER.to_owned(), serde_json::to_vec(value)?)
+        },
+        Value::Bytes(value) => {
+            if value.content_type.contains('\n') {
+                bail!("content type cannot contain a line break");
+            }
+            let header = format!("{BYTES_HEADER} {}", value.content_type);
+            (header, value.data.to_vec())
+        },
+    };
+    let mut contents = Vec::with_capacity(header.len() + 1 + payload.len());
+    contents.extend_from_slice(header.as_bytes());
+    contents.push(b'\n');
+    contents.extend_from_slice(&payload);
+    Ok(contents)
+}
+
+fn decode_value(contents: &[u8]) -> Result<Value> {
+    let split = contents
+        .iter()
+        .position(|byte| *byte == b'\n')
+        .context("stored value has no header")?;
+    let header = str::from_utf8(&contents[.. split])?;
+    let payload = &contents[split + 1 ..];
+    let content_type = match header.split_once(' ') {
+        None if header == JSON_HEADER => {
+            return Ok(Value::Json(serde_json::from_slice(payload)?));
+        },
+        Some((BYTES_HEADER, content_type)) => content_type.to_owned(),
+        _ => bail!("unknown stored value header {header:?}"),
+    };
+    let data = Bytes::copy_from_slice(payload);
+    Ok(Value::Bytes(BytesValue::new(content_type, data)))
+}
+
+async fn read_value(dir_path: &Path, key: &Key) -> Result<Option<Value>> {
+    let path = entry_path(dir_path, key, VALUE_EXTENSION);
+    if let Some(contents) = read_entry(&path).await? {
+        return Ok(Some(decode_value(&contents)?));
+    }
+    read_legacy_value(dir_path, key).await
+}
+
+async fn read_legacy_value(
+    dir_path: &Path,
+    key: &Key,
+) -> Result<Option<Value>> {
+    let path = entry_path(dir_path, key, JSON_EXTENSION);
+    if let Some(contents) = read_entry(&path).await? {
+        return Ok(Some(Value::Json(serde_json::from_slice(&contents)?)));
+    }
+    let path = entry_path(dir_path, key, BYTES_EXTENSION);
+    let Some(data) = read_entry(&path).await? else { return Ok(None) };
+    let path = entry_path(dir_path, key, CONTENT_TYPE_EXTENSION);
+    let content_type = match read_entry(&path).await? {
+        Some(content_type) => String::from_utf8(content_type)?,
+        None => OCTET_STREAM_CONTENT_TYPE.to_owned(),
+    };
+    Ok(Some(Value::Bytes(BytesValue::new(content_type, data))))
+}
+
+async fn read_entry(path: &Path) -> Result<Option<Vec<u8>>> {
+    match fs::read(path).await {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e)?,
+    }
+}
+
+async fn entry_exists(path: &Path) -> Result<bool> {
+    Ok(fs::try_exists(path).await?)
+}
+
+async fn legacy_exists(dir_path: &Path, key: &Key) -> Result<bool> {
+    let json = entry_exists(&entry_path(dir_path, key, JSON_EXTENSION)).await?;
+    let bytes =
+        entry_exists(&entry_path(dir_path, key, BYTES_EXTENSION)).await?;
+    Ok(json || bytes)
+}
+
+async fn write_entry(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(TEMP_SUFFIX);
+    let temp_path = PathBuf::from(temp_path);
+    let mut file = fs::File::create(&temp_path).await?;
+    file.write_all(contents).await?;
+    file.sync_data().await?;
+    drop(file);
+    fs::rename(&temp_path, path).await?;
+    Ok(())
+}
+
+async fn remove_legacy(dir_path: &Path, key: &Key) -> Result<bool> {
+    let json = remove_entry(&entry_path(dir_path, key, JSON_EXTENSION)).await?;
+    let bytes =
+        remove_entry(&entry_path(dir_path, key, BYTES_EXTENSION)).await?;
+    remove_entry(&entry_path(dir_path, key, CONTENT_TYPE_EXTENSION)).await?;
+    Ok(json || bytes)
+}
+
+async fn remove_entry(path: &Path) -> Result<bool> {
+    match fs::remove_file(path).await {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e)?,
+    }
 }
@@ -2,18 +2,19 @@ use std::collections::HashMap;
 
 use anyhow::Result;
 use spalhad_actor::TrivialLoopActor;
-use spalhad_spec::kv::Key;
+use spalhad_spec::kv::{Key, KeyOrigin, Value};
 
 use super::StorageCall;
 
 #[derive(Debug, Clone)]
 pub struct MemoryStorage {
-    map: HashMap<Key, serde_json::Value>,
+    map: HashMap<Key, Value>,
+    origins: HashMap<Key, KeyOrigin>,
 }
 
 impl MemoryStorage {
     pub fn open() -> Self {
-        Self { map: HashMap::new() }
+        Self { map: HashMap::new(), origins: HashMap::new() }
     }
 }
 
@@ -40,10 +41,27 @@ impl TrivialLoopActor for MemoryStorage {
                         key = input.key.to_string(),
                         "handling put memory storage request",
                     );
+                    if let Some(origin) = input.origin {
+                        self.origins.insert(input.key.clone(), origin);
+                    }
                     Ok(self.map.insert(input.key, input.value).is_none())
                 })
                 .await;
             },
+
+            StorageCall::Delete(call) => {
+                let map = &mut self.map;
+                let origins = &mut self.origins;
+                call.handle(|input| async move {
+                    tracing::trace!(
+                        key = input.key.to_string(),
+                        "handling delete memory storage request",
+                    );
+                    origins.remove(&input.key);
+                    Ok(map.remove(&input.key).is_some())
+                })
+                .await;
+            },
         }
 
         Ok(())
@@ -0,0 +1,227 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    mem,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Result, bail};
+use opentelemetry::global;
+use spalhad_actor::{ActorCall, ActorCallback, TrivialLoopActor};
+use spalhad_spec::rpc::{MAX_IN_FLIGHT, RpcCall, RpcReply};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use super::{CircuitBreaker, DeleteOutput, GetOutput, PutOutput, StorageCall};
+use crate::rpc::{ConnectFailed, Connection, RemoteError, Saturated};
+
+type Waiting = Box<dyn FnOnce(Result<&Connection>) + Send>;
+
+#[derive(Default)]
+enum Link {
+    #[default]
+    Idle,
+    Connecting(Vec<Waiting>),
+    Open(Connection),
+}
+
+impl fmt::Debug for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Idle => f.write_str("Idle"),
+            Self::Connecting(waiting) => {
+                f.debug_tuple("Connecting").field(&waiting.len()).finish()
+            },
+            Self::Open(connection) => {
+                f.debug_tuple("Open").field(connection).finish()
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RpcStorage {
+    address: Arc<str>,
+    timeout: Duration,
+    link: Arc<Mutex<Link>>,
+    breaker: Option<CircuitBreaker>,
+}
+
+impl RpcStorage {
+    pub fn open(address: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            address: address.into().into(),
+            timeout,
+            link: Arc::default(),
+            breaker: None,
+        }
+    }
+
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    fn with_connection(&self, waiting: Waiting) {
+        let mut link = self.link.lock().expect("poisoned lock");
+        match &mut *link {
+            Link::Open(connection) if !connection.is_closed() => {
+                return waiting(Ok(connection));
+            },
+            Link::Connecting(queue) if queue.len() >= MAX_IN_FLIGHT => {
+                return waiting(Err(Saturated.into()));
+            },
+            Link::Connecting(queue) => return queue.push(waiting),
+            Link::Open(_) | Link::Idle => (),
+        }
+        *link = Link::Connecting(vec![waiting]);
+        tokio::spawn(connect(
+            self.address.clone(),
+            self.timeout,
+            self.link.clone(),
+        ));
+    }
+
+    fn dispatch<O>(
+        &self,
+        call: RpcCall,
+        span: Span,
+        back: ActorCallback<O>,
+        into_output: fn(RpcReply) -> Result<O>,
+    ) where
+        O: Send + 'static,
+    {
+        let breaker = self.breaker.clone();
+        if let Some(breaker) = &breaker
+            && let Err(error) = breaker.try_acquire()
+        {
+            back.reply(Err(error.into()));
+            return;
+        }
+        let trace = trace_context(&span);
+        self.with_connection(Box::new(move |connection| {
+            let connection = match connection {
+                Ok(connection) => connection,
+                Err(error) => {
+                    let reply = Err(error);
+                    record(breaker.as_ref(), &reply);
+                    back.reply(reply);
+                    return;
+                },
+            };
+            connection.send(call, trace, move |reply| {
+                record(breaker.as_ref(), &reply);
+                back.reply(reply.and_then(into_output));
+            });
+        }));
+    }
+}
+
+async fn connect(address: Arc<str>, timeout: Duration, link: Arc<Mutex<Link>>) {
+    tracing::debug!(address = &*address, "opening rpc connection");
+    let result = Connection::connect(&address, timeout).await;
+    let mut link = link.lock().expect("poisoned lock");
+    let Link::Connecting(waiting) = mem::take(&mut *link) else {
+        unreachable!("only one rpc connection is opened at a time");
+    };
+    match result {
+        Ok(connection) => {
+            for waiting in waiting {
+                waiting(Ok(&connection));
+            }
+            *link = Link::Open(connection);
+        },
+        Err(error) => {
+            let error = ConnectFailed::new(error);
+            for waiting in waiting {
+                waiting(Err(error.clone().into()));
+            }
+        },
+    }
+}
+
+fn record<T>(breaker: Option<&CircuitBreaker>, reply: &Result<T>) {
+    let Some(breaker) = breaker else { return };
+    match reply {
+        Err(error) if error.is::<Saturated>() => (),
+        reply => breaker.record(reply),
+    }
+}
+
+fn trace_context(span: &Span) -> HashMap<String, String> {
+    let mut trace = HashMap::new();
+    let context = span.context();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut trace)
+    });
+    trace
+}
+
+fn into_get_output(reply: RpcReply) -> Result<GetOutput> {
+    match reply {
+        RpcReply::Get { value } => Ok(value),
+        RpcReply::Error(error) => Err(RemoteError(error).into()),
+        reply => bail!("unexpected rpc reply {reply:?}"),
+    }
+}
+
+fn into_put_output(reply: RpcReply) -> Result<PutOutput> {
+    match reply {
+        RpcReply::Put { new } => Ok(new),
+        RpcReply::Error(error) => Err(RemoteError(error).into()),
+        reply => bail!("unexpected rpc reply {reply:?}"),
+    }
+}
+
+fn into_delete_output(reply: RpcReply) -> Result<DeleteOutput> {
+    match reply {
+        RpcReply::Delete { existed } => Ok(existed),
+        RpcReply::Error(error) => Err(RemoteError(error).into()),
+        reply => bail!("unexpected rpc reply {reply:?}"),
+    }
+}
+
+impl TrivialLoopActor for RpcStorage {
+    type Call = StorageCall;
+
+    async fn on_call(&mut self, call: Self::Call) -> Result<()> {
+        match call {
+            StorageCall::Get(call) => {
+                tracing::trace!(
+                    key = call.input.key.to_string(),
+                    "handling get rpc storage request",
+                );
+                let ActorCall { input, back, span } = call;
+                let call = RpcCall::Get { key: input.key };
+                self.dispatch(call, span, back, into_get_output);
+            },
+
+            StorageCall::Put(call) => {
+                tracing::trace!(
+                    key = call.input.key.to_string(),
+                    "handling put rpc storage request",
+                );
+                let ActorCall { input, back, span } = call;
+                let call = RpcCall::Put {
+                    key: input.key,
+                    value: input.value,
+                    origin: input.origin,
+                };
+                self.dispatch(call, span, back, into_put_output);
+            },
+
+            StorageCall::Delete(call) => {
+                tracing::trace!(
+                    key = call.input.key.to_string(),
+                    "handling delete rpc storage request",
+                );
+                let ActorCall { input, back, span } = call;
+                let call = RpcCall::Delete { key: input.key };
+                self.dispatch(call, span, back, into_delete_output);
+            },
+        }
+
+        Ok(())
+    }
+}
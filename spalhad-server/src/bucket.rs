@@ -0,0 +1,259 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use spalhad_spec::{
+    bucket::{Bucket, BucketConfig},
+    kv::Namespace,
+};
+use thiserror::Error;
+use tokio::{sync::Mutex as AsyncMutex, task};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("bucket {0} already holds data, its replication cannot change")]
+pub struct ReplicationLocked(pub Namespace);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Persisted {
+    buckets: Vec<Bucket>,
+    written: Vec<Namespace>,
+}
+
+#[derive(Debug)]
+struct State {
+    self_id: usize,
+    nodes: usize,
+    replication: usize,
+    buckets: BTreeMap<Namespace, Bucket>,
+    written: BTreeSet<Namespace>,
+    path: Option<PathBuf>,
+}
+
+impl State {
+    fn replication(&self, name: &Namespace) -> usize {
+        self.buckets
+            .get(name)
+            .map_or(self.replication, |bucket| bucket.config.replication)
+    }
+
+    fn snapshot(&self) -> Option<(PathBuf, Persisted)> {
+        let path = self.path.clone()?;
+        let persisted = Persisted {
+            buckets: self.buckets.values().cloned().collect(),
+            written: self.written.iter().cloned().collect(),
+        };
+        Some((path, persisted))
+    }
+}
+
+fn write_persisted(path: &Path, persisted: &Persisted) -> Result<()> {
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, serde_json::to_vec(persisted)?)?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct Buckets {
+    state: Arc<Mutex<State>>,
+    persisting: Arc<AsyncMutex<()>>,
+}
+
+impl Buckets {
+    pub fn new(self_id: usize, nodes: usize, replication: usize) -> Self {
+        let state = State {
+            self_id,
+            nodes,
+            replication,
+            buckets: BTreeMap::new(),
+            written: BTreeSet::new(),
+            path: None,
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            persisting: Arc::new(AsyncMutex::new(())),
+        }
+    }
+
+    pub fn from_buckets<I>(
+        self_id: usize,
+        nodes: usize,
+        replication: usize,
+        buckets: I,
+    ) -> Result<Self>
+    where
+        I: IntoIterator<Item = Bucket>,
+    {
+        let this = Self::new(self_id, nodes, replication);
+        this.with_state(|state| {
+            for bucket in buckets {
+                validate(&bucket.config, state.nodes)?;
+                state.buckets.insert(bucket.name.clone(), bucket);
+            }
+            Ok::<_, anyhow::Error>(())
+        })?;
+        Ok(this)
+    }
+
+    pub fn open(
+        self_id: usize,
+        nodes: usize,
+        replication: usize,
+        path: impl Into<PathBuf>,
+    ) -> Result<Self> {
+        let path = path.into();
+        let persisted = match fs::read(&path) {
+            Ok(contents) => serde_json::from_slice(&contents)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Persisted::default()
+            },
+            Err(e) => Err(e)?,
+        };
+        let buckets =
+            Self::from_buckets(self_id, nodes, replication, persisted.buckets)?;
+        buckets.with_state(|state| {
+            state.written.extend(persisted.written);
+            state.path = Some(path);
+            tracing::info!(
+                buckets = state.buckets.len(),
+                "loaded persisted buckets",
+            );
+        });
+        Ok(buckets)
+    }
+
+    fn with_state<F, T>(&self, visitor: F) -> T
+    where
+        F: FnOnce(&mut State) -> T,
+    {
+        visitor(&mut self.state.lock().expect("poisoned lock"))
+    }
+
+    pub async fn persist(&self) -> Result<()> {
+        let _persisting = self.persisting.lock().await;
+        let Some((path, persisted)) = self.with_state(|state| state.snapshot())
+        else {
+            return Ok(());
+        };
+        task::spawn_blocking(move || write_persisted(&path, &persisted)).await?
+    }
+
+    pub fn get(&self, name: &Namespace) -> Option<BucketConfig> {
+        self.with_state(|state| {
+            state.buckets.get(name).map(|bucket| bucket.config)
+        })
+    }
+
+    pub fn changes_replication(
+        &self,
+        name: &Namespace,
+        config: &BucketConfig,
+    ) -> bool {
+        self.with_state(|state| state.replication(name) != config.replication)
+    }
+
+    pub async fn configure(
+        &self,
+        name: Namespace,
+        config: BucketConfig,
+    ) -> Result<Bucket> {
+        let bucket = self.with_state(|state| {
+            validate(&config, state.nodes)?;
+            if state.written.contains(&name)
+                && state.replication(&name) != config.replication
+            {
+                Err(ReplicationLocked(name.clone()))?;
+            }
+            let revision = state
+                .buckets
+                .get(&name)
+                .map_or(1, |bucket| bucket.revision + 1);
+            let bucket =
+                Bucket { name, revision, updated_by: state.self_id, config };
+            tracing::info!(
+                bucket = %bucket.name,
+                revision,
+                ?config,
+                "bucket configured",
+            );
+            state.buckets.insert(bucket.name.clone(), bucket.clone());
+            Ok::<_, anyhow::Error>(bucket)
+        })?;
+        self.persist().await?;
+        Ok(bucket)
+    }
+
+    pub async fn apply(&self, bucket: Bucket) -> Result<bool> {
+        let applied = self.with_state(|state| {
+            validate(&bucket.config, state.nodes)?;
+            if let Some(current) = state.buckets.get(&bucket.name)
+                && !bucket.supersedes(current)
+            {
+                return Ok::<_, anyhow::Error>(false);
+            }
+            tracing::debug!(
+                bucket = %bucket.name,
+                revision = bucket.revision,
+                "bucket synced",
+            );
+            state.buckets.insert(bucket.name.clone(), bucket);
+            Ok(true)
+        })?;
+        if applied {
+            self.persist().await?;
+        }
+        Ok(applied)
+    }
+
+    pub fn mark_written(&self, name: &Namespace) -> bool {
+        self.with_state(|state| {
+            let marked = state.written.insert(name.clone());
+            if marked {
+                tracing::debug!(bucket = %name, "bucket holds data");
+            }
+            marked
+        })
+    }
+
+    pub async fn merge_written<I>(&self, names: I) -> Result<()>
+    where
+        I: IntoIterator<Item = Namespace>,
+    {
+        let merged = self.with_state(|state| {
+            let before = state.written.len();
+            state.written.extend(names);
+            state.written.len() != before
+        });
+        if merged {
+            self.persist().await?;
+        }
+        Ok(())
+    }
+
+    pub fn written(&self) -> Vec<Namespace> {
+        self.with_state(|state| state.written.iter().cloned().collect())
+    }
+
+    pub fn snapshot(&self) -> Vec<Bucket> {
+        self.with_state(|state| state.buckets.values().cloned().collect())
+    }
+}
+
+fn validate(config: &BucketConfig, nodes: usize) -> Result<()> {
+    if !(1 ..= nodes).contains(&config.replication) {
+        bail!("replication must be between 1 and {nodes}");
+    }
+    if !(1 ..= config.replication).contains(&config.min_correct_reads) {
+        bail!("min_correct_reads must be between 1 and replication");
+    }
+    if !(1 ..= config.replication).contains(&config.min_correct_writes) {
+        bail!("min_correct_writes must be between 1 and replication");
+    }
+    Ok(())
+}
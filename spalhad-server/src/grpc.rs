@@ -0,0 +1,279 @@
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+use futures::{Stream, future, stream};
+use spalhad_spec::kv::Value;
+use tokio::{
+    select,
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time,
+};
+use tokio_util::sync::CancellationToken;
+use tonic::{Code, Request, Response, Status, service::Routes};
+
+use crate::actor::{bouncer::BouncerHandle, coordinator};
+
+pub use proto::kv_server::KvServer;
+
+mod convert;
+
+pub mod proto {
+    tonic::include_proto!("spalhad.v1");
+}
+
+const MAX_BATCH_LEN: usize = 1024;
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+pub const MAX_WATCHERS: usize = 256;
+
+pub type WatchStream =
+    Pin<Box<dyn Stream<Item = Result<proto::WatchEvent, Status>> + Send>>;
+
+#[derive(Debug, Clone)]
+pub struct KvService {
+    bouncer: BouncerHandle,
+    cancellation_token: CancellationToken,
+    watchers: Arc<Semaphore>,
+}
+
+impl KvService {
+    pub fn new(
+        bouncer: BouncerHandle,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        let watchers = Arc::new(Semaphore::new(MAX_WATCHERS));
+        Self { bouncer, cancellation_token, watchers }
+    }
+
+    async fn read(
+        &self,
+        target: &convert::Target,
+    ) -> Result<Option<Value>, Status> {
+        self.bouncer
+            .send(coordinator::Get {
+                key: target.key.clone(),
+                namespace: target.namespace.clone(),
+            })
+            .await
+            .map(|output| output.value)
+            .map_err(convert::status)
+    }
+
+    async fn handle_get(
+        &self,
+        request: proto::GetRequest,
+    ) -> Result<proto::GetResponse, Status> {
+        let target = convert::target(request.key, false)?;
+        let value = self.read(&target).await?;
+        Ok(proto::GetResponse { value: value.map(convert::from_value) })
+    }
+
+    async fn handle_put(
+        &self,
+        request: proto::PutRequest,
+    ) -> Result<proto::PutResponse, Status> {
+        let target = convert::target(request.key, request.store_original_key)?;
+        let value = convert::into_value(request.value)?;
+        let new = self
+            .bouncer
+            .send(coordinator::Put {
+                key: target.key,
+                value,
+                namespace: target.namespace,
+                origin: target.origin,
+            })
+            .await
+            .map_err(convert::status)?
+            .value;
+        Ok(proto::PutResponse { new })
+    }
+
+    async fn handle_delete(
+        &self,
+        request: proto::DeleteRequest,
+    ) -> Result<proto::DeleteResponse, Status> {
+        let target = convert::target(request.key, false)?;
+        let existed = self
+            .bouncer
+            .send(coordinator::Delete {
+                key: target.key,
+                namespace: target.namespace,
+            })
+            .await
+            .map_err(convert::status)?
+            .value;
+        Ok(proto::DeleteResponse { existed })
+    }
+
+    async fn handle_operation(
+        &self,
+        operation: proto::Operation,
+    ) -> proto::OperationResult {
+        use proto::{operation, operation_result};
+
+        let result = match operation.kind {
+            Some(operation::Kind::Get(request)) => {
+                self.handle_get(request).await.map(operation_result::Kind::Get)
+            },
+            Some(operation::Kind::Put(request)) => {
+                self.handle_put(request).await.map(operation_result::Kind::Put)
+            },
+            Some(operation::Kind::Delete(request)) => self
+                .handle_delete(request)
+                .await
+                .map(operation_result::Kind::Delete),
+            None => Err(Status::invalid_argument("operation is missing")),
+        };
+        let kind = result.unwrap_or_else(|status| {
+            operation_result::Kind::Error(proto::OperationError {
+                code: status.code().into(),
+                message: status.message().to_owned(),
+            })
+        });
+        proto::OperationResult { kind: Some(kind) }
+    }
+}
+
+pub fn router(service: KvService) -> axum::Router {
+    Routes::new(KvServer::new(service)).into_axum_router()
+}
+
+#[tonic::async_trait]
+impl proto::kv_server::Kv for KvService {
+    async fn get(
+        &self,
+        request: Request<proto::GetRequest>,
+    ) -> Result<Response<proto::GetResponse>, Status> {
+        self.handle_get(request.into_inner()).await.map(Response::new)
+    }
+
+    async fn put(
+        &self,
+        request: Request<proto::PutRequest>,
+    ) -> Result<Response<proto::PutResponse>, Status> {
+        self.handle_put(request.into_inner()).await.map(Response::new)
+    }
+
+    async fn delete(
+        &self,
+        request: Request<proto::DeleteRequest>,
+    ) -> Result<Response<proto::DeleteResponse>, Status> {
+        self.handle_delete(request.into_inner()).await.map(Response::new)
+    }
+
+    async fn batch(
+        &self,
+        request: Request<proto::BatchRequest>,
+    ) -> Result<Response<proto::BatchResponse>, Status> {
+        let operations = request.into_inner().operations;
+        if operations.len() > MAX_BATCH_LEN {
+            return Err(Status::invalid_argument(format!(
+                "batch has more than {MAX_BATCH_LEN} operations"
+            )));
+        }
+        let results = future::join_all(
+            operations
+                .into_iter()
+                .map(|operation| self.handle_operation(operation)),
+        )
+        .await;
+        Ok(Response::new(proto::BatchResponse { results }))
+    }
+
+    type WatchStream = WatchStream;
+
+    async fn watch(
+        &self,
+        request: Request<proto::WatchRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let request = request.into_inner();
+        let target = convert::target(request.key, false)?;
+        let interval = match request.poll_interval_ms {
+            0 => DEFAULT_POLL_INTERVAL,
+            millis => Duration::from_millis(millis.into()),
+        }
+        .max(MIN_POLL_INTERVAL);
+        let permit =
+            self.watchers.clone().try_acquire_owned().map_err(|_| {
+                Status::resource_exhausted(format!(
+                    "more than {MAX_WATCHERS} watches are open"
+                ))
+            })?;
+        let watch = Watch {
+            service: self.clone(),
+            target,
+            interval,
+            last: None,
+            failures: 0,
+            done: false,
+            _permit: permit,
+        };
+        Ok(Response::new(Box::pin(stream::unfold(watch, Watch::next))))
+    }
+}
+
+struct Watch {
+    service: KvService,
+    target: convert::Target,
+    interval: Duration,
+    last: Option<Option<Value>>,
+    failures: u32,
+    done: bool,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Watch {
+    fn delay(&self) -> Duration {
+        let backoff = 2u32.saturating_pow(self.failures.min(16));
+        let cap = MAX_RETRY_BACKOFF.max(self.interval);
+        self.interval.saturating_mul(backoff).min(cap)
+    }
+
+    async fn next(
+        mut self,
+    ) -> Option<(Result<proto::WatchEvent, Status>, Self)> {
+        if self.done {
+            return None;
+        }
+        loop {
+            if self.last.is_some() || self.failures > 0 {
+                select! {
+                    _ = self.service.cancellation_token.cancelled() => {
+                        return None;
+                    },
+                    _ = time::sleep(self.delay()) => (),
+                }
+            }
+            let value = match self.service.read(&self.target).await {
+                Ok(value) => value,
+                Err(status) if is_transient(&status) => {
+                    self.failures += 1;
+                    tracing::debug!(
+                        failures = self.failures,
+                        %status,
+                        "watch read failed, retrying",
+                    );
+                    continue;
+                },
+                Err(status) => {
+                    self.done = true;
+                    return Some((Err(status), self));
+                },
+            };
+            self.failures = 0;
+            if self.last.as_ref() != Some(&value) {
+                self.last = Some(value.clone());
+                let value = value.map(convert::from_value);
+                return Some((Ok(proto::WatchEvent { value }), self));
+            }
+        }
+    }
+}
+
+fn is_transient(status: &Status) -> bool {
+    matches!(status.code(), Code::Unavailable | Code::DeadlineExceeded)
+}
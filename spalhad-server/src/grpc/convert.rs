@@ -0,0 +1,113 @@
+use spalhad_spec::{
+    ErrorCode,
+    kv::{BytesValue, Key, KeyOrigin, Namespace, Value},
+};
+use tonic::{Code, Status};
+
+use super::proto::{self, key_ref, value};
+use crate::http::error;
+
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub key: Key,
+    pub namespace: Option<Namespace>,
+    pub origin: Option<KeyOrigin>,
+}
+
+pub fn target(
+    key: Option<proto::KeyRef>,
+    store_original_key: bool,
+) -> Result<Target, Status> {
+    let key = key.ok_or_else(|| Status::invalid_argument("key is missing"))?;
+    let namespace = match key.namespace.as_str() {
+        "" => None,
+        namespace => Some(
+            namespace
+                .parse::<Namespace>()
+                .map_err(|error| Status::invalid_argument(error.to_string()))?,
+        ),
+    };
+    match (key.key, namespace) {
+        (None, _) => Err(Status::invalid_argument("key is missing")),
+        (Some(key_ref::Key::Hash(_)), Some(_)) => {
+            Err(Status::invalid_argument(
+                "namespaced keys cannot be given as hashes",
+            ))
+        },
+        (Some(key_ref::Key::Hash(hash)), None) => {
+            let key = hash.parse().map_err(
+                |error: spalhad_spec::kv::key::ParseKeyError| {
+                    Status::invalid_argument(error.to_string())
+                },
+            )?;
+            Ok(Target { key, namespace: None, origin: None })
+        },
+        (Some(key_ref::Key::Data(data)), Some(namespace)) => {
+            let data = String::from_utf8(data).map_err(|_| {
+                Status::invalid_argument("namespaced keys must be UTF-8")
+            })?;
+            let origin = KeyOrigin::namespaced(namespace.clone(), data);
+            Ok(Target {
+                key: origin.to_key(),
+                namespace: Some(namespace),
+                origin: Some(origin),
+            })
+        },
+        (Some(key_ref::Key::Data(data)), None) => {
+            let key = Key::from_bytes_key(&data);
+            let origin = store_original_key
+                .then(|| String::from_utf8(data).ok())
+                .flatten()
+                .map(KeyOrigin::new);
+            Ok(Target { key, namespace: None, origin })
+        },
+    }
+}
+
+pub fn into_value(value: Option<proto::Value>) -> Result<Value, Status> {
+    match value.and_then(|value| value.kind) {
+        Some(value::Kind::Json(json)) => serde_json::from_str(&json)
+            .map(Value::Json)
+            .map_err(|error| Status::invalid_argument(error.to_string())),
+        Some(value::Kind::Binary(binary)) => {
+            let value = BytesValue::new(binary.content_type, binary.data);
+            Ok(Value::Bytes(value))
+        },
+        None => Err(Status::invalid_argument("value is missing")),
+    }
+}
+
+pub fn from_value(value: Value) -> proto::Value {
+    let kind = match value {
+        Value::Json(json) => value::Kind::Json(json.to_string()),
+        Value::Bytes(bytes) => value::Kind::Binary(proto::BinaryValue {
+            content_type: bytes.content_type,
+            data: bytes.data.into(),
+        }),
+    };
+    proto::Value { kind: Some(kind) }
+}
+
+pub fn status(error: anyhow::Error) -> Status {
+    let message =
+        error.chain().map(ToString::to_string).collect::<Vec<_>>().join(": ");
+    let code = match error::error_code(&error) {
+        Some(ErrorCode::NotFound) => Code::NotFound,
+        Some(
+            ErrorCode::NotActive
+            | ErrorCode::QuorumFailed
+            | ErrorCode::Unavailable,
+        ) => Code::Unavailable,
+        Some(ErrorCode::Timeout) => Code::DeadlineExceeded,
+        Some(
+            ErrorCode::InvalidKey
+            | ErrorCode::InvalidRequest
+            | ErrorCode::UnsupportedMediaType,
+        ) => Code::InvalidArgument,
+        Some(ErrorCode::BadRunId) => Code::FailedPrecondition,
+        Some(ErrorCode::Forbidden) => Code::PermissionDenied,
+        Some(ErrorCode::Conflict) => Code::Aborted,
+        Some(ErrorCode::Internal | ErrorCode::Unknown) | None => Code::Internal,
+    };
+    Status::new(code, message)
+}
@@ -1,12 +1,27 @@
 use anyhow::Result;
-use axum::Router;
+use axum::{
+    Router,
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware,
+    response::Response,
+};
+use opentelemetry::global;
+use opentelemetry_http::HeaderExtractor;
+use spalhad_spec::cluster::TOPOLOGY_VERSION_HEADER;
 use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
 use tower_http::trace::TraceLayer;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 pub use app::App;
 
-mod error;
+pub(crate) mod error;
 mod app;
+mod debug;
+mod value;
+mod wire;
 
 pub mod v1;
 
@@ -14,12 +29,64 @@ pub fn router() -> Router<App> {
     Router::new().nest("/spalhad", Router::new().nest("/v1", v1::router()))
 }
 
-pub async fn serve(bind_address: &str, router: Router) -> Result<()> {
+pub fn app_router(app: App) -> Router {
+    router()
+        .layer(middleware::map_response_with_state(
+            app.clone(),
+            topology_version,
+        ))
+        .with_state(app)
+}
+
+async fn topology_version(
+    State(app): State<App>,
+    mut response: Response,
+) -> Response {
+    if let Some(topology) = app.topology() {
+        let version = HeaderValue::from(topology.version());
+        response.headers_mut().insert(TOPOLOGY_VERSION_HEADER, version);
+    }
+    response
+}
+
+fn make_request_span(request: &Request) -> Span {
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+    );
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    if let Err(error) = span.set_parent(parent) {
+        tracing::debug!(%error, "could not link request to remote trace");
+    }
+    span
+}
+
+pub async fn serve(
+    bind_address: &str,
+    router: Router,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
     tracing::info!(%bind_address, "binding server socket listener...");
     let listener = TcpListener::bind(bind_address).await?;
     tracing::info!("socket bound.");
+    serve_listener(listener, router, cancellation_token).await
+}
+
+pub async fn serve_listener(
+    listener: TcpListener,
+    router: Router,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
     tracing::info!("serving...");
-    let router = router.route_layer(TraceLayer::new_for_http());
-    axum::serve(listener, router).await?;
+    let router = router.route_layer(
+        TraceLayer::new_for_http().make_span_with(make_request_span),
+    );
+    axum::serve(listener, router)
+        .with_graceful_shutdown(cancellation_token.cancelled_owned())
+        .await?;
+    tracing::info!("server stopped, in-flight requests finished.");
     Ok(())
 }
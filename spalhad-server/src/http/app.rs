@@ -1,28 +1,148 @@
-use spalhad_actor::ActorOptions;
-use spalhad_spec::cluster::RunId;
+use std::time::Duration;
 
-use crate::actor::{
-    bouncer::{Bouncer, BouncerHandle},
-    coordinator::CoordinatorHandle,
-    storage::StorageHandle,
+use spalhad_actor::{ActorMetricsRegistry, ActorOptions, ActorRegistry};
+use spalhad_client::RetryPolicy;
+use spalhad_spec::{
+    cluster::{PeerTransport, RunId},
+    wire::WireFormat,
+};
+
+use crate::{
+    actor::{
+        bouncer::{Bouncer, BouncerHandle},
+        coordinator::CoordinatorHandle,
+        storage::{PeerBreakers, StorageHandle},
+    },
+    bucket::Buckets,
+    topology::Topology,
 };
 
 #[derive(Debug, Clone)]
 pub struct App {
     bouncer: BouncerHandle,
     run_id: RunId,
+    actor_options: ActorOptions,
+    peer_timeout: Option<Duration>,
+    peer_breakers: Option<PeerBreakers>,
+    peer_retry_policy: Option<RetryPolicy>,
+    peer_wire_format: Option<WireFormat>,
+    peer_transport: PeerTransport,
+    topology: Option<Topology>,
+    buckets: Option<Buckets>,
 }
 
 impl App {
     pub fn new(
-        storage_options: &ActorOptions<'_>,
+        storage_options: &ActorOptions,
         storage: StorageHandle,
         coordinator: CoordinatorHandle,
     ) -> Self {
         let run_id = RunId::generate();
         let bouncer_actor = Bouncer::open(run_id, storage, coordinator);
         let bouncer = storage_options.spawn(bouncer_actor);
-        Self { bouncer, run_id }
+        Self {
+            bouncer,
+            run_id,
+            actor_options: storage_options.clone(),
+            peer_timeout: None,
+            peer_breakers: None,
+            peer_retry_policy: None,
+            peer_wire_format: None,
+            peer_transport: PeerTransport::default(),
+            topology: None,
+            buckets: None,
+        }
+    }
+
+    pub fn set_peer_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.peer_timeout = timeout;
+        self
+    }
+
+    pub fn with_peer_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.set_peer_timeout(timeout);
+        self
+    }
+
+    pub fn set_peer_breakers(
+        &mut self,
+        breakers: Option<PeerBreakers>,
+    ) -> &mut Self {
+        self.peer_breakers = breakers;
+        self
+    }
+
+    pub fn with_peer_breakers(
+        mut self,
+        breakers: Option<PeerBreakers>,
+    ) -> Self {
+        self.set_peer_breakers(breakers);
+        self
+    }
+
+    pub fn set_peer_retry_policy(
+        &mut self,
+        retry_policy: Option<RetryPolicy>,
+    ) -> &mut Self {
+        self.peer_retry_policy = retry_policy;
+        self
+    }
+
+    pub fn with_peer_retry_policy(
+        mut self,
+        retry_policy: Option<RetryPolicy>,
+    ) -> Self {
+        self.set_peer_retry_policy(retry_policy);
+        self
+    }
+
+    pub fn set_peer_wire_format(
+        &mut self,
+        wire_format: Option<WireFormat>,
+    ) -> &mut Self {
+        self.peer_wire_format = wire_format;
+        self
+    }
+
+    pub fn with_peer_wire_format(
+        mut self,
+        wire_format: Option<WireFormat>,
+    ) -> Self {
+        self.set_peer_wire_format(wire_format);
+        self
+    }
+
+    pub fn set_peer_transport(
+        &mut self,
+        transport: PeerTransport,
+    ) -> &mut Self {
+        self.peer_transport = transport;
+        self
+    }
+
+    pub fn with_peer_transport(mut self, transport: PeerTransport) -> Self {
+        self.set_peer_transport(transport);
+        self
+    }
+
+    pub fn set_topology(&mut self, topology: Option<Topology>) -> &mut Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_topology(mut self, topology: Option<Topology>) -> Self {
+        self.set_topology(topology);
+        self
+    }
+
+    pub fn set_buckets(&mut self, buckets: Option<Buckets>) -> &mut Self {
+        self.buckets = buckets;
+        self
+    }
+
+    pub fn with_buckets(mut self, buckets: Option<Buckets>) -> Self {
+        self.set_buckets(buckets);
+        self
     }
 
     pub fn bouncer(&self) -> &BouncerHandle {
@@ -32,4 +152,44 @@ impl App {
     pub fn self_run_id(&self) -> RunId {
         self.run_id
     }
+
+    pub fn actor_options(&self) -> &ActorOptions {
+        &self.actor_options
+    }
+
+    pub fn actor_metrics(&self) -> Option<&ActorMetricsRegistry> {
+        self.actor_options.metrics()
+    }
+
+    pub fn actor_registry(&self) -> Option<&ActorRegistry> {
+        self.actor_options.registry()
+    }
+
+    pub fn peer_timeout(&self) -> Option<Duration> {
+        self.peer_timeout
+    }
+
+    pub fn peer_breakers(&self) -> Option<&PeerBreakers> {
+        self.peer_breakers.as_ref()
+    }
+
+    pub fn peer_retry_policy(&self) -> Option<&RetryPolicy> {
+        self.peer_retry_policy.as_ref()
+    }
+
+    pub fn peer_wire_format(&self) -> Option<WireFormat> {
+        self.peer_wire_format
+    }
+
+    pub fn peer_transport(&self) -> PeerTransport {
+        self.peer_transport
+    }
+
+    pub fn topology(&self) -> Option<&Topology> {
+        self.topology.as_ref()
+    }
+
+    pub fn buckets(&self) -> Option<&Buckets> {
+        self.buckets.as_ref()
+    }
 }
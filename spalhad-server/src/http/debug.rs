@@ -0,0 +1,70 @@
+use axum::{
+    extract::FromRequestParts,
+    http::{HeaderValue, request::Parts},
+    response::{IntoResponse, Response},
+};
+use spalhad_spec::replica::{
+    DEBUG_HEADER,
+    DEBUG_REPLICAS,
+    REPLICAS_HEADER,
+    ReplicaOutcome,
+};
+
+use crate::http::error::HttpError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DebugReplicas(pub bool);
+
+impl DebugReplicas {
+    pub fn attach<T>(
+        self,
+        replicas: Vec<ReplicaOutcome>,
+        response: T,
+    ) -> WithReplicas<T> {
+        WithReplicas { replicas: self.0.then_some(replicas), response }
+    }
+}
+
+impl<S> FromRequestParts<S> for DebugReplicas
+where
+    S: Send + Sync,
+{
+    type Rejection = HttpError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let enabled = parts
+            .headers
+            .get_all(DEBUG_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|flag| flag.trim().eq_ignore_ascii_case(DEBUG_REPLICAS));
+        Ok(Self(enabled))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WithReplicas<T> {
+    replicas: Option<Vec<ReplicaOutcome>>,
+    response: T,
+}
+
+impl<T> IntoResponse for WithReplicas<T>
+where
+    T: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        let mut response = self.response.into_response();
+        let header = self
+            .replicas
+            .and_then(|replicas| serde_json::to_string(&replicas).ok())
+            .and_then(|replicas| HeaderValue::from_str(&replicas).ok());
+        if let Some(header) = header {
+            response.headers_mut().insert(REPLICAS_HEADER, header);
+        }
+        response
+    }
+}
@@ -1,18 +1,32 @@
 use axum::{Json, http::StatusCode};
-
+use spalhad_client::ResponseError;
 pub use spalhad_spec::Error;
+use spalhad_spec::{
+    ErrorCode,
+    kv::{key::ParseKeyError, namespace::ParseNamespaceError},
+};
+use tokio::time::error::Elapsed;
+
+use crate::{
+    actor::{bouncer, coordinator::QuorumError, storage::CircuitOpen},
+    rpc,
+};
 
-use crate::actor::bouncer;
+pub type HttpError = (StatusCode, Json<Error>);
 
-pub type HttpResult<T, E = (StatusCode, Json<Error>)> = Result<Json<T>, E>;
+pub type HttpResult<T, E = HttpError> = Result<Json<T>, E>;
 
 pub fn make_response(
     status: StatusCode,
 ) -> impl FnOnce(anyhow::Error) -> (StatusCode, Json<Error>) + Send + 'static {
-    move |error| {
-        let trace = error.chain().map(ToString::to_string).collect();
-        (status, Json(Error { trace }))
-    }
+    make_coded_response(status, status_error_code(status))
+}
+
+pub fn make_coded_response(
+    status: StatusCode,
+    fallback_code: ErrorCode,
+) -> impl FnOnce(anyhow::Error) -> (StatusCode, Json<Error>) + Send + 'static {
+    move |error| (status, Json(describe(&error, fallback_code)))
 }
 
 pub fn when_not_bouncer(
@@ -26,3 +40,59 @@ pub fn when_not_bouncer(
         }
     }
 }
+
+pub fn describe(error: &anyhow::Error, fallback_code: ErrorCode) -> Error {
+    let trace = error.chain().map(ToString::to_string).collect();
+    let code = error_code(error).unwrap_or(fallback_code);
+    let described = Error::new(code, trace);
+    match error.chain().find_map(|cause| cause.downcast_ref::<QuorumError>()) {
+        Some(quorum) => described
+            .with_quorum(Some(quorum.details))
+            .with_replicas(quorum.replicas.clone()),
+        None => described,
+    }
+}
+
+pub fn error_code(error: &anyhow::Error) -> Option<ErrorCode> {
+    error.chain().find_map(|cause| {
+        if let Some(error) = cause.downcast_ref::<bouncer::Error>() {
+            let code = match error {
+                bouncer::Error::AlreadyActive => ErrorCode::Conflict,
+                bouncer::Error::BadRunId => ErrorCode::BadRunId,
+                bouncer::Error::NotActive => ErrorCode::NotActive,
+            };
+            Some(code)
+        } else if let Some(error) = cause.downcast_ref::<ResponseError>() {
+            Some(error.code())
+        } else if let Some(error) = cause.downcast_ref::<rpc::RemoteError>() {
+            Some(error.0.code)
+        } else if cause.is::<QuorumError>() {
+            Some(ErrorCode::QuorumFailed)
+        } else if cause.is::<CircuitOpen>() || cause.is::<rpc::Saturated>() {
+            Some(ErrorCode::Unavailable)
+        } else if cause.is::<Elapsed>() || cause.is::<rpc::TimedOut>() {
+            Some(ErrorCode::Timeout)
+        } else if cause.is::<ParseKeyError>()
+            || cause.is::<ParseNamespaceError>()
+        {
+            Some(ErrorCode::InvalidKey)
+        } else {
+            None
+        }
+    })
+}
+
+fn status_error_code(status: StatusCode) -> ErrorCode {
+    match status {
+        StatusCode::NOT_FOUND => ErrorCode::NotFound,
+        StatusCode::BAD_REQUEST => ErrorCode::InvalidRequest,
+        StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+        StatusCode::CONFLICT => ErrorCode::Conflict,
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => ErrorCode::UnsupportedMediaType,
+        StatusCode::SERVICE_UNAVAILABLE => ErrorCode::Unavailable,
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => {
+            ErrorCode::Timeout
+        },
+        _ => ErrorCode::Internal,
+    }
+}
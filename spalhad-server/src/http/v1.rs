@@ -3,12 +3,16 @@ use axum::Router;
 use crate::http::App;
 
 pub mod kv;
+pub mod ns;
 pub mod sync;
 pub mod internal;
+pub mod admin;
 
 pub fn router() -> Router<App> {
     Router::new()
         .nest("/kv", kv::router())
+        .nest("/ns", ns::router())
         .nest("/sync", sync::router())
         .nest("/internal/kv", internal::router())
+        .nest("/admin", admin::router())
 }
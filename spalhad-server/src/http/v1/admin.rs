@@ -0,0 +1,272 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow, bail};
+use axum::{
+    Json,
+    Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, put},
+};
+use spalhad_actor::ActorMetricsSnapshot;
+use spalhad_spec::{
+    admin::{
+        ActorStats,
+        ActorsResponse,
+        BreakerStats,
+        BreakerStatus,
+        BreakersResponse,
+        LatencyBucket,
+        LatencyStats,
+        RegisteredActor,
+        RegistryResponse,
+        ReplacePeerRequest,
+        ReplacePeerResponse,
+    },
+    bucket::{BucketsResponse, PutBucketRequest, PutBucketResponse},
+    cluster::PeerTransport,
+    kv::Namespace,
+};
+use tokio::time::Instant;
+
+use crate::{
+    actor::storage::{
+        self,
+        BreakerSnapshot,
+        BreakerState,
+        ClientStorage,
+        RpcStorage,
+        StorageCall,
+    },
+    bucket::ReplicationLocked,
+    http::{
+        App,
+        error::{self, HttpResult},
+    },
+    sync,
+};
+
+const DEFAULT_PEER_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn router() -> Router<App> {
+    Router::new()
+        .route("/actors", get(actors))
+        .route("/registry", get(registry))
+        .route("/peers", put(replace_peer))
+        .route("/breakers", get(breakers))
+        .route("/buckets", get(buckets))
+        .route("/buckets/{name}", put(put_bucket))
+}
+
+async fn actors(State(app): State<App>) -> HttpResult<ActorsResponse> {
+    let registry = app
+        .actor_metrics()
+        .context("actor metrics are disabled")
+        .map_err(error::make_response(StatusCode::NOT_FOUND))?;
+    let actors = registry.snapshot().into_iter().map(actor_stats).collect();
+    Ok(Json(ActorsResponse { actors }))
+}
+
+async fn registry(State(app): State<App>) -> HttpResult<RegistryResponse> {
+    let registry = app
+        .actor_registry()
+        .context("actor registry is disabled")
+        .map_err(error::make_response(StatusCode::NOT_FOUND))?;
+    let actors = registry
+        .list()
+        .into_iter()
+        .map(|actor| RegisteredActor {
+            name: actor.name,
+            call_type: actor.call_type.to_owned(),
+            closed: actor.closed,
+        })
+        .collect();
+    Ok(Json(RegistryResponse { actors }))
+}
+
+async fn replace_peer(
+    State(app): State<App>,
+    Json(body): Json<ReplacePeerRequest>,
+) -> HttpResult<ReplacePeerResponse> {
+    spawn_peer(&app, &body)
+        .map_err(error::make_response(StatusCode::BAD_REQUEST))?;
+    if let Some(topology) = app.topology() {
+        topology
+            .set_address(body.node_id, &body.address)
+            .map_err(error::make_response(StatusCode::BAD_REQUEST))?;
+    }
+    Ok(Json(body))
+}
+
+fn spawn_peer(app: &App, request: &ReplacePeerRequest) -> Result<()> {
+    let registry =
+        app.actor_registry().context("actor registry is disabled")?;
+    let name = storage::peer_name(request.node_id);
+    if registry.lookup::<StorageCall>(&name).is_none() {
+        bail!("node {} is not a known peer", request.node_id);
+    }
+    let breaker =
+        app.peer_breakers().map(|breakers| breakers.reset(request.node_id));
+    match app.peer_transport() {
+        PeerTransport::Http => {
+            let mut peer = match app.peer_timeout() {
+                Some(timeout) => {
+                    ClientStorage::open_with_timeout(&request.address, timeout)?
+                },
+                None => ClientStorage::open(&request.address),
+            };
+            if let Some(retry_policy) = app.peer_retry_policy() {
+                peer = peer.with_retry_policy(*retry_policy);
+            }
+            if let Some(wire_format) = app.peer_wire_format() {
+                peer = peer.with_wire_format(wire_format);
+            }
+            if let Some(breaker) = breaker {
+                peer = peer.with_circuit_breaker(breaker);
+            }
+            tracing::info!(
+                node = request.node_id,
+                address = request.address,
+                "replacing peer storage",
+            );
+            app.actor_options().spawn_named(name, peer);
+        },
+        PeerTransport::Rpc => {
+            let rpc_address = request.rpc_address.as_deref().context(
+                "peers use the rpc transport, rpc_address is missing",
+            )?;
+            let timeout = app.peer_timeout().unwrap_or(DEFAULT_PEER_TIMEOUT);
+            let mut peer = RpcStorage::open(rpc_address, timeout);
+            if let Some(breaker) = breaker {
+                peer = peer.with_circuit_breaker(breaker);
+            }
+            tracing::info!(
+                node = request.node_id,
+                address = request.address,
+                rpc_address,
+                "replacing peer storage",
+            );
+            app.actor_options().spawn_named(name, peer);
+        },
+    }
+    Ok(())
+}
+
+async fn breakers(State(app): State<App>) -> HttpResult<BreakersResponse> {
+    let breakers = app
+        .peer_breakers()
+        .context("circuit breakers are disabled")
+        .map_err(error::make_response(StatusCode::NOT_FOUND))?;
+    let breakers = breakers
+        .snapshot()
+        .into_iter()
+        .map(|(node_id, snapshot)| breaker_stats(node_id, snapshot))
+        .collect();
+    Ok(Json(BreakersResponse { breakers }))
+}
+
+async fn buckets(State(app): State<App>) -> HttpResult<BucketsResponse> {
+    let buckets = app
+        .buckets()
+        .context("buckets are disabled")
+        .map_err(error::make_response(StatusCode::NOT_FOUND))?;
+    Ok(Json(BucketsResponse {
+        buckets: buckets.snapshot(),
+        written: buckets.written(),
+    }))
+}
+
+async fn put_bucket(
+    State(app): State<App>,
+    Path(name): Path<Namespace>,
+    Json(body): Json<PutBucketRequest>,
+) -> HttpResult<PutBucketResponse> {
+    let buckets = app
+        .buckets()
+        .context("buckets are disabled")
+        .map_err(error::make_response(StatusCode::NOT_FOUND))?;
+    let peers = app.topology().map(|topology| {
+        let topology = topology.snapshot();
+        let addresses: Vec<_> =
+            topology.nodes.into_iter().map(|node| node.address).collect();
+        (topology.self_id, addresses)
+    });
+    let timeout = app.peer_timeout().unwrap_or(DEFAULT_PEER_TIMEOUT);
+    if let Some((self_id, addresses)) = &peers
+        && buckets.changes_replication(&name, &body)
+    {
+        let unreachable =
+            sync::pull_buckets(*self_id, buckets, addresses, timeout).await;
+        if !unreachable.is_empty() {
+            Err(anyhow!(
+                "nodes {unreachable:?} are unreachable, cannot tell whether \
+                 bucket {name} holds data"
+            ))
+            .map_err(error::make_response(StatusCode::SERVICE_UNAVAILABLE))?;
+        }
+    }
+    let bucket = buckets.configure(name, body).await.map_err(|error| {
+        let status = if error.is::<ReplicationLocked>() {
+            StatusCode::CONFLICT
+        } else {
+            StatusCode::BAD_REQUEST
+        };
+        error::make_response(status)(error)
+    })?;
+    let unreachable = match &peers {
+        Some((self_id, addresses)) => {
+            sync::broadcast_bucket(*self_id, &bucket, addresses, timeout).await
+        },
+        None => Vec::new(),
+    };
+    Ok(Json(PutBucketResponse { bucket, unreachable }))
+}
+
+fn breaker_stats(node_id: usize, snapshot: BreakerSnapshot) -> BreakerStats {
+    let (state, until) = match snapshot.state {
+        BreakerState::Closed => (BreakerStatus::Closed, None),
+        BreakerState::Open { until } => (BreakerStatus::Open, Some(until)),
+        BreakerState::HalfOpen { until } => {
+            (BreakerStatus::HalfOpen, Some(until))
+        },
+    };
+    let retry_in =
+        until.map(|until| until.saturating_duration_since(Instant::now()));
+    BreakerStats {
+        node_id,
+        state,
+        consecutive_failures: snapshot.consecutive_failures,
+        retry_in_ms: retry_in.map(|retry_in| saturate(retry_in.as_millis())),
+    }
+}
+
+fn actor_stats(snapshot: ActorMetricsSnapshot) -> ActorStats {
+    let buckets = snapshot
+        .latency
+        .buckets
+        .into_iter()
+        .map(|bucket| LatencyBucket {
+            le_us: bucket.upper_bound.map(|bound| saturate(bound.as_micros())),
+            count: bucket.count,
+        })
+        .collect();
+    ActorStats {
+        name: snapshot.name,
+        uptime_ms: saturate(snapshot.uptime.as_millis()),
+        mailbox_len: snapshot.mailbox_len,
+        control_mailbox_len: snapshot.control_mailbox_len,
+        calls: snapshot.calls,
+        calls_per_second: snapshot.calls_per_second,
+        error_replies: snapshot.error_replies,
+        dropped_replies: snapshot.dropped_replies,
+        latency: LatencyStats {
+            count: snapshot.latency.count,
+            total_us: saturate(snapshot.latency.total.as_micros()),
+            buckets,
+        },
+    }
+}
+
+fn saturate(value: u128) -> u64 {
+    u64::try_from(value).unwrap_or(u64::MAX)
+}
@@ -1,18 +1,26 @@
 use anyhow::Context;
 use axum::{
-    Json,
     Router,
-    extract::{Path, State},
+    extract::State,
     http::StatusCode,
-    routing::{get, post},
+    routing::{delete, get, post},
+};
+use spalhad_spec::kv::{
+    DeleteResponse,
+    GetResponse,
+    Key,
+    PutRequest,
+    PutResponse,
+    Value,
 };
-use spalhad_spec::kv::{GetResponse, Key, PutRequest, PutResponse};
 
 use crate::{
     actor::storage,
     http::{
         App,
-        error::{self, HttpResult},
+        error::{self, HttpError},
+        v1::kv,
+        wire::{Accept, Encoded, KeyPath, Wire},
     },
 };
 
@@ -20,31 +28,46 @@ pub fn router() -> Router<App> {
     Router::new()
         .route("/{key}", get(get_by_key))
         .route("/{key}", post(put_by_key))
+        .route("/{key}", delete(delete_by_key))
 }
 
 async fn get_by_key(
     State(app): State<App>,
-    Path(key): Path<Key>,
-) -> HttpResult<GetResponse<serde_json::Value>> {
+    Accept(format): Accept,
+    KeyPath(key): KeyPath<Key>,
+) -> Result<Encoded<GetResponse<Value>>, HttpError> {
     app.bouncer()
         .send(storage::Get { key })
         .await
         .map_err(error::when_not_bouncer(StatusCode::INTERNAL_SERVER_ERROR))?
         .context("key not found")
         .map_err(error::make_response(StatusCode::NOT_FOUND))
-        .map(|value| GetResponse { value })
-        .map(Json)
+        .map(|value| Encoded::new(format, GetResponse { value }))
 }
 
 async fn put_by_key(
     State(app): State<App>,
-    Path(key): Path<Key>,
-    Json(body): Json<PutRequest<serde_json::Value>>,
-) -> HttpResult<PutResponse> {
+    Accept(format): Accept,
+    KeyPath(key): KeyPath<Key>,
+    Wire(body): Wire<PutRequest<Value>>,
+) -> Result<Encoded<PutResponse>, HttpError> {
+    kv::verify_origin(&key, body.origin.as_ref())
+        .map_err(error::make_response(StatusCode::BAD_REQUEST))?;
+    app.bouncer()
+        .send(storage::Put { key, value: body.value, origin: body.origin })
+        .await
+        .map_err(error::when_not_bouncer(StatusCode::INTERNAL_SERVER_ERROR))
+        .map(|new| Encoded::new(format, PutResponse { new }))
+}
+
+async fn delete_by_key(
+    State(app): State<App>,
+    Accept(format): Accept,
+    KeyPath(key): KeyPath<Key>,
+) -> Result<Encoded<DeleteResponse>, HttpError> {
     app.bouncer()
-        .send(storage::Put { key, value: body.value })
+        .send(storage::Delete { key })
         .await
         .map_err(error::when_not_bouncer(StatusCode::INTERNAL_SERVER_ERROR))
-        .map(|new| PutResponse { new })
-        .map(Json)
+        .map(|existed| Encoded::new(format, DeleteResponse { existed }))
 }
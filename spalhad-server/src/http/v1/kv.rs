@@ -1,18 +1,21 @@
-use anyhow::Context;
+use anyhow::{Result, bail};
 use axum::{
     Json,
     Router,
-    extract::{Path, State},
+    extract::State,
     http::StatusCode,
     routing::{get, post},
 };
-use spalhad_spec::kv::{GetResponse, Key, PutRequest, PutResponse};
+use spalhad_spec::kv::{Key, KeyOrigin, PutResponse};
 
 use crate::{
     actor::coordinator,
     http::{
         App,
-        error::{self, HttpResult},
+        debug::{DebugReplicas, WithReplicas},
+        error::{self, HttpError},
+        value::{ValueBody, ValueResponse},
+        wire::KeyPath,
     },
 };
 
@@ -24,27 +27,48 @@ pub fn router() -> Router<App> {
 
 async fn get_by_key(
     State(app): State<App>,
-    Path(key): Path<Key>,
-) -> HttpResult<GetResponse<serde_json::Value>> {
-    app.bouncer()
-        .send(coordinator::Get { key })
+    KeyPath(key): KeyPath<Key>,
+    debug: DebugReplicas,
+) -> Result<WithReplicas<ValueResponse>, HttpError> {
+    let output = app
+        .bouncer()
+        .send(coordinator::Get { key, namespace: None })
         .await
-        .map_err(error::when_not_bouncer(StatusCode::INTERNAL_SERVER_ERROR))?
-        .context("key not found")
-        .map_err(error::make_response(StatusCode::NOT_FOUND))
-        .map(|value| GetResponse { value })
-        .map(Json)
+        .map_err(error::when_not_bouncer(StatusCode::INTERNAL_SERVER_ERROR))?;
+    ValueResponse::found(output, debug)
 }
 
 async fn put_by_key(
     State(app): State<App>,
-    Path(key): Path<Key>,
-    Json(body): Json<PutRequest<serde_json::Value>>,
-) -> HttpResult<PutResponse> {
+    KeyPath(key): KeyPath<Key>,
+    debug: DebugReplicas,
+    body: ValueBody,
+) -> Result<WithReplicas<Json<PutResponse>>, HttpError> {
+    verify_origin(&key, body.origin.as_ref())
+        .map_err(error::make_response(StatusCode::BAD_REQUEST))?;
     app.bouncer()
-        .send(coordinator::Put { key, value: body.value })
+        .send(coordinator::Put {
+            key,
+            value: body.value,
+            namespace: None,
+            origin: body.origin,
+        })
         .await
         .map_err(error::when_not_bouncer(StatusCode::INTERNAL_SERVER_ERROR))
-        .map(|new| PutResponse { new })
-        .map(Json)
+        .map(|output| {
+            let response = Json(PutResponse { new: output.value });
+            debug.attach(output.replicas, response)
+        })
+}
+
+pub(crate) fn verify_origin(
+    key: &Key,
+    origin: Option<&KeyOrigin>,
+) -> Result<()> {
+    if let Some(origin) = origin
+        && origin.to_key() != *key
+    {
+        bail!("key origin does not hash to {key}");
+    }
+    Ok(())
 }
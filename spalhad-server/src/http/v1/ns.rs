@@ -0,0 +1,62 @@
+use axum::{
+    Json,
+    Router,
+    extract::State,
+    http::StatusCode,
+    routing::{get, post},
+};
+use spalhad_spec::kv::{Key, KeyOrigin, Namespace, PutResponse};
+
+use crate::{
+    actor::coordinator,
+    http::{
+        App,
+        debug::{DebugReplicas, WithReplicas},
+        error::{self, HttpError},
+        value::{ValueBody, ValueResponse},
+        wire::KeyPath,
+    },
+};
+
+pub fn router() -> Router<App> {
+    Router::new()
+        .route("/{namespace}/kv/{key}", get(get_by_key))
+        .route("/{namespace}/kv/{key}", post(put_by_key))
+}
+
+async fn get_by_key(
+    State(app): State<App>,
+    KeyPath((namespace, key_data)): KeyPath<(Namespace, String)>,
+    debug: DebugReplicas,
+) -> Result<WithReplicas<ValueResponse>, HttpError> {
+    let key = Key::from_namespaced_key(&namespace, key_data);
+    let output = app
+        .bouncer()
+        .send(coordinator::Get { key, namespace: Some(namespace) })
+        .await
+        .map_err(error::when_not_bouncer(StatusCode::INTERNAL_SERVER_ERROR))?;
+    ValueResponse::found(output, debug)
+}
+
+async fn put_by_key(
+    State(app): State<App>,
+    KeyPath((namespace, key_data)): KeyPath<(Namespace, String)>,
+    debug: DebugReplicas,
+    body: ValueBody,
+) -> Result<WithReplicas<Json<PutResponse>>, HttpError> {
+    let origin = KeyOrigin::namespaced(namespace.clone(), key_data);
+    let key = origin.to_key();
+    app.bouncer()
+        .send(coordinator::Put {
+            key,
+            value: body.value,
+            namespace: Some(namespace),
+            origin: Some(origin),
+        })
+        .await
+        .map_err(error::when_not_bouncer(StatusCode::INTERNAL_SERVER_ERROR))
+        .map(|output| {
+            let response = Json(PutResponse { new: output.value });
+            debug.attach(output.replicas, response)
+        })
+}
@@ -1,4 +1,4 @@
-use anyhow::anyhow;
+use anyhow::Context;
 use axum::{
     Json,
     Router,
@@ -6,15 +6,24 @@ use axum::{
     http::StatusCode,
     routing::{get, post},
 };
-use spalhad_spec::cluster::{
-    ActivateRequest,
-    ActivateResponse,
-    IsActiveResponse,
-    RunIdResponse,
+use spalhad_spec::{
+    bucket::{SyncBucketRequest, SyncBucketResponse},
+    cluster::{
+        ActivateRequest,
+        ActivateResponse,
+        IsActiveResponse,
+        PeerStatusRequest,
+        PeerStatusResponse,
+        RunIdResponse,
+        TopologyResponse,
+    },
 };
 
 use crate::{
-    actor::bouncer::{self, Activated},
+    actor::{
+        bouncer::{self, Activated},
+        coordinator::{self, PeerStatusSet},
+    },
     http::{
         App,
         error::{self, HttpResult},
@@ -26,6 +35,9 @@ pub fn router() -> Router<App> {
         .route("/runid", get(run_id))
         .route("/activate", post(activate))
         .route("/active", get(is_active))
+        .route("/peer", post(set_peer_status))
+        .route("/topology", get(topology))
+        .route("/buckets", post(sync_bucket))
 }
 
 pub async fn run_id(State(app): State<App>) -> HttpResult<RunIdResponse> {
@@ -38,7 +50,7 @@ pub async fn run_id(State(app): State<App>) -> HttpResult<RunIdResponse> {
         let response = RunIdResponse { run_id: app.self_run_id() };
         Ok(Json(response))
     } else {
-        Err(anyhow!("not active yet"))
+        Err(bouncer::Error::NotActive.into())
             .map_err(error::make_response(StatusCode::FORBIDDEN))
     }
 }
@@ -63,3 +75,47 @@ pub async fn is_active(State(app): State<App>) -> HttpResult<IsActiveResponse> {
         .map(|is_active| IsActiveResponse { is_active })
         .map(Json)
 }
+
+pub async fn set_peer_status(
+    State(app): State<App>,
+    Json(body): Json<PeerStatusRequest>,
+) -> HttpResult<PeerStatusResponse> {
+    let message = coordinator::SetPeerStatus {
+        node_id: body.node_id,
+        status: body.status,
+    };
+    let PeerStatusSet = app
+        .bouncer()
+        .send(message)
+        .await
+        .map_err(error::make_response(StatusCode::BAD_REQUEST))?;
+    if let Some(topology) = app.topology() {
+        topology
+            .set_status(body.node_id, body.status)
+            .map_err(error::make_response(StatusCode::BAD_REQUEST))?;
+    }
+    Ok(Json(body))
+}
+
+pub async fn topology(State(app): State<App>) -> HttpResult<TopologyResponse> {
+    let topology = app
+        .topology()
+        .context("cluster topology is unknown")
+        .map_err(error::make_response(StatusCode::NOT_FOUND))?;
+    Ok(Json(topology.snapshot()))
+}
+
+pub async fn sync_bucket(
+    State(app): State<App>,
+    Json(body): Json<SyncBucketRequest>,
+) -> HttpResult<SyncBucketResponse> {
+    let buckets = app
+        .buckets()
+        .context("buckets are disabled")
+        .map_err(error::make_response(StatusCode::NOT_FOUND))?;
+    let applied = buckets
+        .apply(body)
+        .await
+        .map_err(error::make_response(StatusCode::BAD_REQUEST))?;
+    Ok(Json(SyncBucketResponse { applied }))
+}
@@ -0,0 +1,108 @@
+use anyhow::anyhow;
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{StatusCode, header::CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use spalhad_spec::kv::{
+    BytesValue,
+    GetResponse,
+    KEY_ORIGIN_HEADER,
+    KeyOrigin,
+    PutRequest,
+    Value,
+    value::{self, OCTET_STREAM_CONTENT_TYPE},
+};
+
+use crate::{
+    actor::coordinator::{GetOutput, Replicated},
+    http::{
+        debug::{DebugReplicas, WithReplicas},
+        error::{self, HttpError},
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct ValueBody {
+    pub value: Value,
+    pub origin: Option<KeyOrigin>,
+}
+
+impl<S> FromRequest<S> for ValueBody
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(
+        request: Request,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let content_type = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .unwrap_or(OCTET_STREAM_CONTENT_TYPE)
+            .to_owned();
+        if value::is_json_content_type(&content_type) {
+            let Json(body) =
+                Json::<PutRequest<serde_json::Value>>::from_request(
+                    request, state,
+                )
+                .await
+                .map_err(IntoResponse::into_response)?;
+            Ok(Self { value: Value::Json(body.value), origin: body.origin })
+        } else {
+            let origin = request
+                .headers()
+                .get(KEY_ORIGIN_HEADER)
+                .map(|origin| KeyOrigin::from_header_value(origin.as_bytes()))
+                .transpose()
+                .map_err(|error| {
+                    error::make_response(StatusCode::BAD_REQUEST)(error.into())
+                        .into_response()
+                })?;
+            let data = Bytes::from_request(request, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            let value = BytesValue::new(content_type, data);
+            Ok(Self { value: Value::Bytes(value), origin })
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ValueResponse(pub Value);
+
+impl ValueResponse {
+    pub fn found(
+        output: GetOutput,
+        debug: DebugReplicas,
+    ) -> Result<WithReplicas<Self>, HttpError> {
+        let Replicated { value, replicas } = output;
+        match value {
+            Some(value) => Ok(debug.attach(replicas, Self(value))),
+            None => {
+                let (status, Json(error)) =
+                    error::make_response(StatusCode::NOT_FOUND)(anyhow!(
+                        "key not found"
+                    ));
+                Err((status, Json(error.with_replicas(replicas))))
+            },
+        }
+    }
+}
+
+impl IntoResponse for ValueResponse {
+    fn into_response(self) -> Response {
+        match self.0 {
+            Value::Json(value) => Json(GetResponse { value }).into_response(),
+            Value::Bytes(value) => {
+                ([(CONTENT_TYPE, value.content_type)], value.data)
+                    .into_response()
+            },
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::anyhow;
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Path, Request},
+    http::{
+        StatusCode,
+        header::{ACCEPT, CONTENT_TYPE},
+        request::Parts,
+    },
+    response::{IntoResponse, Response},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use spalhad_spec::{ErrorCode, wire::WireFormat};
+
+use crate::http::error::{self, HttpError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Accept(pub WireFormat);
+
+impl<S> FromRequestParts<S> for Accept
+where
+    S: Send + Sync,
+{
+    type Rejection = HttpError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let format = parts
+            .headers
+            .get(ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .map(WireFormat::from_accept)
+            .unwrap_or_default();
+        Ok(Self(format))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyPath<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for KeyPath<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Send,
+{
+    type Rejection = HttpError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        Path::from_request_parts(parts, state)
+            .await
+            .map(|Path(value)| Self(value))
+            .map_err(|rejection| {
+                let status = rejection.status();
+                let code = if status == StatusCode::BAD_REQUEST {
+                    ErrorCode::InvalidKey
+                } else {
+                    ErrorCode::Internal
+                };
+                error::make_coded_response(status, code)(rejection.into())
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Wire<T>(pub T);
+
+impl<S, T> FromRequest<S> for Wire<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = HttpError;
+
+    async fn from_request(
+        request: Request,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let content_type = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .unwrap_or_default();
+        let format =
+            WireFormat::from_content_type(content_type).ok_or_else(|| {
+                error::make_response(StatusCode::UNSUPPORTED_MEDIA_TYPE)(
+                    anyhow!("unsupported content type {content_type:?}"),
+                )
+            })?;
+        let body = Bytes::from_request(request, state)
+            .await
+            .map_err(anyhow::Error::from)
+            .map_err(error::make_response(StatusCode::BAD_REQUEST))?;
+        format
+            .decode(&body)
+            .map(Self)
+            .map_err(anyhow::Error::from)
+            .map_err(error::make_response(StatusCode::BAD_REQUEST))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Encoded<T> {
+    pub format: WireFormat,
+    pub value: T,
+}
+
+impl<T> Encoded<T> {
+    pub fn new(format: WireFormat, value: T) -> Self {
+        Self { format, value }
+    }
+}
+
+impl<T> IntoResponse for Encoded<T>
+where
+    T: Serialize,
+{
+    fn into_response(self) -> Response {
+        match self.format.encode(&self.value) {
+            Ok(body) => ([(CONTENT_TYPE, self.format.content_type())], body)
+                .into_response(),
+            Err(error) => error::make_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )(error.into())
+            .into_response(),
+        }
+    }
+}
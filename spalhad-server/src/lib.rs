@@ -1,3 +1,10 @@
 pub mod actor;
 pub mod sync;
+pub mod topology;
+pub mod bucket;
 pub mod http;
+pub mod rpc;
+pub mod grpc;
+pub mod resp;
+#[cfg(feature = "sim")]
+pub mod sim;
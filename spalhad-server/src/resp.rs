@@ -0,0 +1,13 @@
+pub use codec::{
+    CodecError,
+    MAX_ARGS,
+    MAX_BULK_LEN,
+    MAX_INLINE_LEN,
+    Reply,
+    RespCodec,
+};
+pub use server::serve;
+
+mod codec;
+mod command;
+mod server;
@@ -0,0 +1,240 @@
+use std::{io, ops::Range};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use thiserror::Error;
+use tokio_util::codec::{Decoder, Encoder};
+
+pub const MAX_BULK_LEN: usize = 64 * 1024 * 1024;
+
+pub const MAX_ARGS: usize = 64 * 1024;
+
+pub const MAX_INLINE_LEN: usize = 64 * 1024;
+
+#[derive(Debug, Error)]
+pub enum CodecError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Protocol error: {0}")]
+    Protocol(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Simple(&'static str),
+    Error(String),
+    Integer(i64),
+    Bulk(Option<Bytes>),
+    Array(Vec<Reply>),
+}
+
+impl Reply {
+    pub const OK: Self = Self::Simple("OK");
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::Error(message.into())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RespCodec {
+    array: Option<PartialArray>,
+}
+
+#[derive(Debug)]
+struct PartialArray {
+    count: usize,
+    pos: usize,
+    ranges: Vec<Range<usize>>,
+}
+
+impl Decoder for RespCodec {
+    type Item = Vec<Bytes>;
+    type Error = CodecError;
+
+    fn decode(
+        &mut self,
+        buf: &mut BytesMut,
+    ) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            let parsed = match (self.array.take(), buf.first()) {
+                (Some(array), _) => self.parse_array(array, buf)?,
+                (None, None) => return Ok(None),
+                (None, Some(b'*')) => match PartialArray::start(buf)? {
+                    Some(array) => self.parse_array(array, buf)?,
+                    None => None,
+                },
+                (None, Some(_)) => parse_inline(buf)?,
+            };
+            let Some((ranges, len)) = parsed else {
+                return Ok(None);
+            };
+            let frame = buf.split_to(len).freeze();
+            if !ranges.is_empty() {
+                let args = ranges
+                    .into_iter()
+                    .map(|range| frame.slice(range))
+                    .collect();
+                return Ok(Some(args));
+            }
+        }
+    }
+}
+
+impl RespCodec {
+    fn parse_array(
+        &mut self,
+        mut array: PartialArray,
+        buf: &[u8],
+    ) -> Result<Parsed, CodecError> {
+        if array.parse(buf)? {
+            Ok(Some((array.ranges, array.pos)))
+        } else {
+            self.array = Some(array);
+            Ok(None)
+        }
+    }
+}
+
+impl Encoder<Reply> for RespCodec {
+    type Error = CodecError;
+
+    fn encode(
+        &mut self,
+        reply: Reply,
+        buf: &mut BytesMut,
+    ) -> Result<(), Self::Error> {
+        encode_reply(&reply, buf);
+        Ok(())
+    }
+}
+
+fn encode_reply(reply: &Reply, buf: &mut BytesMut) {
+    match reply {
+        Reply::Simple(message) => {
+            buf.put_u8(b'+');
+            buf.put_slice(message.as_bytes());
+        },
+        Reply::Error(message) => {
+            buf.put_u8(b'-');
+            for byte in message.bytes() {
+                let byte =
+                    if byte == b'\r' || byte == b'\n' { b' ' } else { byte };
+                buf.put_u8(byte);
+            }
+        },
+        Reply::Integer(value) => {
+            buf.put_u8(b':');
+            buf.put_slice(value.to_string().as_bytes());
+        },
+        Reply::Bulk(None) => buf.put_slice(b"$-1"),
+        Reply::Bulk(Some(data)) => {
+            buf.put_u8(b'$');
+            buf.put_slice(data.len().to_string().as_bytes());
+            buf.put_slice(b"\r\n");
+            buf.put_slice(data);
+        },
+        Reply::Array(replies) => {
+            buf.put_u8(b'*');
+            buf.put_slice(replies.len().to_string().as_bytes());
+            buf.put_slice(b"\r\n");
+            for reply in replies {
+                encode_reply(reply, buf);
+            }
+            return;
+        },
+    }
+    buf.put_slice(b"\r\n");
+}
+
+type Parsed = Option<(Vec<Range<usize>>, usize)>;
+
+impl PartialArray {
+    fn start(buf: &[u8]) -> Result<Option<Self>, CodecError> {
+        let Some((header, pos)) = line(buf, 0, MAX_INLINE_LEN)? else {
+            return Ok(None);
+        };
+        let count = parse_len(&header[1 ..], "invalid multibulk length")?;
+        if count > MAX_ARGS {
+            return Err(CodecError::Protocol("invalid multibulk length"));
+        }
+        Ok(Some(Self { count, pos, ranges: Vec::new() }))
+    }
+
+    fn parse(&mut self, buf: &[u8]) -> Result<bool, CodecError> {
+        while self.ranges.len() < self.count {
+            let Some((header, start)) = line(buf, self.pos, MAX_INLINE_LEN)?
+            else {
+                return Ok(false);
+            };
+            if header.first() != Some(&b'$') {
+                return Err(CodecError::Protocol("expected '$'"));
+            }
+            let len = parse_len(&header[1 ..], "invalid bulk length")?;
+            if len > MAX_BULK_LEN {
+                return Err(CodecError::Protocol("invalid bulk length"));
+            }
+            let end = start + len;
+            if buf.len() < end + 2 {
+                return Ok(false);
+            }
+            if &buf[end .. end + 2] != b"\r\n" {
+                return Err(CodecError::Protocol(
+                    "expected CRLF after bulk data",
+                ));
+            }
+            self.ranges.push(start .. end);
+            self.pos = end + 2;
+        }
+        Ok(true)
+    }
+}
+
+fn parse_inline(buf: &[u8]) -> Result<Parsed, CodecError> {
+    let Some((content, len)) = line(buf, 0, MAX_INLINE_LEN)? else {
+        return Ok(None);
+    };
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (i, byte) in content.iter().enumerate() {
+        match (byte.is_ascii_whitespace(), start) {
+            (true, Some(arg_start)) => {
+                ranges.push(arg_start .. i);
+                start = None;
+            },
+            (false, None) => start = Some(i),
+            _ => (),
+        }
+    }
+    if let Some(arg_start) = start {
+        ranges.push(arg_start .. content.len());
+    }
+    Ok(Some((ranges, len)))
+}
+
+fn line(
+    buf: &[u8],
+    start: usize,
+    max_len: usize,
+) -> Result<Option<(&[u8], usize)>, CodecError> {
+    let rest = &buf[start.min(buf.len()) ..];
+    match rest.windows(2).position(|window| window == b"\r\n") {
+        Some(len) if len > max_len => {
+            Err(CodecError::Protocol("too big request line"))
+        },
+        Some(len) => Ok(Some((&rest[.. len], start + len + 2))),
+        None if rest.len() > max_len => {
+            Err(CodecError::Protocol("too big request line"))
+        },
+        None => Ok(None),
+    }
+}
+
+fn parse_len(
+    digits: &[u8],
+    message: &'static str,
+) -> Result<usize, CodecError> {
+    std::str::from_utf8(digits)
+        .ok()
+        .and_then(|digits| digits.parse().ok())
+        .ok_or(CodecError::Protocol(message))
+}
@@ -0,0 +1,193 @@
+use anyhow::Result;
+use bytes::Bytes;
+use futures::future;
+use spalhad_spec::kv::{BytesValue, Key, Value};
+
+use super::codec::Reply;
+use crate::actor::{bouncer::BouncerHandle, coordinator};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Ping(Option<Bytes>),
+    Quit,
+    Get(Bytes),
+    Set(Bytes, Bytes),
+    Del(Vec<Bytes>),
+    Exists(Vec<Bytes>),
+    MGet(Vec<Bytes>),
+    MSet(Vec<(Bytes, Bytes)>),
+}
+
+impl Command {
+    pub fn parse(args: Vec<Bytes>) -> Result<Self, Reply> {
+        let mut args = args.into_iter();
+        let Some(name) = args.next() else {
+            return Err(Reply::error("ERR empty command"));
+        };
+        let name = String::from_utf8_lossy(&name).to_ascii_uppercase();
+        let args: Vec<Bytes> = args.collect();
+        let wrong_arity = || {
+            Reply::error(format!(
+                "ERR wrong number of arguments for '{}' command",
+                name.to_ascii_lowercase(),
+            ))
+        };
+        match (name.as_str(), args.len()) {
+            ("PING", 0 ..= 1) => Ok(Self::Ping(args.into_iter().next())),
+            ("QUIT", _) => Ok(Self::Quit),
+            ("GET", 1) => Ok(Self::Get(args.into_iter().next().unwrap())),
+            ("SET", 2) => {
+                let mut args = args.into_iter();
+                let key = args.next().unwrap();
+                let value = args.next().unwrap();
+                Ok(Self::Set(key, value))
+            },
+            ("SET", 3 ..) => {
+                Err(Reply::error("ERR SET options are not supported"))
+            },
+            ("DEL", 1 ..) => Ok(Self::Del(args)),
+            ("EXISTS", 1 ..) => Ok(Self::Exists(args)),
+            ("MGET", 1 ..) => Ok(Self::MGet(args)),
+            ("MSET", len) if len > 0 && len % 2 == 0 => {
+                let pairs = args
+                    .chunks_exact(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect();
+                Ok(Self::MSet(pairs))
+            },
+            (
+                "PING" | "GET" | "SET" | "DEL" | "EXISTS" | "MGET" | "MSET",
+                _,
+            ) => Err(wrong_arity()),
+            _ => Err(Reply::error(format!(
+                "ERR unknown command '{}'",
+                name.to_ascii_lowercase(),
+            ))),
+        }
+    }
+
+    pub async fn execute(self, bouncer: &BouncerHandle) -> Reply {
+        let result = match self {
+            Self::Ping(None) => Ok(Reply::Simple("PONG")),
+            Self::Ping(Some(message)) => Ok(Reply::Bulk(Some(message))),
+            Self::Quit => Ok(Reply::OK),
+            Self::Get(key) => get(bouncer, key).await.map(Reply::Bulk),
+            Self::Set(key, value) => {
+                set(bouncer, key, value).await.map(|()| Reply::OK)
+            },
+            Self::Del(keys) => {
+                let deleted = future::join_all(
+                    keys.into_iter().map(|key| delete(bouncer, key)),
+                )
+                .await;
+                settle(deleted, "deleted")
+                    .map(|deleted| count(deleted.into_iter()))
+            },
+            Self::Exists(keys) => {
+                let values = future::try_join_all(
+                    keys.into_iter().map(|key| get(bouncer, key)),
+                )
+                .await;
+                values.map(|values| count(values.iter().map(Option::is_some)))
+            },
+            Self::MGet(keys) => {
+                let values = future::try_join_all(
+                    keys.into_iter().map(|key| get(bouncer, key)),
+                )
+                .await;
+                values.map(|values| {
+                    Reply::Array(values.into_iter().map(Reply::Bulk).collect())
+                })
+            },
+            Self::MSet(pairs) => {
+                let stored = future::join_all(
+                    pairs
+                        .into_iter()
+                        .map(|(key, value)| set(bouncer, key, value)),
+                )
+                .await;
+                settle(stored, "stored").map(|_| Reply::OK)
+            },
+        };
+        result.unwrap_or_else(|error| {
+            let message = error
+                .chain()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(": ");
+            Reply::error(format!("ERR {message}"))
+        })
+    }
+}
+
+fn settle<T>(results: Vec<Result<T>>, action: &str) -> Result<Vec<T>> {
+    let total = results.len();
+    let mut outputs = Vec::with_capacity(total);
+    let mut failure = None;
+    for result in results {
+        match result {
+            Ok(output) => outputs.push(output),
+            Err(error) => {
+                failure.get_or_insert(error);
+            },
+        }
+    }
+    match failure {
+        None => Ok(outputs),
+        Some(error) => Err(error.context(format!(
+            "only {} of {total} keys were {action}",
+            outputs.len(),
+        ))),
+    }
+}
+
+fn count(flags: impl Iterator<Item = bool>) -> Reply {
+    Reply::Integer(flags.filter(|flag| *flag).count() as i64)
+}
+
+async fn get(bouncer: &BouncerHandle, key: Bytes) -> Result<Option<Bytes>> {
+    let value = bouncer
+        .send(coordinator::Get {
+            key: Key::from_bytes_key(&key),
+            namespace: None,
+        })
+        .await?;
+    Ok(value.value.map(into_data))
+}
+
+async fn set(bouncer: &BouncerHandle, key: Bytes, data: Bytes) -> Result<()> {
+    bouncer
+        .send(coordinator::Put {
+            key: Key::from_bytes_key(&key),
+            value: from_data(data),
+            namespace: None,
+            origin: None,
+        })
+        .await?;
+    Ok(())
+}
+
+async fn delete(bouncer: &BouncerHandle, key: Bytes) -> Result<bool> {
+    bouncer
+        .send(coordinator::Delete {
+            key: Key::from_bytes_key(&key),
+            namespace: None,
+        })
+        .await
+        .map(|output| output.value)
+}
+
+fn from_data(data: Bytes) -> Value {
+    match str::from_utf8(&data) {
+        Ok(text) => Value::Json(text.into()),
+        Err(_) => Value::Bytes(BytesValue::octet_stream(data)),
+    }
+}
+
+fn into_data(value: Value) -> Bytes {
+    match value {
+        Value::Json(serde_json::Value::String(text)) => text.into(),
+        Value::Json(json) => json.to_string().into(),
+        Value::Bytes(bytes) => bytes.data,
+    }
+}
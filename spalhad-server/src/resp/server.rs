@@ -0,0 +1,109 @@
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    select,
+};
+use tokio_util::{codec::Framed, sync::CancellationToken, task::TaskTracker};
+use tracing::Instrument;
+
+use super::{
+    codec::{CodecError, Reply, RespCodec},
+    command::Command,
+};
+use crate::actor::bouncer::BouncerHandle;
+
+pub async fn serve(
+    bind_address: &str,
+    bouncer: BouncerHandle,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    tracing::info!(%bind_address, "binding resp socket listener...");
+    let listener = TcpListener::bind(bind_address).await?;
+    tracing::info!("resp socket bound.");
+    let connections = TaskTracker::new();
+    loop {
+        let accepted = select! {
+            _ = cancellation_token.cancelled() => break,
+            accepted = listener.accept() => accepted,
+        };
+        let (stream, peer) = match accepted {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                tracing::warn!(%error, "failed to accept resp connection");
+                continue;
+            },
+        };
+        let span = tracing::info_span!("resp-connection", %peer);
+        let connection = serve_connection(
+            stream,
+            peer,
+            bouncer.clone(),
+            cancellation_token.clone(),
+        );
+        connections.spawn(connection.instrument(span));
+    }
+    connections.close();
+    connections.wait().await;
+    tracing::info!("resp server stopped, in-flight commands finished.");
+    Ok(())
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    bouncer: BouncerHandle,
+    cancellation_token: CancellationToken,
+) {
+    tracing::debug!("resp connection opened");
+    if let Err(error) =
+        try_serve_connection(stream, bouncer, cancellation_token).await
+    {
+        tracing::warn!(%peer, %error, "resp connection failed");
+    }
+    tracing::debug!("resp connection closed");
+}
+
+async fn try_serve_connection(
+    stream: TcpStream,
+    bouncer: BouncerHandle,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    stream.set_nodelay(true)?;
+    let mut frames = Framed::new(stream, RespCodec::default());
+
+    loop {
+        let frame = select! {
+            _ = cancellation_token.cancelled() => break,
+            frame = frames.next() => frame,
+        };
+        let args = match frame {
+            None => break,
+            Some(Ok(args)) => args,
+            Some(Err(CodecError::Protocol(message))) => {
+                let reply =
+                    Reply::error(format!("ERR Protocol error: {message}"));
+                frames.send(reply).await?;
+                break;
+            },
+            Some(Err(CodecError::Io(error))) => Err(error)?,
+        };
+        let command = Command::parse(args);
+        let quit = command == Ok(Command::Quit);
+        let reply = match command {
+            Ok(command) => command.execute(&bouncer).await,
+            Err(reply) => reply,
+        };
+        frames.feed(reply).await?;
+        if quit {
+            break;
+        }
+        if frames.read_buffer().is_empty() {
+            frames.flush().await?;
+        }
+    }
+    frames.flush().await?;
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use std::sync::Arc;
+
+use spalhad_spec::{Error, rpc::MAX_FRAME_LEN};
+use thiserror::Error;
+use tokio_util::codec::LengthDelimitedCodec;
+
+pub use connection::Connection;
+pub use server::{serve, serve_listener};
+
+mod connection;
+mod server;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("rpc request timed out")]
+pub struct TimedOut;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("rpc connection has too many requests in flight")]
+pub struct Saturated;
+
+#[derive(Debug, Clone, Error)]
+#[error("{}", .0.trace.join(": "))]
+pub struct RemoteError(pub Error);
+
+#[derive(Debug, Clone, Error)]
+#[error("failed to open rpc connection")]
+pub struct ConnectFailed(#[source] Arc<anyhow::Error>);
+
+impl ConnectFailed {
+    pub fn new(error: anyhow::Error) -> Self {
+        Self(Arc::new(error))
+    }
+}
+
+fn codec() -> LengthDelimitedCodec {
+    LengthDelimitedCodec::builder().max_frame_length(MAX_FRAME_LEN).new_codec()
+}
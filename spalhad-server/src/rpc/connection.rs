@@ -0,0 +1,210 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow};
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use spalhad_spec::rpc::{
+    self,
+    MAX_BATCH_LEN,
+    MAX_IN_FLIGHT,
+    RpcCall,
+    RpcReply,
+    RpcRequest,
+    RpcResponse,
+};
+use tokio::{
+    net::TcpStream,
+    select,
+    sync::mpsc,
+    time::{self, Instant},
+};
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+type Callback = Box<dyn FnOnce(Result<RpcReply>) + Send>;
+
+struct Outgoing {
+    call: RpcCall,
+    trace: HashMap<String, String>,
+    callback: Callback,
+}
+
+#[derive(Debug, Clone)]
+pub struct Connection {
+    outgoing: mpsc::Sender<Outgoing>,
+}
+
+impl Connection {
+    pub async fn connect(address: &str, timeout: Duration) -> Result<Self> {
+        let stream = time::timeout(timeout, TcpStream::connect(address))
+            .await
+            .with_context(|| format!("timed out connecting to {address}"))?
+            .with_context(|| format!("failed to connect to {address}"))?;
+        stream.set_nodelay(true)?;
+        let (outgoing, requests) = mpsc::channel(MAX_IN_FLIGHT);
+        let address = address.to_owned();
+        tokio::spawn(async move {
+            if let Err(error) = drive(stream, requests, timeout).await {
+                tracing::warn!(%address, %error, "rpc connection failed");
+            }
+        });
+        Ok(Self { outgoing })
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.outgoing.is_closed()
+    }
+
+    pub fn send<F>(
+        &self,
+        call: RpcCall,
+        trace: HashMap<String, String>,
+        callback: F,
+    ) where
+        F: FnOnce(Result<RpcReply>) + Send + 'static,
+    {
+        let outgoing = Outgoing { call, trace, callback: Box::new(callback) };
+        match self.outgoing.try_send(outgoing) {
+            Ok(()) => (),
+            Err(mpsc::error::TrySendError::Full(outgoing)) => {
+                (outgoing.callback)(Err(super::Saturated.into()));
+            },
+            Err(mpsc::error::TrySendError::Closed(outgoing)) => {
+                (outgoing.callback)(Err(anyhow!("rpc connection is closed")));
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct Pending {
+    next_id: u64,
+    callbacks: HashMap<u64, Callback>,
+    deadlines: VecDeque<(Instant, u64)>,
+}
+
+impl Pending {
+    fn register(
+        &mut self,
+        outgoing: Outgoing,
+        timeout: Duration,
+    ) -> RpcRequest {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.callbacks.insert(id, outgoing.callback);
+        self.deadlines.push_back((Instant::now() + timeout, id));
+        RpcRequest { id, trace: outgoing.trace, call: outgoing.call }
+    }
+
+    fn resolve(&mut self, response: RpcResponse) {
+        match self.callbacks.remove(&response.id) {
+            Some(callback) => callback(Ok(response.reply)),
+            None => {
+                tracing::debug!(id = response.id, "dropping late rpc response")
+            },
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.deadlines.front().map(|(deadline, _)| *deadline)
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((deadline, id)) = self.deadlines.front().copied()
+            && deadline <= now
+        {
+            self.deadlines.pop_front();
+            if let Some(callback) = self.callbacks.remove(&id) {
+                callback(Err(super::TimedOut.into()));
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.callbacks.len() >= MAX_IN_FLIGHT
+    }
+
+    fn fail_all(&mut self, reason: &str) {
+        self.deadlines.clear();
+        for (_, callback) in self.callbacks.drain() {
+            callback(Err(anyhow!("{reason}")));
+        }
+    }
+}
+
+async fn drive(
+    stream: TcpStream,
+    mut requests: mpsc::Receiver<Outgoing>,
+    timeout: Duration,
+) -> Result<()> {
+    let (reader, writer) = stream.into_split();
+    let mut frames_in = FramedRead::new(reader, super::codec());
+    let mut frames_out = FramedWrite::new(writer, super::codec());
+    let mut pending = Pending::default();
+    let mut batch = Vec::with_capacity(MAX_BATCH_LEN);
+    let mut draining = false;
+
+    let result = loop {
+        if draining && pending.is_empty() {
+            break Ok(());
+        }
+        let next_deadline = pending.next_deadline();
+
+        select! {
+            count = requests.recv_many(&mut batch, MAX_BATCH_LEN),
+                if !draining && !pending.is_full() =>
+            {
+                if count == 0 {
+                    draining = true;
+                    continue;
+                }
+                let requests: Vec<RpcRequest> = batch
+                    .drain(..)
+                    .map(|outgoing| pending.register(outgoing, timeout))
+                    .collect();
+                let frame = match rpc::encode_batch(&requests) {
+                    Ok(frame) => frame,
+                    Err(error) => break Err(error.into()),
+                };
+                if let Err(error) = frames_out.send(Bytes::from(frame)).await {
+                    break Err(error.into());
+                }
+            },
+
+            frame = frames_in.next() => {
+                let frame = match frame {
+                    Some(Ok(frame)) => frame,
+                    Some(Err(error)) => break Err(error.into()),
+                    None => break Err(anyhow!("connection closed by peer")),
+                };
+                let responses: Vec<RpcResponse> =
+                    match rpc::decode_batch(&frame) {
+                        Ok(responses) => responses,
+                        Err(error) => break Err(error.into()),
+                    };
+                for response in responses {
+                    pending.resolve(response);
+                }
+            },
+
+            _ = time::sleep_until(next_deadline.unwrap_or_else(Instant::now)),
+                if next_deadline.is_some() =>
+            {
+                pending.expire(Instant::now());
+            },
+        }
+    };
+
+    requests.close();
+    pending.fail_all("rpc connection closed");
+    while let Ok(outgoing) = requests.try_recv() {
+        (outgoing.callback)(Err(anyhow!("rpc connection closed")));
+    }
+    result
+}
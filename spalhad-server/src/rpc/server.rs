@@ -0,0 +1,202 @@
+use std::{collections::HashMap, net::SocketAddr};
+
+use anyhow::{Result, bail};
+use bytes::Bytes;
+use futures::{FutureExt, SinkExt, StreamExt, stream::FuturesUnordered};
+use opentelemetry::global;
+use spalhad_spec::{
+    ErrorCode,
+    rpc::{
+        self,
+        MAX_BATCH_LEN,
+        MAX_IN_FLIGHT,
+        RpcCall,
+        RpcReply,
+        RpcRequest,
+        RpcResponse,
+    },
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    select,
+};
+use tokio_util::{
+    codec::{FramedRead, FramedWrite},
+    sync::CancellationToken,
+    task::TaskTracker,
+};
+use tracing::{Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::{
+    actor::{bouncer::BouncerHandle, storage},
+    http::{error, v1::kv},
+};
+
+pub async fn serve(
+    bind_address: &str,
+    bouncer: BouncerHandle,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    tracing::info!(%bind_address, "binding rpc socket listener...");
+    let listener = TcpListener::bind(bind_address).await?;
+    tracing::info!("rpc socket bound.");
+    serve_listener(listener, bouncer, cancellation_token).await
+}
+
+pub async fn serve_listener(
+    listener: TcpListener,
+    bouncer: BouncerHandle,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    let connections = TaskTracker::new();
+    loop {
+        let accepted = select! {
+            _ = cancellation_token.cancelled() => break,
+            accepted = listener.accept() => accepted,
+        };
+        let (stream, peer) = match accepted {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                tracing::warn!(%error, "failed to accept rpc connection");
+                continue;
+            },
+        };
+        let span = tracing::info_span!("rpc-connection", %peer);
+        let connection = serve_connection(
+            stream,
+            peer,
+            bouncer.clone(),
+            cancellation_token.clone(),
+        );
+        connections.spawn(connection.instrument(span));
+    }
+    connections.close();
+    connections.wait().await;
+    tracing::info!("rpc server stopped, in-flight requests finished.");
+    Ok(())
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    bouncer: BouncerHandle,
+    cancellation_token: CancellationToken,
+) {
+    tracing::debug!("rpc connection opened");
+    if let Err(error) =
+        try_serve_connection(stream, bouncer, cancellation_token).await
+    {
+        tracing::warn!(%peer, %error, "rpc connection failed");
+    }
+    tracing::debug!("rpc connection closed");
+}
+
+async fn try_serve_connection(
+    stream: TcpStream,
+    bouncer: BouncerHandle,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    stream.set_nodelay(true)?;
+    let (reader, writer) = stream.into_split();
+    let mut frames_in = FramedRead::new(reader, super::codec());
+    let mut frames_out = FramedWrite::new(writer, super::codec());
+    let mut in_flight = FuturesUnordered::new();
+    let mut closing = false;
+
+    loop {
+        select! {
+            _ = cancellation_token.cancelled(), if !closing => {
+                closing = true;
+            },
+
+            frame = frames_in.next(),
+                if !closing && in_flight.len() < MAX_IN_FLIGHT =>
+            match frame {
+                Some(frame) => {
+                    let requests: Vec<RpcRequest> =
+                        rpc::decode_batch(&frame?)?;
+                    if requests.len() > MAX_BATCH_LEN {
+                        bail!("rpc batch of {} requests is too big", requests.len());
+                    }
+                    for request in requests {
+                        in_flight.push(handle(&bouncer, request));
+                    }
+                },
+                None => closing = true,
+            },
+
+            Some(response) = in_flight.next() => {
+                let mut responses = vec![response];
+                while let Some(Some(response)) = in_flight.next().now_or_never()
+                {
+                    responses.push(response);
+                }
+                let frame = rpc::encode_batch(&responses)?;
+                frames_out.send(Bytes::from(frame)).await?;
+            },
+        }
+
+        if closing && in_flight.is_empty() {
+            break Ok(());
+        }
+    }
+}
+
+fn handle(
+    bouncer: &BouncerHandle,
+    request: RpcRequest,
+) -> impl Future<Output = RpcResponse> + use<> {
+    let bouncer = bouncer.clone();
+    let span = request_span(&request);
+    async move {
+        let id = request.id;
+        let reply = match request.call {
+            RpcCall::Get { key } => {
+                tracing::trace!(key = key.to_string(), "handling rpc get");
+                bouncer
+                    .send(storage::Get { key })
+                    .await
+                    .map(|value| RpcReply::Get { value })
+            },
+
+            RpcCall::Put { key, value, origin } => {
+                tracing::trace!(key = key.to_string(), "handling rpc put");
+                match kv::verify_origin(&key, origin.as_ref()) {
+                    Ok(()) => bouncer
+                        .send(storage::Put { key, value, origin })
+                        .await
+                        .map(|new| RpcReply::Put { new }),
+                    Err(error) => Err(error),
+                }
+            },
+
+            RpcCall::Delete { key } => {
+                tracing::trace!(key = key.to_string(), "handling rpc delete");
+                bouncer
+                    .send(storage::Delete { key })
+                    .await
+                    .map(|existed| RpcReply::Delete { existed })
+            },
+        };
+        let reply = reply.unwrap_or_else(|error| {
+            RpcReply::Error(error::describe(&error, ErrorCode::Internal))
+        });
+        RpcResponse { id, reply }
+    }
+    .instrument(span)
+}
+
+fn request_span(request: &RpcRequest) -> Span {
+    let span = tracing::info_span!("rpc-request", id = request.id);
+    if request.trace.is_empty() {
+        return span;
+    }
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&request.trace as &HashMap<String, String>)
+    });
+    if let Err(error) = span.set_parent(parent) {
+        tracing::debug!(%error, "could not link request to remote trace");
+    }
+    span
+}
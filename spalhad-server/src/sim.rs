@@ -0,0 +1,450 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use futures::future;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use spalhad_actor::{
+    ActorCall,
+    ActorOptions,
+    ActorRegistry,
+    CallInjection,
+    TrivialLoopActor,
+};
+use spalhad_spec::{
+    bucket::Bucket,
+    cluster::{PeerStatus, RunId},
+};
+use spalhad_task::TaskManager;
+use tokio::time;
+
+use crate::{
+    actor::{
+        bouncer::{self, Bouncer, BouncerCall, BouncerHandle},
+        coordinator::{self, Coordinator},
+        storage::{
+            self,
+            BreakerConfig,
+            CircuitBreaker,
+            MemoryStorage,
+            PeerBreakers,
+            StorageCall,
+        },
+    },
+    bucket::Buckets,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Faults {
+    pub drop_probability: f64,
+    pub min_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Faults {
+    pub fn none() -> Self {
+        Self {
+            drop_probability: 0.0,
+            min_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct NetworkState {
+    rng: ChaCha8Rng,
+    faults: Faults,
+    nodes: Vec<Option<BouncerHandle>>,
+    down: Vec<bool>,
+    groups: Vec<usize>,
+    slowdowns: Vec<Duration>,
+    delivered: Vec<usize>,
+    attempted: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimNetwork {
+    state: Arc<Mutex<NetworkState>>,
+}
+
+impl SimNetwork {
+    pub fn new(seed: u64, nodes: usize, faults: Faults) -> Self {
+        let state = NetworkState {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            faults,
+            nodes: vec![None; nodes],
+            down: vec![false; nodes],
+            groups: vec![0; nodes],
+            slowdowns: vec![Duration::ZERO; nodes],
+            delivered: vec![0; nodes],
+            attempted: vec![0; nodes],
+        };
+        Self { state: Arc::new(Mutex::new(state)) }
+    }
+
+    fn with_state<F, T>(&self, visitor: F) -> T
+    where
+        F: FnOnce(&mut NetworkState) -> T,
+    {
+        visitor(&mut self.state.lock().expect("poisoned lock"))
+    }
+
+    pub fn set_faults(&self, faults: Faults) {
+        self.with_state(|state| state.faults = faults);
+    }
+
+    pub fn partition(&self, groups: impl IntoIterator<Item = usize>) {
+        self.with_state(|state| {
+            for (node_group, group) in state.groups.iter_mut().zip(groups) {
+                *node_group = group;
+            }
+        });
+    }
+
+    pub fn heal(&self) {
+        self.partition(std::iter::repeat(0));
+    }
+
+    pub fn crash(&self, node: usize) {
+        self.with_state(|state| state.down[node] = true);
+    }
+
+    pub fn recover(&self, node: usize) {
+        self.with_state(|state| state.down[node] = false);
+    }
+
+    pub fn is_down(&self, node: usize) -> bool {
+        self.with_state(|state| state.down[node])
+    }
+
+    pub fn slow_down(&self, node: usize, delay: Duration) {
+        self.with_state(|state| state.slowdowns[node] = delay);
+    }
+
+    pub fn delivered(&self, node: usize) -> usize {
+        self.with_state(|state| state.delivered[node])
+    }
+
+    pub fn attempted(&self, node: usize) -> usize {
+        self.with_state(|state| state.attempted[node])
+    }
+
+    fn attach(&self, node: usize, bouncer: BouncerHandle) {
+        self.with_state(|state| state.nodes[node] = Some(bouncer));
+    }
+
+    fn detach(&self, node: usize) {
+        self.with_state(|state| state.nodes[node] = None);
+    }
+
+    fn target(&self, node: usize) -> Option<BouncerHandle> {
+        self.with_state(|state| state.nodes[node].clone())
+    }
+
+    fn transmit(&self, from: usize, to: usize) -> Option<Duration> {
+        self.with_state(|state| {
+            state.attempted[to] += 1;
+            let faults = state.faults;
+            let dropped = state.rng.random_bool(faults.drop_probability);
+            let delay =
+                state.rng.random_range(faults.min_delay ..= faults.max_delay);
+            let reachable = !state.down[from]
+                && !state.down[to]
+                && state.groups[from] == state.groups[to];
+            if !reachable || dropped {
+                return None;
+            }
+            state.delivered[to] += 1;
+            Some(delay + state.slowdowns[to])
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimStorage {
+    from: usize,
+    to: usize,
+    network: SimNetwork,
+    timeout: Duration,
+    breaker: Option<CircuitBreaker>,
+}
+
+impl SimStorage {
+    pub fn open(
+        from: usize,
+        to: usize,
+        network: SimNetwork,
+        timeout: Duration,
+    ) -> Self {
+        Self { from, to, network, timeout, breaker: None }
+    }
+
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    async fn round_trip<I, O>(&self, input: I) -> Result<O>
+    where
+        BouncerCall: CallInjection<ActorCall<I, O>>,
+    {
+        let Some(breaker) = &self.breaker else {
+            return self.exchange(input).await?;
+        };
+        breaker.try_acquire()?;
+        let output = self.exchange(input).await;
+        breaker.record(&output);
+        output?
+    }
+
+    async fn exchange<I, O>(&self, input: I) -> Result<Result<O>>
+    where
+        BouncerCall: CallInjection<ActorCall<I, O>>,
+    {
+        let exchange = async {
+            let Some(delay) = self.network.transmit(self.from, self.to) else {
+                return future::pending().await;
+            };
+            time::sleep(delay).await;
+            let target = self
+                .network
+                .target(self.to)
+                .with_context(|| format!("node {} is not attached", self.to))?;
+            let output = target.send(input).await;
+            let Some(delay) = self.network.transmit(self.to, self.from) else {
+                return future::pending().await;
+            };
+            time::sleep(delay).await;
+            Ok(output)
+        };
+        time::timeout(self.timeout, exchange)
+            .await
+            .with_context(|| format!("node {} timed out", self.to))?
+    }
+}
+
+impl TrivialLoopActor for SimStorage {
+    type Call = StorageCall;
+
+    async fn on_call(&mut self, call: Self::Call) -> Result<()> {
+        match call {
+            StorageCall::Get(call) => {
+                call.handle(|input| async {
+                    tracing::trace!(
+                        key = input.key.to_string(),
+                        from = self.from,
+                        to = self.to,
+                        "handling get simulated storage request",
+                    );
+                    self.round_trip(input).await
+                })
+                .await;
+            },
+
+            StorageCall::Put(call) => {
+                call.handle(|input| async {
+                    tracing::trace!(
+                        key = input.key.to_string(),
+                        from = self.from,
+                        to = self.to,
+                        "handling put simulated storage request",
+                    );
+                    self.round_trip(input).await
+                })
+                .await;
+            },
+
+            StorageCall::Delete(call) => {
+                call.handle(|input| async {
+                    tracing::trace!(
+                        key = input.key.to_string(),
+                        from = self.from,
+                        to = self.to,
+                        "handling delete simulated storage request",
+                    );
+                    self.round_trip(input).await
+                })
+                .await;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub nodes: usize,
+    pub replication: usize,
+    pub min_correct_reads: usize,
+    pub min_correct_writes: usize,
+    pub concurrency_level: usize,
+    pub timeout: Duration,
+    pub faults: Faults,
+    pub hedge_percentile: Option<f64>,
+    pub breaker: Option<BreakerConfig>,
+    pub buckets: Vec<Bucket>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimNode {
+    bouncer: BouncerHandle,
+    run_id: RunId,
+    breakers: Option<PeerBreakers>,
+    buckets: Buckets,
+    task_manager: TaskManager,
+}
+
+impl SimNode {
+    pub fn bouncer(&self) -> &BouncerHandle {
+        &self.bouncer
+    }
+
+    pub fn run_id(&self) -> RunId {
+        self.run_id
+    }
+
+    pub fn breakers(&self) -> Option<&PeerBreakers> {
+        self.breakers.as_ref()
+    }
+
+    pub fn buckets(&self) -> &Buckets {
+        &self.buckets
+    }
+}
+
+#[derive(Debug)]
+pub struct SimCluster {
+    network: SimNetwork,
+    nodes: Vec<Option<SimNode>>,
+}
+
+impl SimCluster {
+    pub fn spawn(
+        options: &ActorOptions,
+        seed: u64,
+        config: &SimConfig,
+    ) -> Self {
+        let network = SimNetwork::new(seed, config.nodes, config.faults);
+        let mut nodes = Vec::with_capacity(config.nodes);
+
+        for i in 0 .. config.nodes {
+            let task_manager = TaskManager::new();
+            let registry = ActorRegistry::new();
+            registry.clear_on_shutdown(&task_manager);
+            let options = options
+                .clone()
+                .with_task_manager(&task_manager)
+                .with_registry(Some(registry.clone()));
+            let storage = options.spawn_named(
+                format!("MemoryStorage[{i}]"),
+                MemoryStorage::open(),
+            );
+            let breakers = config.breaker.map(PeerBreakers::new);
+            let buckets = Buckets::from_buckets(
+                i,
+                config.nodes,
+                config.replication,
+                config.buckets.iter().cloned(),
+            )
+            .expect("invalid bucket");
+            let peers = (0 .. config.nodes).map(|j| {
+                if i == j {
+                    return storage.clone();
+                }
+                let mut peer =
+                    SimStorage::open(i, j, network.clone(), config.timeout);
+                if let Some(breakers) = &breakers {
+                    peer = peer.with_circuit_breaker(breakers.breaker(j));
+                }
+                options.spawn_named(storage::peer_name(j), peer)
+            });
+            let peer_names = (0 .. config.nodes)
+                .filter(|j| *j != i)
+                .map(|j| (j, storage::peer_name(j)));
+            let coordinator = Coordinator::new(
+                config.replication,
+                config.min_correct_reads,
+                config.min_correct_writes,
+                config.concurrency_level,
+                peers.collect::<Vec<_>>(),
+            )
+            .with_registry(registry, peer_names)
+            .with_hedging(config.hedge_percentile)
+            .with_breakers(breakers.clone())
+            .with_buckets(Some(buckets.clone()));
+            let coordinator =
+                options.spawn_named(format!("Coordinator[{i}]"), coordinator);
+            let run_id = RunId::generate();
+            let bouncer = options.spawn_named(
+                format!("Bouncer[{i}]"),
+                Bouncer::open(run_id, storage, coordinator),
+            );
+            network.attach(i, bouncer.clone());
+            nodes.push(Some(SimNode {
+                bouncer,
+                run_id,
+                breakers,
+                buckets,
+                task_manager,
+            }));
+        }
+
+        Self { network, nodes }
+    }
+
+    pub async fn activate_all(&self) -> Result<()> {
+        for node in self.nodes.iter().flatten() {
+            node.bouncer
+                .send(bouncer::Activate { run_id: node.run_id })
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub fn network(&self) -> &SimNetwork {
+        &self.network
+    }
+
+    pub fn node(&self, index: usize) -> &SimNode {
+        self.nodes[index].as_ref().expect("node was shut down")
+    }
+
+    pub async fn shutdown(&mut self, index: usize) -> Result<()> {
+        let node = self.nodes[index].take().context("node was shut down")?;
+        let announcements = (0 .. self.nodes.len())
+            .filter(|peer| *peer != index)
+            .map(|peer| self.announce(index, peer, PeerStatus::Leaving));
+        future::join_all(announcements).await;
+        self.network.detach(index);
+        let SimNode { bouncer, task_manager, .. } = node;
+        drop(bouncer);
+        task_manager.cancel();
+        task_manager.wait_all().await
+    }
+
+    async fn announce(&self, from: usize, to: usize, status: PeerStatus) {
+        let announcement = async {
+            let delay = self.network.transmit(from, to)?;
+            time::sleep(delay).await;
+            let target = self.network.target(to)?;
+            let status = coordinator::SetPeerStatus { node_id: from, status };
+            target.send(status).await.ok()
+        };
+        if announcement.await.is_none() {
+            tracing::warn!(from, to, "failed to announce status");
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
@@ -1,6 +1,21 @@
+use std::time::Duration;
+
 use anyhow::Result;
+use futures::future;
 use spalhad_client::Client;
-use spalhad_spec::cluster::RunId;
+use spalhad_spec::{
+    bucket::Bucket,
+    cluster::{PeerStatus, RunId},
+};
+
+use spalhad_task::RestartPolicy;
+use tokio::{
+    select,
+    time::{self, Instant, MissedTickBehavior},
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::bucket::Buckets;
 
 pub async fn activate(self_run_id: RunId, self_base_url: &str) -> Result<()> {
     tracing::info!(
@@ -14,3 +29,150 @@ pub async fn activate(self_run_id: RunId, self_base_url: &str) -> Result<()> {
     tracing::info!("Done. Active.");
     Ok(())
 }
+
+pub async fn announce(
+    self_id: usize,
+    status: PeerStatus,
+    addresses: &[String],
+    timeout: Duration,
+) -> Result<()> {
+    tracing::info!(?status, "Announcing status to peers...");
+    let announcements = addresses
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != self_id)
+        .map(|(_, address)| async move {
+            let result = announce_to(address, self_id, status, timeout).await;
+            if let Err(error) = result {
+                tracing::warn!(%address, %error, "Failed to announce status");
+            }
+        });
+    future::join_all(announcements).await;
+    tracing::info!("Done. Announced.");
+    Ok(())
+}
+
+async fn announce_to(
+    address: &str,
+    self_id: usize,
+    status: PeerStatus,
+    timeout: Duration,
+) -> Result<()> {
+    let client = Client::with_timeout(address, timeout)?;
+    client.set_peer_status(self_id, status).await?;
+    Ok(())
+}
+
+pub async fn broadcast_bucket(
+    self_id: usize,
+    bucket: &Bucket,
+    addresses: &[String],
+    timeout: Duration,
+) -> Vec<usize> {
+    let broadcasts = addresses
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != self_id)
+        .map(|(i, address)| async move {
+            let result = broadcast_bucket_to(address, bucket, timeout).await;
+            match result {
+                Ok(()) => None,
+                Err(error) => {
+                    tracing::warn!(%address, %error, "Failed to sync bucket");
+                    Some(i)
+                },
+            }
+        });
+    future::join_all(broadcasts).await.into_iter().flatten().collect()
+}
+
+async fn broadcast_bucket_to(
+    address: &str,
+    bucket: &Bucket,
+    timeout: Duration,
+) -> Result<()> {
+    let client = Client::with_timeout(address, timeout)?;
+    client.sync_bucket(bucket).await?;
+    Ok(())
+}
+
+pub async fn pull_buckets(
+    self_id: usize,
+    buckets: &Buckets,
+    addresses: &[String],
+    timeout: Duration,
+) -> Vec<usize> {
+    let pulls = addresses
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != self_id)
+        .map(|(i, address)| async move {
+            let result = pull_buckets_from(address, buckets, timeout).await;
+            match result {
+                Ok(()) => None,
+                Err(error) => {
+                    tracing::warn!(%address, %error, "Failed to pull buckets");
+                    Some(i)
+                },
+            }
+        });
+    future::join_all(pulls).await.into_iter().flatten().collect()
+}
+
+pub async fn pull_buckets_at_start(
+    self_id: usize,
+    buckets: &Buckets,
+    addresses: &[String],
+    timeout: Duration,
+    policy: RestartPolicy,
+) {
+    tracing::info!("Pulling buckets from peers...");
+    let peers = addresses.len().saturating_sub(1);
+    for attempt in 0 ..= policy.max_restarts {
+        let unreachable =
+            pull_buckets(self_id, buckets, addresses, timeout).await;
+        if unreachable.len() < peers || peers == 0 {
+            tracing::info!(?unreachable, "Done. Pulled buckets.");
+            return;
+        }
+        if attempt < policy.max_restarts {
+            time::sleep(policy.backoff).await;
+        }
+    }
+    tracing::warn!("No peer answered, serving with the persisted buckets");
+}
+
+pub async fn resync_buckets(
+    self_id: usize,
+    buckets: &Buckets,
+    addresses: &[String],
+    timeout: Duration,
+    interval: Duration,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    let mut ticks = time::interval_at(Instant::now() + interval, interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        select! {
+            _ = ticks.tick() => (),
+            _ = cancellation_token.cancelled() => return Ok(()),
+        }
+        let unreachable =
+            pull_buckets(self_id, buckets, addresses, timeout).await;
+        tracing::debug!(?unreachable, "Re-synced buckets");
+    }
+}
+
+async fn pull_buckets_from(
+    address: &str,
+    buckets: &Buckets,
+    timeout: Duration,
+) -> Result<()> {
+    let client = Client::with_timeout(address, timeout)?;
+    let response = client.buckets().await?;
+    for bucket in response.buckets {
+        buckets.apply(bucket).await?;
+    }
+    buckets.merge_written(response.written).await?;
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Result, bail};
+use spalhad_spec::cluster::{
+    ClusterConfig,
+    PeerStatus,
+    TopologyNode,
+    TopologyResponse,
+    topology_version,
+};
+
+#[derive(Debug)]
+struct State {
+    self_id: usize,
+    replication: usize,
+    nodes: Vec<TopologyNode>,
+}
+
+impl State {
+    fn version(&self) -> u64 {
+        topology_version(self.replication, &self.nodes)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Topology {
+    state: Arc<Mutex<State>>,
+}
+
+impl Topology {
+    pub fn new(self_id: usize, cluster_config: &ClusterConfig) -> Self {
+        let nodes = cluster_config
+            .addresses
+            .iter()
+            .map(|address| TopologyNode {
+                address: address.clone(),
+                status: PeerStatus::Joined,
+            })
+            .collect();
+        let state =
+            State { self_id, replication: cluster_config.replication, nodes };
+        Self { state: Arc::new(Mutex::new(state)) }
+    }
+
+    fn with_state<F, T>(&self, visitor: F) -> T
+    where
+        F: FnOnce(&mut State) -> T,
+    {
+        visitor(&mut self.state.lock().expect("poisoned lock"))
+    }
+
+    pub fn version(&self) -> u64 {
+        self.with_state(|state| state.version())
+    }
+
+    pub fn set_status(&self, node_id: usize, status: PeerStatus) -> Result<()> {
+        self.update(node_id, |node| {
+            let changed = node.status != status;
+            node.status = status;
+            changed
+        })
+    }
+
+    pub fn set_address(&self, node_id: usize, address: &str) -> Result<()> {
+        self.update(node_id, |node| {
+            let changed = node.address != address;
+            node.address = address.to_owned();
+            changed
+        })
+    }
+
+    fn update<F>(&self, node_id: usize, visitor: F) -> Result<()>
+    where
+        F: FnOnce(&mut TopologyNode) -> bool,
+    {
+        self.with_state(|state| {
+            let Some(node) = state.nodes.get_mut(node_id) else {
+                bail!("unknown node {node_id}");
+            };
+            if visitor(node) {
+                tracing::debug!(version = state.version(), "topology changed");
+            }
+            Ok(())
+        })
+    }
+
+    pub fn snapshot(&self) -> TopologyResponse {
+        self.with_state(|state| TopologyResponse {
+            version: state.version(),
+            self_id: state.self_id,
+            replication: state.replication,
+            nodes: state.nodes.clone(),
+        })
+    }
+}
@@ -0,0 +1,123 @@
+use std::{io, time::Duration};
+
+use anyhow::{Result, anyhow};
+use spalhad_server::{
+    actor::storage::{
+        BreakerConfig,
+        BreakerState,
+        CircuitBreaker,
+        CircuitOpen,
+        PeerBreakers,
+        is_peer_failure,
+    },
+    rpc::TimedOut,
+};
+use tokio::time::{self, Instant};
+
+const COOLDOWN: Duration = Duration::from_secs(5);
+
+fn breaker(failure_threshold: usize) -> CircuitBreaker {
+    CircuitBreaker::new(BreakerConfig::new(failure_threshold, COOLDOWN))
+}
+
+fn refused() -> anyhow::Error {
+    io::Error::from(io::ErrorKind::ConnectionRefused).into()
+}
+
+#[tokio::test(start_paused = true)]
+async fn opens_after_consecutive_failures() {
+    let breaker = breaker(3);
+    breaker.record_failure();
+    breaker.record_failure();
+    breaker.record_success();
+    breaker.record_failure();
+    breaker.record_failure();
+    assert_eq!(breaker.snapshot().state, BreakerState::Closed);
+    assert_eq!(breaker.snapshot().consecutive_failures, 2);
+
+    breaker.record_failure();
+    assert_eq!(
+        breaker.snapshot().state,
+        BreakerState::Open { until: Instant::now() + COOLDOWN }
+    );
+    assert!(breaker.is_open());
+    assert_eq!(breaker.try_acquire(), Err(CircuitOpen));
+}
+
+#[tokio::test(start_paused = true)]
+async fn lets_one_probe_through_after_cooldown() {
+    let breaker = breaker(1);
+    breaker.record_failure();
+    time::advance(COOLDOWN).await;
+    assert!(!breaker.is_open());
+
+    assert_eq!(breaker.try_acquire(), Ok(()));
+    assert_eq!(
+        breaker.snapshot().state,
+        BreakerState::HalfOpen { until: Instant::now() + COOLDOWN }
+    );
+    assert_eq!(breaker.try_acquire(), Err(CircuitOpen));
+
+    breaker.record_success();
+    assert_eq!(breaker.snapshot().state, BreakerState::Closed);
+    assert_eq!(breaker.snapshot().consecutive_failures, 0);
+    assert_eq!(breaker.try_acquire(), Ok(()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn failed_probe_reopens_immediately() {
+    let breaker = breaker(3);
+    for _ in 0 .. 3 {
+        breaker.record_failure();
+    }
+    time::advance(COOLDOWN).await;
+    assert_eq!(breaker.try_acquire(), Ok(()));
+
+    breaker.record_failure();
+    assert_eq!(
+        breaker.snapshot().state,
+        BreakerState::Open { until: Instant::now() + COOLDOWN }
+    );
+    assert_eq!(breaker.snapshot().consecutive_failures, 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn guard_counts_only_transport_and_timeout_errors() {
+    let breaker = breaker(2);
+    let rejected: Result<()> =
+        breaker.guard(async { Err(anyhow!("node is not active")) }).await;
+    assert!(rejected.is_err());
+    assert_eq!(breaker.snapshot().consecutive_failures, 0);
+
+    let refused: Result<()> = breaker.guard(async { Err(refused()) }).await;
+    assert!(refused.is_err());
+    let timed_out: Result<()> =
+        breaker.guard(async { Err(TimedOut.into()) }).await;
+    assert!(timed_out.is_err());
+    assert!(breaker.is_open());
+
+    let skipped = breaker.guard(async { Ok(()) }).await;
+    let error = skipped.expect_err("open breaker should skip the request");
+    assert!(error.is::<CircuitOpen>());
+}
+
+#[test]
+fn classifies_peer_failures() {
+    assert!(is_peer_failure(&refused()));
+    assert!(is_peer_failure(&refused().context("sending request")));
+    assert!(is_peer_failure(&TimedOut.into()));
+    assert!(!is_peer_failure(&anyhow!("key not found")));
+    assert!(!is_peer_failure(&CircuitOpen.into()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn peer_breakers_share_and_reset_state() {
+    let breakers = PeerBreakers::new(BreakerConfig::new(1, COOLDOWN));
+    assert!(breakers.get(1).is_none());
+    breakers.breaker(1).record_failure();
+    assert!(breakers.get(1).expect("breaker for node 1").is_open());
+
+    let fresh = breakers.reset(1);
+    assert!(!fresh.is_open());
+    assert_eq!(breakers.snapshot(), [(1, fresh.snapshot())]);
+}
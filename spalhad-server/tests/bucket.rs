@@ -0,0 +1,77 @@
+use std::{fs, path::PathBuf, process};
+
+use spalhad_server::bucket::{Buckets, ReplicationLocked};
+use spalhad_spec::{bucket::BucketConfig, kv::Namespace};
+
+fn namespace(name: &str) -> Namespace {
+    name.parse().expect("valid namespace")
+}
+
+fn config(replication: usize, quorum: usize) -> BucketConfig {
+    BucketConfig {
+        replication,
+        min_correct_reads: quorum,
+        min_correct_writes: quorum,
+    }
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("spalhad-bucket-{name}-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("create scratch dir");
+    dir
+}
+
+#[tokio::test]
+async fn buckets_survive_a_restart() {
+    let dir = scratch_dir("restart");
+    let path = dir.join("buckets.json");
+    let billing = namespace("billing");
+
+    let buckets = Buckets::open(0, 5, 3, &path).expect("open empty");
+    assert!(buckets.snapshot().is_empty());
+    let bucket = buckets
+        .configure(billing.clone(), config(5, 3))
+        .await
+        .expect("configure");
+    assert!(buckets.mark_written(&billing));
+    assert!(!buckets.mark_written(&billing));
+    buckets.persist().await.expect("persist");
+    drop(buckets);
+
+    let reopened = Buckets::open(0, 5, 3, &path).expect("reopen");
+    assert_eq!(reopened.snapshot(), [bucket]);
+    assert_eq!(reopened.written(), std::slice::from_ref(&billing));
+    let error = reopened
+        .configure(billing.clone(), config(3, 2))
+        .await
+        .expect_err("written bucket keeps its replication");
+    assert_eq!(
+        error.downcast::<ReplicationLocked>().ok(),
+        Some(ReplicationLocked(billing))
+    );
+    assert!(!dir.join("buckets.tmp").exists());
+
+    fs::remove_dir_all(&dir).expect("remove scratch dir");
+}
+
+#[tokio::test]
+async fn written_namespaces_lock_the_default_replication_too() {
+    let buckets = Buckets::new(0, 5, 3);
+    let fresh = namespace("fresh");
+    let used = namespace("used");
+    buckets.merge_written([used.clone()]).await.expect("merge written");
+
+    assert!(!buckets.changes_replication(&used, &config(3, 1)));
+    buckets
+        .configure(used.clone(), config(3, 1))
+        .await
+        .expect("same replication");
+    assert!(buckets.changes_replication(&used, &config(1, 1)));
+    buckets
+        .configure(used, config(1, 1))
+        .await
+        .expect_err("default replication is locked");
+    buckets.configure(fresh, config(1, 1)).await.expect("empty bucket");
+}
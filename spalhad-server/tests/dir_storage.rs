@@ -0,0 +1,162 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use serde_json::json;
+use spalhad_actor::{ActorHandle, ActorOptions};
+use spalhad_client::Client;
+use spalhad_server::{
+    actor::{
+        bouncer,
+        coordinator::Coordinator,
+        storage::{self, DirStorage, StorageCall},
+    },
+    http::{self, App},
+};
+use spalhad_spec::kv::{BytesValue, KEY_ORIGIN_HEADER, Key, KeyOrigin, Value};
+use spalhad_task::TaskManager;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("spalhad-dir-{name}-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("create scratch dir");
+    dir
+}
+
+fn entry(dir: &Path, key: &Key, extension: &str) -> PathBuf {
+    dir.join(format!("{key}.{extension}"))
+}
+
+fn bytes(content_type: &str, data: &'static [u8]) -> Value {
+    Value::Bytes(BytesValue::new(content_type.to_owned(), data))
+}
+
+async fn put(
+    storage: &ActorHandle<StorageCall>,
+    key: &Key,
+    value: Value,
+) -> bool {
+    storage
+        .send(storage::Put { key: key.clone(), value, origin: None })
+        .await
+        .expect("put")
+}
+
+async fn get(storage: &ActorHandle<StorageCall>, key: &Key) -> Option<Value> {
+    storage.send(storage::Get { key: key.clone() }).await.expect("get")
+}
+
+#[tokio::test]
+async fn switching_value_kinds_leaves_a_single_entry() {
+    let dir = scratch_dir("switch");
+    let task_manager = TaskManager::new();
+    let storage =
+        ActorOptions::new(&task_manager).spawn(DirStorage::open(&dir));
+    let key = Key::from_bytes_key("foo");
+
+    assert!(put(&storage, &key, Value::Json(json!({"a": 1}))).await);
+    let image = bytes("image/png", b"\x89PNG\n\0");
+    assert!(!put(&storage, &key, image.clone()).await);
+    assert_eq!(get(&storage, &key).await, Some(image));
+    assert!(!put(&storage, &key, Value::Json(json!("back"))).await);
+    assert_eq!(get(&storage, &key).await, Some(Value::Json(json!("back"))));
+
+    let mut files: Vec<_> = fs::read_dir(&dir)
+        .expect("list dir")
+        .map(|entry| entry.expect("entry").file_name().into_string())
+        .collect();
+    files.sort();
+    assert_eq!(files, [Ok(format!("{key}.value"))]);
+
+    assert!(
+        storage
+            .send(storage::Delete { key: key.clone() })
+            .await
+            .expect("delete")
+    );
+    assert_eq!(get(&storage, &key).await, None);
+    fs::remove_dir_all(&dir).expect("remove scratch dir");
+}
+
+#[tokio::test]
+async fn leftovers_of_an_interrupted_write_are_ignored() {
+    let dir = scratch_dir("crash");
+    let task_manager = TaskManager::new();
+    let storage =
+        ActorOptions::new(&task_manager).spawn(DirStorage::open(&dir));
+    let key = Key::from_bytes_key("foo");
+
+    fs::write(entry(&dir, &key, "json"), b"\"stale\"").expect("legacy json");
+    assert_eq!(get(&storage, &key).await, Some(Value::Json(json!("stale"))));
+    fs::write(entry(&dir, &key, "value.tmp"), b"json\n\"torn").expect("temp");
+    assert_eq!(get(&storage, &key).await, Some(Value::Json(json!("stale"))));
+
+    fs::write(entry(&dir, &key, "value"), b"bytes text/plain\nfresh")
+        .expect("value");
+    assert_eq!(get(&storage, &key).await, Some(bytes("text/plain", b"fresh")));
+
+    let legacy = Key::from_bytes_key("legacy");
+    fs::write(entry(&dir, &legacy, "bin"), b"raw").expect("legacy bytes");
+    fs::write(entry(&dir, &legacy, "type"), b"text/csv").expect("legacy type");
+    assert_eq!(get(&storage, &legacy).await, Some(bytes("text/csv", b"raw")));
+    assert!(!put(&storage, &legacy, Value::Json(json!(1))).await);
+    assert!(!entry(&dir, &legacy, "bin").exists());
+    assert!(!entry(&dir, &legacy, "type").exists());
+    fs::remove_dir_all(&dir).expect("remove scratch dir");
+}
+
+#[tokio::test]
+async fn binary_puts_keep_the_original_key() {
+    let dir = scratch_dir("origin");
+    let task_manager = TaskManager::new();
+    let options = ActorOptions::new(&task_manager);
+    let storage = options.spawn(DirStorage::open(&dir));
+    let coordinator =
+        options.spawn(Coordinator::new(1, 1, 1, 1, vec![storage.clone()]));
+    let app = App::new(&options, storage, coordinator);
+    app.bouncer()
+        .send(bouncer::Activate { run_id: app.self_run_id() })
+        .await
+        .expect("activate");
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let address = listener.local_addr().expect("local address");
+    let server = tokio::spawn(async move {
+        axum::serve(listener, http::app_router(app)).await
+    });
+
+    let client =
+        Client::new(format!("http://{address}")).with_store_original_keys(true);
+    let value = BytesValue::new("text/plain".to_owned(), &b"hi"[..]);
+    assert!(client.put_bytes("chave-ção", value.clone()).await.expect("put"));
+    assert_eq!(client.get_bytes("chave-ção").await.expect("get"), Some(value));
+    let key = Key::from_bytes_key("chave-ção");
+    let origin = fs::read(entry(&dir, &key, "origin.json")).expect("origin");
+    let origin: KeyOrigin = serde_json::from_slice(&origin).expect("json");
+    assert_eq!(origin, KeyOrigin::new("chave-ção"));
+
+    let wrong = KeyOrigin::new("other").to_header_value();
+    let head = [
+        format!("POST /spalhad/v1/kv/{key} HTTP/1.1"),
+        format!("host: {address}"),
+        "content-type: text/plain".to_owned(),
+        format!("{KEY_ORIGIN_HEADER}: {wrong}"),
+        "content-length: 2".to_owned(),
+        "connection: close".to_owned(),
+    ];
+    let mut stream = TcpStream::connect(address).await.expect("connect");
+    let request = format!("{}\r\n\r\nhi", head.join("\r\n"));
+    stream.write_all(request.as_bytes()).await.expect("request");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.expect("response");
+    assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+
+    server.abort();
+    fs::remove_dir_all(&dir).expect("remove scratch dir");
+}
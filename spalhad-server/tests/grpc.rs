@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use spalhad_actor::ActorOptions;
+use spalhad_server::{
+    actor::coordinator,
+    grpc::{
+        KvService,
+        MAX_WATCHERS,
+        proto::{self, key_ref, kv_server::Kv},
+    },
+    sim::{Faults, SimCluster, SimConfig},
+};
+use spalhad_spec::kv::{Key, Value};
+use spalhad_task::TaskManager;
+use tokio::{runtime, time};
+use tokio_util::sync::CancellationToken;
+use tonic::{Code, Request};
+
+fn simulate<F>(future: F) -> F::Output
+where
+    F: Future,
+{
+    runtime::Builder::new_current_thread()
+        .enable_time()
+        .start_paused(true)
+        .build()
+        .expect("failed to build simulation runtime")
+        .block_on(future)
+}
+
+fn config() -> SimConfig {
+    SimConfig {
+        nodes: 3,
+        replication: 3,
+        min_correct_reads: 2,
+        min_correct_writes: 2,
+        concurrency_level: 3,
+        timeout: Duration::from_millis(200),
+        faults: Faults::none(),
+        hedge_percentile: None,
+        breaker: None,
+        buckets: Vec::new(),
+    }
+}
+
+fn watch_request(key: &Key) -> Request<proto::WatchRequest> {
+    let key = proto::KeyRef {
+        namespace: String::new(),
+        key: Some(key_ref::Key::Hash(key.to_string())),
+    };
+    Request::new(proto::WatchRequest { key: Some(key), poll_interval_ms: 1 })
+}
+
+#[test]
+fn watch_retries_while_quorum_is_unavailable() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let cluster = SimCluster::spawn(&options, 0, &config());
+        cluster.activate_all().await.expect("activation should not fail");
+        let bouncer = cluster.node(0).bouncer();
+        let service = KvService::new(bouncer.clone(), CancellationToken::new());
+        let key = Key::from_bytes([7; 32]);
+
+        cluster.network().crash(1);
+        cluster.network().crash(2);
+        let mut events = service
+            .watch(watch_request(&key))
+            .await
+            .expect("watch should open")
+            .into_inner();
+        let pending = time::timeout(Duration::from_secs(10), events.next());
+        assert!(pending.await.is_err(), "watch should keep retrying");
+
+        cluster.network().recover(1);
+        cluster.network().recover(2);
+        let event = time::timeout(Duration::from_secs(60), events.next())
+            .await
+            .expect("watch should recover")
+            .expect("stream should stay open")
+            .expect("event should not be an error");
+        assert_eq!(event.value, None);
+
+        bouncer
+            .send(coordinator::Put {
+                key: key.clone(),
+                value: Value::Json(serde_json::json!("watched")),
+                namespace: None,
+                origin: None,
+            })
+            .await
+            .expect("put should reach quorum");
+        let event = time::timeout(Duration::from_secs(5), events.next())
+            .await
+            .expect("watch should see the put")
+            .expect("stream should stay open")
+            .expect("event should not be an error");
+        assert!(event.value.is_some());
+    });
+}
+
+#[test]
+fn watches_beyond_the_limit_are_rejected() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let cluster = SimCluster::spawn(&options, 0, &config());
+        let service = KvService::new(
+            cluster.node(0).bouncer().clone(),
+            CancellationToken::new(),
+        );
+        let key = Key::from_bytes([7; 32]);
+
+        let mut watches = Vec::new();
+        for _ in 0 .. MAX_WATCHERS {
+            let watch = service.watch(watch_request(&key)).await;
+            watches.push(watch.expect("watch below the limit should open"));
+        }
+        let status = service
+            .watch(watch_request(&key))
+            .await
+            .err()
+            .expect("watch above the limit should fail");
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        watches.pop();
+        service
+            .watch(watch_request(&key))
+            .await
+            .expect("closing a watch should free a slot");
+    });
+}
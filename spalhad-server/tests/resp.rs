@@ -0,0 +1,176 @@
+use bytes::{Bytes, BytesMut};
+use spalhad_server::resp::{
+    CodecError,
+    MAX_ARGS,
+    MAX_BULK_LEN,
+    MAX_INLINE_LEN,
+    Reply,
+    RespCodec,
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+fn decode(input: &[u8]) -> (Result<Option<Vec<Bytes>>, CodecError>, BytesMut) {
+    let mut buf = BytesMut::from(input);
+    let decoded = RespCodec::default().decode(&mut buf);
+    (decoded, buf)
+}
+
+fn args(items: &[&str]) -> Vec<Bytes> {
+    items.iter().map(|item| Bytes::copy_from_slice(item.as_bytes())).collect()
+}
+
+fn protocol_error(input: &[u8]) -> &'static str {
+    match decode(input).0 {
+        Err(CodecError::Protocol(message)) => message,
+        other => panic!("expected a protocol error, got {other:?}"),
+    }
+}
+
+fn encode(reply: Reply) -> BytesMut {
+    let mut buf = BytesMut::new();
+    RespCodec::default().encode(reply, &mut buf).expect("encoding cannot fail");
+    buf
+}
+
+#[test]
+fn multibulk_commands_are_decoded() {
+    let (decoded, rest) = decode(b"*2\r\n$3\r\nGET\r\n$5\r\nhe\r\no\r\n");
+    assert_eq!(decoded.expect("valid frame"), Some(args(&["GET", "he\r\no"])));
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn partial_frames_wait_for_more_data() {
+    let frame = b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n";
+    for len in 0 .. frame.len() {
+        let (decoded, rest) = decode(&frame[.. len]);
+        assert_eq!(decoded.expect("prefix is not an error"), None, "{len}");
+        assert_eq!(&rest[..], &frame[.. len]);
+    }
+
+    let mut buf = BytesMut::new();
+    let mut codec = RespCodec::default();
+    for byte in frame {
+        assert_eq!(codec.decode(&mut buf).expect("prefix"), None);
+        buf.extend_from_slice(&[*byte]);
+    }
+    assert_eq!(
+        codec.decode(&mut buf).expect("frame"),
+        Some(args(&["GET", "key"]))
+    );
+}
+
+#[test]
+fn partial_arrays_resume_where_they_stopped() {
+    let mut buf = BytesMut::from(&b"*65536\r\n$3\r\nGET\r\n$3"[..]);
+    let mut codec = RespCodec::default();
+    assert_eq!(codec.decode(&mut buf).expect("prefix"), None);
+    buf.extend_from_slice(b"\r\nkeyXX");
+    let error = codec.decode(&mut buf).expect_err("missing CRLF");
+    assert!(matches!(
+        error,
+        CodecError::Protocol("expected CRLF after bulk data")
+    ));
+
+    let mut buf = BytesMut::from(&b"*1\r\n$4\r\nPING\r\n"[..]);
+    assert_eq!(codec.decode(&mut buf).expect("fresh"), Some(args(&["PING"])));
+}
+
+#[test]
+fn pipelined_frames_are_decoded_one_at_a_time() {
+    let mut buf =
+        BytesMut::from(&b"*1\r\n$4\r\nPING\r\nGET key\r\n*1\r\n$4"[..]);
+    let mut codec = RespCodec::default();
+    assert_eq!(codec.decode(&mut buf).expect("first"), Some(args(&["PING"])));
+    assert_eq!(
+        codec.decode(&mut buf).expect("second"),
+        Some(args(&["GET", "key"])),
+    );
+    assert_eq!(codec.decode(&mut buf).expect("partial third"), None);
+    assert_eq!(&buf[..], b"*1\r\n$4");
+}
+
+#[test]
+fn inline_commands_are_split_on_whitespace() {
+    let (decoded, rest) = decode(b"  SET\tkey   value \r\n");
+    assert_eq!(
+        decoded.expect("valid line"),
+        Some(args(&["SET", "key", "value"]))
+    );
+    assert!(rest.is_empty());
+
+    let (decoded, rest) = decode(b"PING");
+    assert_eq!(decoded.expect("partial line"), None);
+    assert_eq!(&rest[..], b"PING");
+}
+
+#[test]
+fn empty_lines_are_skipped() {
+    let (decoded, rest) = decode(b"\r\n   \r\n\r\nPING\r\n");
+    assert_eq!(decoded.expect("valid line"), Some(args(&["PING"])));
+    assert!(rest.is_empty());
+
+    let (decoded, rest) = decode(b"\r\n \r\n");
+    assert_eq!(decoded.expect("blank lines"), None);
+    assert!(rest.is_empty());
+
+    let (decoded, rest) = decode(b"*0\r\nPING\r\n");
+    assert_eq!(decoded.expect("empty array"), Some(args(&["PING"])));
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn oversize_frames_are_rejected() {
+    let bulk = format!("*1\r\n${}\r\n", MAX_BULK_LEN + 1);
+    assert_eq!(protocol_error(bulk.as_bytes()), "invalid bulk length");
+    let multibulk = format!("*{}\r\n", MAX_ARGS + 1);
+    assert_eq!(
+        protocol_error(multibulk.as_bytes()),
+        "invalid multibulk length"
+    );
+
+    let line = vec![b'a'; MAX_INLINE_LEN + 1];
+    assert_eq!(protocol_error(&line), "too big request line");
+    let mut header = b"*1\r\n$".to_vec();
+    header.extend(vec![b'1'; MAX_INLINE_LEN + 1]);
+    assert_eq!(protocol_error(&header), "too big request line");
+
+    let bulk = format!("*1\r\n${MAX_BULK_LEN}\r\n");
+    let (decoded, _) = decode(bulk.as_bytes());
+    assert_eq!(decoded.expect("largest bulk is allowed"), None);
+}
+
+#[test]
+fn malformed_frames_are_rejected() {
+    assert_eq!(
+        protocol_error(b"*1\r\n$3\r\nGETxx"),
+        "expected CRLF after bulk data",
+    );
+    assert_eq!(
+        protocol_error(b"*1\r\n$3\r\nGET\n\r"),
+        "expected CRLF after bulk data"
+    );
+    assert_eq!(protocol_error(b"*1\r\n:3\r\n"), "expected '$'");
+    assert_eq!(protocol_error(b"*x\r\n"), "invalid multibulk length");
+    assert_eq!(protocol_error(b"*-1\r\n"), "invalid multibulk length");
+    assert_eq!(protocol_error(b"*1\r\n$-1\r\n"), "invalid bulk length");
+}
+
+#[test]
+fn replies_are_encoded() {
+    assert_eq!(&encode(Reply::OK)[..], b"+OK\r\n");
+    assert_eq!(
+        &encode(Reply::error("ERR bad\r\nline"))[..],
+        b"-ERR bad  line\r\n"
+    );
+    assert_eq!(&encode(Reply::Integer(-3))[..], b":-3\r\n");
+    assert_eq!(&encode(Reply::Bulk(None))[..], b"$-1\r\n");
+    assert_eq!(
+        &encode(Reply::Array(vec![
+            Reply::Bulk(Some(Bytes::from_static(b"a\r\nb"))),
+            Reply::Bulk(None),
+            Reply::Array(Vec::new()),
+        ]))[..],
+        b"*3\r\n$4\r\na\r\nb\r\n$-1\r\n*0\r\n",
+    );
+}
@@ -0,0 +1,441 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use serde_json::json;
+use spalhad_actor::{ActorOptions, TrivialLoopActor};
+use spalhad_server::{
+    actor::{
+        bouncer::{self, Bouncer},
+        coordinator::Coordinator,
+        storage::{
+            self,
+            BreakerConfig,
+            BreakerState,
+            CircuitBreaker,
+            GetCall,
+            RpcStorage,
+            StorageCall,
+        },
+    },
+    rpc::{self, Connection, RemoteError, Saturated, TimedOut},
+};
+use spalhad_spec::{
+    Error,
+    ErrorCode,
+    kv::{Key, Value},
+    rpc::{
+        MAX_FRAME_LEN,
+        MAX_IN_FLIGHT,
+        RpcCall,
+        RpcReply,
+        RpcRequest,
+        RpcResponse,
+        decode_batch,
+        encode_batch,
+    },
+};
+use spalhad_task::TaskManager;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, oneshot},
+    time,
+};
+use tokio_util::{
+    codec::{Framed, LengthDelimitedCodec},
+    sync::CancellationToken,
+};
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+type Replies = Vec<oneshot::Receiver<Result<RpcReply>>>;
+
+fn codec() -> LengthDelimitedCodec {
+    LengthDelimitedCodec::builder().max_frame_length(MAX_FRAME_LEN).new_codec()
+}
+
+fn get(key: u8) -> RpcCall {
+    RpcCall::Get { key: Key::from_bytes([key; 32]) }
+}
+
+fn send(
+    connection: &Connection,
+    call: RpcCall,
+) -> oneshot::Receiver<Result<RpcReply>> {
+    let (sender, receiver) = oneshot::channel();
+    connection.send(call, HashMap::new(), move |reply| {
+        let _ = sender.send(reply);
+    });
+    receiver
+}
+
+async fn fake_peer() -> (String, TcpListener) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let address = listener.local_addr().expect("local address").to_string();
+    (address, listener)
+}
+
+async fn accept(
+    listener: &TcpListener,
+) -> Framed<TcpStream, LengthDelimitedCodec> {
+    let (stream, _) = listener.accept().await.expect("accept");
+    Framed::new(stream, codec())
+}
+
+async fn read_requests(
+    framed: &mut Framed<TcpStream, LengthDelimitedCodec>,
+    count: usize,
+) -> Vec<RpcRequest> {
+    let mut requests = Vec::new();
+    while requests.len() < count {
+        let frame = framed.next().await.expect("frame").expect("valid frame");
+        requests.extend(decode_batch::<RpcRequest>(&frame).expect("batch"));
+    }
+    requests
+}
+
+#[tokio::test]
+async fn responses_are_matched_by_id() {
+    let (address, listener) = fake_peer().await;
+    let connection =
+        Connection::connect(&address, TIMEOUT).await.expect("connect");
+    let mut framed = accept(&listener).await;
+
+    let replies: Replies =
+        (0 .. 4).map(|key| send(&connection, get(key))).collect();
+    let requests = read_requests(&mut framed, 4).await;
+    let responses: Vec<_> = requests
+        .iter()
+        .rev()
+        .map(|request| {
+            let RpcCall::Get { key } = &request.call else {
+                panic!("unexpected call {:?}", request.call)
+            };
+            let value = Value::Json(json!(key.to_string()));
+            RpcResponse {
+                id: request.id,
+                reply: RpcReply::Get { value: Some(value) },
+            }
+        })
+        .collect();
+    for response in responses {
+        let frame = encode_batch(&[response]).expect("encode");
+        framed.send(Bytes::from(frame)).await.expect("send");
+    }
+
+    for (key, reply) in replies.into_iter().enumerate() {
+        let reply = reply.await.expect("callback").expect("reply");
+        let expected = Key::from_bytes([key as u8; 32]).to_string();
+        assert_eq!(
+            reply,
+            RpcReply::Get { value: Some(Value::Json(json!(expected))) }
+        );
+    }
+}
+
+#[tokio::test]
+async fn unanswered_requests_time_out_and_late_replies_are_dropped() {
+    let (address, listener) = fake_peer().await;
+    let connection =
+        Connection::connect(&address, TIMEOUT).await.expect("connect");
+    let mut framed = accept(&listener).await;
+
+    let reply = send(&connection, get(1));
+    let request = read_requests(&mut framed, 1).await.remove(0);
+    let error = reply.await.expect("callback").expect_err("no answer");
+    assert!(error.is::<TimedOut>(), "{error:?}");
+
+    let late = RpcResponse {
+        id: request.id,
+        reply: RpcReply::Delete { existed: true },
+    };
+    framed
+        .send(Bytes::from(encode_batch(&[late]).expect("encode")))
+        .await
+        .expect("send");
+    let reply = send(&connection, get(2));
+    let request = read_requests(&mut framed, 1).await.remove(0);
+    let response =
+        RpcResponse { id: request.id, reply: RpcReply::Get { value: None } };
+    framed
+        .send(Bytes::from(encode_batch(&[response]).expect("encode")))
+        .await
+        .expect("send");
+    assert_eq!(
+        reply.await.expect("callback").expect("reply"),
+        RpcReply::Get { value: None }
+    );
+    assert!(!connection.is_closed());
+}
+
+#[tokio::test]
+async fn oversize_frames_close_the_connection() {
+    let (address, listener) = fake_peer().await;
+    let connection =
+        Connection::connect(&address, TIMEOUT).await.expect("connect");
+    let (mut stream, _) = listener.accept().await.expect("accept");
+
+    let reply = send(&connection, get(1));
+    let mut header = [0; 4];
+    stream.read_exact(&mut header).await.expect("request header");
+    let oversize = u32::try_from(MAX_FRAME_LEN + 1).expect("frame length fits");
+    stream.write_all(&oversize.to_be_bytes()).await.expect("header");
+
+    let error = reply.await.expect("callback").expect_err("connection failed");
+    assert_eq!(error.to_string(), "rpc connection closed");
+    time::timeout(TIMEOUT, async {
+        while !connection.is_closed() {
+            time::sleep(Duration::from_millis(1)).await;
+        }
+    })
+    .await
+    .expect("connection should close");
+}
+
+#[tokio::test]
+async fn losing_the_peer_fails_pending_and_later_requests() {
+    let (address, listener) = fake_peer().await;
+    let connection =
+        Connection::connect(&address, TIMEOUT).await.expect("connect");
+    let mut framed = accept(&listener).await;
+
+    let replies: Replies =
+        (0 .. 3).map(|key| send(&connection, get(key))).collect();
+    read_requests(&mut framed, 3).await;
+    drop(framed);
+
+    for reply in replies {
+        let error = reply.await.expect("callback").expect_err("peer is gone");
+        assert_eq!(error.to_string(), "rpc connection closed");
+    }
+    assert!(connection.is_closed());
+    let error =
+        send(&connection, get(4)).await.expect("callback").expect_err("closed");
+    assert_eq!(error.to_string(), "rpc connection is closed");
+}
+
+#[tokio::test]
+async fn saturated_connections_fail_fast() {
+    let (address, listener) = fake_peer().await;
+    let connection =
+        Connection::connect(&address, TIMEOUT).await.expect("connect");
+    let _framed = accept(&listener).await;
+
+    let mut replies: Replies =
+        (0 .. MAX_IN_FLIGHT).map(|_| send(&connection, get(0))).collect();
+    let error = send(&connection, get(0))
+        .await
+        .expect("callback")
+        .expect_err("queue is full");
+    assert!(error.is::<Saturated>(), "{error:?}");
+
+    let error = replies.remove(0).await.expect("callback").expect_err("late");
+    assert!(error.is::<TimedOut>(), "{error:?}");
+}
+
+#[derive(Debug, Clone, Default)]
+struct Held {
+    calls: Arc<Mutex<Vec<GetCall>>>,
+}
+
+impl Held {
+    fn len(&self) -> usize {
+        self.calls.lock().expect("poisoned lock").len()
+    }
+
+    fn release(&self) {
+        for call in self.calls.lock().expect("poisoned lock").drain(..) {
+            call.back.reply_ok(None);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HoldingStorage(Held);
+
+impl TrivialLoopActor for HoldingStorage {
+    type Call = StorageCall;
+
+    async fn on_call(&mut self, call: Self::Call) -> Result<()> {
+        match call {
+            StorageCall::Get(call) => {
+                self.0.calls.lock().expect("poisoned lock").push(call)
+            },
+            _ => panic!("unexpected storage call"),
+        }
+        Ok(())
+    }
+}
+
+async fn rpc_server(held: &Held, task_manager: &TaskManager) -> String {
+    let options =
+        ActorOptions::new(task_manager).with_channel_size(MAX_IN_FLIGHT * 2);
+    let storage = options.spawn(HoldingStorage(held.clone()));
+    let coordinator =
+        options.spawn(Coordinator::new(1, 1, 1, 1, vec![storage.clone()]));
+    let run_id = spalhad_spec::cluster::RunId::generate();
+    let bouncer = options.spawn(Bouncer::open(run_id, storage, coordinator));
+    bouncer.send(bouncer::Activate { run_id }).await.expect("activate");
+    let (address, listener) = fake_peer().await;
+    tokio::spawn(rpc::serve_listener(
+        listener,
+        bouncer,
+        CancellationToken::new(),
+    ));
+    address
+}
+
+#[tokio::test]
+async fn server_stops_reading_at_the_in_flight_limit() {
+    let task_manager = TaskManager::new();
+    let held = Held::default();
+    let address = rpc_server(&held, &task_manager).await;
+    let stream = TcpStream::connect(&address).await.expect("connect");
+    let mut framed = Framed::new(stream, codec());
+
+    let total = MAX_IN_FLIGHT + 10;
+    for id in 0 .. total as u64 {
+        let request = RpcRequest { id, trace: HashMap::new(), call: get(0) };
+        let frame = encode_batch(&[request]).expect("encode");
+        framed.feed(Bytes::from(frame)).await.expect("feed");
+    }
+    framed.flush().await.expect("flush");
+
+    time::timeout(Duration::from_secs(5), async {
+        while held.len() < MAX_IN_FLIGHT {
+            time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("server should take requests up to the limit");
+    time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(held.len(), MAX_IN_FLIGHT);
+
+    let mut answered = 0;
+    while answered < total {
+        held.release();
+        let frame = time::timeout(Duration::from_secs(5), framed.next())
+            .await
+            .expect("server should answer")
+            .expect("frame")
+            .expect("valid frame");
+        answered += decode_batch::<RpcResponse>(&frame).expect("batch").len();
+    }
+    assert_eq!(answered, total);
+}
+
+#[tokio::test]
+async fn server_drops_connections_sending_oversize_frames() {
+    let task_manager = TaskManager::new();
+    let address = rpc_server(&Held::default(), &task_manager).await;
+    let mut stream = TcpStream::connect(&address).await.expect("connect");
+    let oversize = u32::try_from(MAX_FRAME_LEN + 1).expect("frame length fits");
+    stream.write_all(&oversize.to_be_bytes()).await.expect("header");
+    let mut buffer = Vec::new();
+    let read = time::timeout(TIMEOUT, stream.read_to_end(&mut buffer))
+        .await
+        .expect("server should hang up");
+    assert_eq!(read.expect("clean close"), 0);
+}
+
+#[tokio::test]
+async fn remote_errors_keep_their_code() {
+    let (address, listener) = fake_peer().await;
+    let task_manager = TaskManager::new();
+    let storage = ActorOptions::new(&task_manager)
+        .spawn(RpcStorage::open(address, TIMEOUT));
+    let peer = tokio::spawn(async move {
+        let mut framed = accept(&listener).await;
+        for code in [ErrorCode::NotActive, ErrorCode::Timeout] {
+            let request = read_requests(&mut framed, 1).await.remove(0);
+            let error = Error::new(code, vec!["remote failure".to_owned()]);
+            let response =
+                RpcResponse { id: request.id, reply: RpcReply::Error(error) };
+            let frame = encode_batch(&[response]).expect("encode");
+            framed.send(Bytes::from(frame)).await.expect("send");
+        }
+        framed
+    });
+
+    let key = Key::from_bytes([0; 32]);
+    let error = storage
+        .send(storage::Get { key: key.clone() })
+        .await
+        .expect_err("peer is not active");
+    let remote = error.downcast_ref::<RemoteError>().expect("remote error");
+    assert_eq!(remote.0.code, ErrorCode::NotActive);
+    assert_eq!(error.to_string(), "remote failure");
+    assert!(!storage::is_timeout(&error));
+
+    let error =
+        storage.send(storage::Get { key }).await.expect_err("peer timed out");
+    assert!(storage::is_timeout(&error), "{error:?}");
+    drop(peer.await.expect("fake peer"));
+}
+
+#[tokio::test]
+async fn saturation_does_not_trip_the_breaker() {
+    let (address, listener) = fake_peer().await;
+    let _peer = tokio::spawn(async move {
+        let framed = accept(&listener).await;
+        time::sleep(Duration::from_secs(60)).await;
+        drop(framed);
+    });
+    let task_manager = TaskManager::new();
+    let breaker =
+        CircuitBreaker::new(BreakerConfig::new(1, Duration::from_secs(60)));
+    let rpc_storage = RpcStorage::open(address, Duration::from_secs(30))
+        .with_circuit_breaker(breaker.clone());
+    let storage = ActorOptions::new(&task_manager)
+        .with_channel_size(MAX_IN_FLIGHT * 4)
+        .spawn(rpc_storage);
+
+    let (errors, mut saturated) = mpsc::unbounded_channel();
+    for _ in 0 .. MAX_IN_FLIGHT * 3 {
+        let storage = storage.clone();
+        let errors = errors.clone();
+        tokio::spawn(async move {
+            let key = Key::from_bytes([0; 32]);
+            if let Err(error) = storage.send(storage::Get { key }).await {
+                let _ = errors.send(error);
+            }
+        });
+    }
+    let error = time::timeout(Duration::from_secs(5), saturated.recv())
+        .await
+        .expect("some requests should be rejected")
+        .expect("error");
+    assert!(error.is::<Saturated>(), "{error:?}");
+    assert_eq!(breaker.snapshot().state, BreakerState::Closed);
+    assert_eq!(breaker.snapshot().consecutive_failures, 0);
+}
+
+#[tokio::test]
+async fn failed_connects_fail_every_waiting_call() {
+    let (address, listener) = fake_peer().await;
+    drop(listener);
+    let task_manager = TaskManager::new();
+    let storage = ActorOptions::new(&task_manager)
+        .spawn(RpcStorage::open(address, TIMEOUT));
+
+    let calls = (0 .. 5).map(|key| {
+        let storage = storage.clone();
+        tokio::spawn(async move {
+            storage.send(storage::Get { key: Key::from_bytes([key; 32]) }).await
+        })
+    });
+    for call in futures::future::join_all(calls).await {
+        let error = call.expect("call task").expect_err("peer is unreachable");
+        assert!(error.is::<rpc::ConnectFailed>(), "{error:?}");
+        assert!(
+            error.chain().any(|cause| cause.is::<std::io::Error>()),
+            "{error:?}",
+        );
+    }
+}
@@ -0,0 +1,876 @@
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use spalhad_actor::ActorOptions;
+use spalhad_server::{
+    actor::{
+        bouncer,
+        coordinator,
+        storage::{self, BreakerConfig, BreakerState},
+    },
+    bucket,
+    sim::{Faults, SimCluster, SimConfig},
+};
+use spalhad_spec::{
+    QuorumDetails,
+    bucket::{Bucket, BucketConfig},
+    cluster::RunId,
+    kv::{Key, Namespace, Value},
+    replica::{ReplicaOutcome, ReplicaResult},
+};
+use spalhad_task::TaskManager;
+use tokio::{
+    runtime,
+    time::{self, Instant},
+};
+
+const NODES: usize = 5;
+const KEYS: u8 = 4;
+const STEPS: usize = 40;
+
+fn simulate<F>(future: F) -> F::Output
+where
+    F: Future,
+{
+    runtime::Builder::new_current_thread()
+        .enable_time()
+        .start_paused(true)
+        .build()
+        .expect("failed to build simulation runtime")
+        .block_on(future)
+}
+
+fn seeds(default: u64) -> u64 {
+    std::env::var("SPALHAD_SIM_SEEDS")
+        .ok()
+        .and_then(|seeds| seeds.parse().ok())
+        .unwrap_or(default)
+}
+
+fn config(faults: Faults) -> SimConfig {
+    SimConfig {
+        nodes: NODES,
+        replication: 3,
+        min_correct_reads: 2,
+        min_correct_writes: 2,
+        concurrency_level: 4,
+        timeout: Duration::from_millis(200),
+        faults,
+        hedge_percentile: None,
+        breaker: None,
+        buckets: Vec::new(),
+    }
+}
+
+fn hedged(faults: Faults) -> SimConfig {
+    SimConfig { hedge_percentile: Some(0.9), ..config(faults) }
+}
+
+fn lossy() -> Faults {
+    Faults {
+        drop_probability: 0.1,
+        min_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(150),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct KeyHistory {
+    acceptable: Vec<Value>,
+    maybe_absent: bool,
+}
+
+impl Default for KeyHistory {
+    fn default() -> Self {
+        Self { acceptable: Vec::new(), maybe_absent: true }
+    }
+}
+
+async fn run_workload(
+    seed: u64,
+    config: SimConfig,
+    inject_failures: bool,
+) -> Vec<String> {
+    let task_manager = TaskManager::new();
+    let options = ActorOptions::new(&task_manager);
+    let cluster = SimCluster::spawn(&options, seed, &config);
+    cluster.activate_all().await.expect("activation should not fail");
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed.wrapping_add(1));
+    let keys: Vec<_> = (0 .. KEYS).map(|i| Key::from_bytes([i; 32])).collect();
+    let mut histories = vec![KeyHistory::default(); keys.len()];
+    let mut trace = Vec::new();
+
+    for step in 0 .. STEPS {
+        let action = rng.random_range(0 .. 10);
+        if inject_failures && action < 4 {
+            let node = rng.random_range(0 .. NODES);
+            match action {
+                0 => {
+                    let groups: Vec<_> = (0 .. NODES)
+                        .map(|_| rng.random_range(0 .. 2))
+                        .collect();
+                    trace.push(format!("{step}: partition {groups:?}"));
+                    cluster.network().partition(groups);
+                },
+                1 => {
+                    trace.push(format!("{step}: heal"));
+                    cluster.network().heal();
+                },
+                2 => {
+                    trace.push(format!("{step}: crash {node}"));
+                    cluster.network().crash(node);
+                },
+                _ => {
+                    trace.push(format!("{step}: recover {node}"));
+                    cluster.network().recover(node);
+                },
+            }
+            continue;
+        }
+
+        let up: Vec<_> =
+            (0 .. NODES).filter(|i| !cluster.network().is_down(*i)).collect();
+        if up.is_empty() {
+            continue;
+        }
+        let node = up[rng.random_range(0 .. up.len())];
+        let key_index = rng.random_range(0 .. keys.len());
+        let key = keys[key_index].clone();
+        let history = &mut histories[key_index];
+        let bouncer = cluster.node(node).bouncer();
+
+        if rng.random_bool(0.5) {
+            let value = Value::Json(serde_json::json!(step));
+            let result = bouncer
+                .send(coordinator::Put {
+                    key,
+                    value: value.clone(),
+                    namespace: None,
+                    origin: None,
+                })
+                .await
+                .map(|output| output.value);
+            trace.push(format!(
+                "{step}: put node={node} key={key_index} value={value:?} -> \
+                 {:?}",
+                result.as_ref().map_err(|_| ()),
+            ));
+            match result {
+                Ok(_) => {
+                    history.acceptable = vec![value];
+                    history.maybe_absent = false;
+                },
+                Err(_) => history.acceptable.push(value),
+            }
+        } else {
+            let result = bouncer
+                .send(coordinator::Get { key, namespace: None })
+                .await
+                .map(|output| output.value);
+            trace.push(format!(
+                "{step}: get node={node} key={key_index} -> {:?}",
+                result.as_ref().map_err(|_| ()),
+            ));
+            match result {
+                Ok(Some(value)) => assert!(
+                    history.acceptable.contains(&value),
+                    "seed {seed}: stale read of {value:?}, expected one of \
+                     {:?}\n{}",
+                    history.acceptable,
+                    trace.join("\n"),
+                ),
+                Ok(None) => assert!(
+                    history.maybe_absent,
+                    "seed {seed}: lost write, expected one of {:?}\n{}",
+                    history.acceptable,
+                    trace.join("\n"),
+                ),
+                Err(_) => (),
+            }
+        }
+    }
+
+    trace
+}
+
+#[test]
+fn bouncer_guards_activation_and_storage() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let cluster = SimCluster::spawn(&options, 0, &config(Faults::none()));
+        let node = cluster.node(0);
+        let key = Key::from_bytes([0; 32]);
+
+        let error = node
+            .bouncer()
+            .send(storage::Get { key: key.clone() })
+            .await
+            .expect_err("inactive node should reject storage calls");
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(bouncer::Error::NotActive),
+        ));
+
+        let error = node
+            .bouncer()
+            .send(bouncer::Activate { run_id: RunId::generate() })
+            .await
+            .expect_err("activation with a foreign run id should fail");
+        assert!(matches!(error.downcast_ref(), Some(bouncer::Error::BadRunId)));
+
+        node.bouncer()
+            .send(bouncer::Activate { run_id: node.run_id() })
+            .await
+            .expect("activation with own run id should succeed");
+        assert!(node.bouncer().send(bouncer::IsActive).await.unwrap());
+
+        let error = node
+            .bouncer()
+            .send(bouncer::Activate { run_id: node.run_id() })
+            .await
+            .expect_err("second activation should fail");
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(bouncer::Error::AlreadyActive),
+        ));
+
+        let value = node.bouncer().send(storage::Get { key }).await.unwrap();
+        assert_eq!(value, None);
+    });
+}
+
+#[test]
+fn fault_free_cluster_always_reaches_quorum() {
+    for seed in 0 .. seeds(100) {
+        let trace = simulate(run_workload(seed, config(Faults::none()), false));
+        let failed = trace.iter().find(|line| line.ends_with("Err(())"));
+        assert!(failed.is_none(), "seed {seed}: {failed:?}");
+    }
+}
+
+#[test]
+fn quorum_reads_never_observe_stale_values_under_faults() {
+    for seed in 0 .. seeds(1000) {
+        simulate(run_workload(seed, config(lossy()), true));
+    }
+}
+
+#[test]
+fn hedged_reads_never_observe_stale_values_under_faults() {
+    for seed in 0 .. seeds(300) {
+        simulate(run_workload(seed, hedged(lossy()), true));
+    }
+}
+
+#[test]
+fn hedging_avoids_and_masks_slow_replicas() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let faults = Faults {
+            drop_probability: 0.0,
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        };
+        let config = SimConfig {
+            nodes: 3,
+            timeout: Duration::from_secs(5),
+            ..hedged(faults)
+        };
+        let cluster = SimCluster::spawn(&options, 0, &config);
+        cluster.activate_all().await.expect("activation should not fail");
+        let network = cluster.network();
+        let bouncer = cluster.node(0).bouncer();
+        let key = Key::from_bytes([0; 32]);
+        let value = Value::Json(serde_json::json!("hedged"));
+
+        network.slow_down(2, Duration::from_millis(20));
+        bouncer
+            .send(coordinator::Put {
+                key: key.clone(),
+                value: value.clone(),
+                namespace: None,
+                origin: None,
+            })
+            .await
+            .expect("put should reach quorum");
+
+        let delivered = network.delivered(2);
+        for _ in 0 .. 20 {
+            let read = bouncer
+                .send(coordinator::Get { key: key.clone(), namespace: None })
+                .await;
+            assert_eq!(read.unwrap().value, Some(value.clone()));
+        }
+        let hedged = network.delivered(2) - delivered;
+        assert!(hedged <= 5, "slow replica was asked {hedged} times out of 20");
+
+        network.slow_down(1, Duration::from_secs(1));
+        let started = Instant::now();
+        let read = bouncer
+            .send(coordinator::Get { key: key.clone(), namespace: None })
+            .await;
+        assert_eq!(read.unwrap().value, Some(value));
+        assert!(
+            started.elapsed() < Duration::from_millis(100),
+            "hedged read took {:?}",
+            started.elapsed(),
+        );
+    });
+}
+
+#[test]
+fn abandoned_replicas_do_not_look_fast() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let faults = Faults {
+            drop_probability: 0.0,
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        };
+        let config = SimConfig {
+            nodes: 3,
+            timeout: Duration::from_secs(5),
+            ..hedged(faults)
+        };
+        let cluster = SimCluster::spawn(&options, 0, &config);
+        cluster.activate_all().await.expect("activation should not fail");
+        let network = cluster.network();
+        let bouncer = cluster.node(0).bouncer();
+        let key = Key::from_bytes([0; 32]);
+        let value = Value::Json(serde_json::json!("hedged"));
+
+        network.slow_down(2, Duration::from_millis(20));
+        bouncer
+            .send(coordinator::Put {
+                key: key.clone(),
+                value: value.clone(),
+                namespace: None,
+                origin: None,
+            })
+            .await
+            .expect("put should reach quorum");
+
+        let delivered = network.delivered(2);
+        for _ in 0 .. 300 {
+            let read = bouncer
+                .send(coordinator::Get { key: key.clone(), namespace: None })
+                .await;
+            assert_eq!(read.unwrap().value, Some(value.clone()));
+        }
+        let asked = network.delivered(2) - delivered;
+        assert!(asked <= 80, "slow replica was asked {asked} times out of 300");
+    });
+}
+
+#[test]
+fn breaker_skips_crashed_peer_and_closes_after_probe() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let cooldown = Duration::from_secs(1);
+        let config = SimConfig {
+            nodes: 3,
+            concurrency_level: 3,
+            timeout: Duration::from_millis(100),
+            breaker: Some(BreakerConfig::new(3, cooldown)),
+            ..config(Faults::none())
+        };
+        let cluster = SimCluster::spawn(&options, 0, &config);
+        cluster.activate_all().await.expect("activation should not fail");
+        let network = cluster.network();
+        let bouncer = cluster.node(0).bouncer();
+        let breakers = cluster.node(0).breakers().expect("breakers enabled");
+        let key = Key::from_bytes([0; 32]);
+
+        let put = |step: u64| {
+            let value = Value::Json(serde_json::json!(step));
+            bouncer.send(coordinator::Put {
+                key: key.clone(),
+                value,
+                namespace: None,
+                origin: None,
+            })
+        };
+
+        network.crash(2);
+        for step in 0 .. 3 {
+            put(step).await.expect("put should reach quorum");
+            tokio::time::sleep(config.timeout * 2).await;
+        }
+        let breaker = breakers.get(2).expect("breaker for node 2");
+        assert!(matches!(breaker.snapshot().state, BreakerState::Open { .. }));
+
+        let attempted = network.attempted(2);
+        for step in 3 .. 10 {
+            put(step).await.expect("put should reach quorum");
+        }
+        assert_eq!(network.attempted(2), attempted);
+
+        network.recover(2);
+        tokio::time::sleep(cooldown).await;
+        put(10).await.expect("put should reach quorum");
+        tokio::time::sleep(config.timeout * 2).await;
+        assert!(network.attempted(2) > attempted);
+        assert_eq!(breaker.snapshot().state, BreakerState::Closed);
+        assert_eq!(breaker.snapshot().consecutive_failures, 0);
+    });
+}
+
+#[test]
+fn buckets_pick_replication_and_quorum_per_request() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let bucket = |name: &str, replication, quorum| Bucket {
+            name: name.parse().expect("valid namespace"),
+            revision: 1,
+            updated_by: 0,
+            config: BucketConfig {
+                replication,
+                min_correct_reads: quorum,
+                min_correct_writes: quorum,
+            },
+        };
+        let config = SimConfig {
+            buckets: vec![bucket("critical", 5, 5), bucket("scratch", 1, 1)],
+            ..config(Faults::none())
+        };
+        let cluster = SimCluster::spawn(&options, 0, &config);
+        cluster.activate_all().await.expect("activation should not fail");
+        let network = cluster.network();
+        let bouncer = cluster.node(0).bouncer();
+        let delivered_to_peers =
+            || (1 .. cluster.len()).map(|i| network.delivered(i)).sum();
+
+        let put = |namespace: Option<&str>, key_byte: u8| {
+            let namespace: Option<Namespace> =
+                namespace.map(|name| name.parse().expect("valid namespace"));
+            bouncer.send(coordinator::Put {
+                key: Key::from_bytes([key_byte; 32]),
+                value: Value::Json(serde_json::json!(key_byte)),
+                namespace,
+                origin: None,
+            })
+        };
+
+        let before: usize = delivered_to_peers();
+        put(Some("scratch"), 0).await.expect("put should reach quorum");
+        assert_eq!(delivered_to_peers(), before);
+
+        let before: usize = delivered_to_peers();
+        put(Some("critical"), 1).await.expect("put should reach quorum");
+        assert_eq!(delivered_to_peers(), before + 4);
+
+        let before: usize = delivered_to_peers();
+        put(Some("unknown"), 0).await.expect("put should reach quorum");
+        assert_eq!(delivered_to_peers(), before + 2);
+
+        network.crash(4);
+        let error = put(Some("critical"), 1)
+            .await
+            .expect_err("quorum needs every node");
+        let quorum = error
+            .downcast_ref::<coordinator::QuorumError>()
+            .expect("failure should carry quorum details");
+        let details =
+            QuorumDetails { asked: 5, answered: 4, agreeing: 4, required: 5 };
+        assert_eq!(quorum.details, details);
+        let results: Vec<_> = quorum
+            .replicas
+            .iter()
+            .map(|replica| (replica.node, replica.result))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                (2, ReplicaResult::Disagreed),
+                (3, ReplicaResult::Disagreed),
+                (4, ReplicaResult::TimedOut),
+                (0, ReplicaResult::Disagreed),
+                (1, ReplicaResult::Disagreed),
+            ]
+        );
+        put(None, 0).await.expect("default replicas are not crashed");
+    });
+}
+
+#[test]
+fn bucket_replication_is_locked_once_written() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let cluster = SimCluster::spawn(&options, 0, &config(Faults::none()));
+        cluster.activate_all().await.expect("activation should not fail");
+        let billing: Namespace = "billing".parse().expect("valid namespace");
+        let wider = BucketConfig {
+            replication: 5,
+            min_correct_reads: 3,
+            min_correct_writes: 3,
+        };
+
+        let coordinator = cluster.node(0).buckets();
+        coordinator
+            .configure(billing.clone(), wider)
+            .await
+            .expect("empty bucket");
+        cluster
+            .node(0)
+            .bouncer()
+            .send(coordinator::Put {
+                key: Key::from_namespaced_key(&billing, "foo"),
+                value: Value::Json(serde_json::json!(1)),
+                namespace: Some(billing.clone()),
+                origin: None,
+            })
+            .await
+            .expect("put should reach quorum");
+        assert_eq!(coordinator.written(), std::slice::from_ref(&billing));
+
+        let narrower = BucketConfig { replication: 3, ..wider };
+        let error = coordinator
+            .configure(billing.clone(), narrower)
+            .await
+            .expect_err("replicas of stored keys would be stranded");
+        assert!(error.is::<bucket::ReplicationLocked>());
+        let stricter = BucketConfig { min_correct_writes: 5, ..wider };
+        coordinator
+            .configure(billing.clone(), stricter)
+            .await
+            .expect("quorum changes keep the replicas");
+
+        let peer = cluster.node(1).buckets();
+        for bucket in coordinator.snapshot() {
+            peer.apply(bucket).await.expect("sync bucket");
+        }
+        peer.merge_written(coordinator.written()).await.expect("merge written");
+        assert!(peer.configure(billing, narrower).await.is_err());
+    });
+}
+
+#[test]
+fn failed_puts_do_not_lock_bucket_replication() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let config = SimConfig { nodes: 3, ..config(Faults::none()) };
+        let cluster = SimCluster::spawn(&options, 0, &config);
+        cluster.activate_all().await.expect("activation should not fail");
+        let billing: Namespace = "billing".parse().expect("valid namespace");
+
+        cluster.network().crash(1);
+        cluster.network().crash(2);
+        cluster
+            .node(0)
+            .bouncer()
+            .send(coordinator::Put {
+                key: Key::from_namespaced_key(&billing, "foo"),
+                value: Value::Json(serde_json::json!(1)),
+                namespace: Some(billing.clone()),
+                origin: None,
+            })
+            .await
+            .expect_err("put cannot reach quorum");
+        let buckets = cluster.node(0).buckets();
+        assert!(buckets.written().is_empty());
+        let narrower = BucketConfig {
+            replication: 1,
+            min_correct_reads: 1,
+            min_correct_writes: 1,
+        };
+        buckets
+            .configure(billing, narrower)
+            .await
+            .expect("nothing was written into the bucket");
+    });
+}
+
+#[test]
+fn delete_counts_acks_from_replicas_missing_the_key() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let config = SimConfig {
+            nodes: 3,
+            min_correct_writes: 3,
+            concurrency_level: 3,
+            ..config(Faults::none())
+        };
+        let cluster = SimCluster::spawn(&options, 0, &config);
+        cluster.activate_all().await.expect("activation should not fail");
+        let key = Key::from_bytes([0; 32]);
+        let value = Value::Json(serde_json::json!("partial"));
+
+        for node in 0 .. 2 {
+            cluster
+                .node(node)
+                .bouncer()
+                .send(storage::Put {
+                    key: key.clone(),
+                    value: value.clone(),
+                    origin: None,
+                })
+                .await
+                .expect("local put should succeed");
+        }
+
+        let delete = coordinator::Delete { key: key.clone(), namespace: None };
+        let deleted = cluster
+            .node(0)
+            .bouncer()
+            .send(delete.clone())
+            .await
+            .expect("every replica acknowledged the delete");
+        assert!(deleted.value);
+        assert_eq!(deleted.replicas.len(), 3);
+
+        let deleted = cluster
+            .node(0)
+            .bouncer()
+            .send(delete)
+            .await
+            .expect("deleting a missing key still reaches quorum");
+        assert!(!deleted.value);
+    });
+}
+
+#[test]
+fn put_counts_acks_from_replicas_that_had_the_key() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let config = SimConfig {
+            nodes: 3,
+            min_correct_writes: 3,
+            concurrency_level: 3,
+            ..config(Faults::none())
+        };
+        let cluster = SimCluster::spawn(&options, 0, &config);
+        cluster.activate_all().await.expect("activation should not fail");
+        let key = Key::from_bytes([0; 32]);
+        let value = Value::Json(serde_json::json!("partial"));
+
+        cluster
+            .node(1)
+            .bouncer()
+            .send(storage::Put {
+                key: key.clone(),
+                value: value.clone(),
+                origin: None,
+            })
+            .await
+            .expect("local put should succeed");
+
+        let put = coordinator::Put {
+            key: key.clone(),
+            value: value.clone(),
+            namespace: None,
+            origin: None,
+        };
+        let written = cluster
+            .node(0)
+            .bouncer()
+            .send(put)
+            .await
+            .expect("every replica acknowledged the put");
+        assert!(!written.value, "one replica already had the key");
+        assert!(
+            written
+                .replicas
+                .iter()
+                .all(|replica| replica.result == ReplicaResult::Agreed)
+        );
+
+        let put = coordinator::Put {
+            key: Key::from_bytes([1; 32]),
+            value,
+            namespace: None,
+            origin: None,
+        };
+        let written = cluster
+            .node(0)
+            .bouncer()
+            .send(put)
+            .await
+            .expect("every replica acknowledged the put");
+        assert!(written.value);
+    });
+}
+
+fn results(replicas: &[ReplicaOutcome]) -> Vec<(usize, ReplicaResult)> {
+    let mut results: Vec<_> =
+        replicas.iter().map(|replica| (replica.node, replica.result)).collect();
+    results.sort_by_key(|(node, _)| *node);
+    results
+}
+
+async fn read_with_replicas(
+    values: &[Option<&str>],
+    crashed: &[usize],
+) -> Result<coordinator::Replicated<Option<Value>>, coordinator::QuorumError> {
+    let task_manager = TaskManager::new();
+    let options = ActorOptions::new(&task_manager);
+    let config = SimConfig {
+        nodes: values.len(),
+        concurrency_level: values.len(),
+        ..config(Faults::none())
+    };
+    let cluster = SimCluster::spawn(&options, 0, &config);
+    cluster.activate_all().await.expect("activation should not fail");
+    let key = Key::from_bytes([0; 32]);
+    for (node, value) in values.iter().enumerate() {
+        let Some(value) = value else { continue };
+        cluster
+            .node(node)
+            .bouncer()
+            .send(storage::Put {
+                key: key.clone(),
+                value: Value::Json(serde_json::json!(value)),
+                origin: None,
+            })
+            .await
+            .expect("local put should succeed");
+    }
+    for &node in crashed {
+        cluster.network().crash(node);
+    }
+    cluster
+        .node(0)
+        .bouncer()
+        .send(coordinator::Get { key, namespace: None })
+        .await
+        .map_err(|error| {
+            error
+                .downcast::<coordinator::QuorumError>()
+                .expect("failure should carry quorum details")
+        })
+}
+
+#[test]
+fn only_replicas_of_the_quorum_value_agree() {
+    simulate(async {
+        let read = read_with_replicas(&[Some("a"), Some("a"), Some("b")], &[])
+            .await
+            .expect("two replicas agree");
+        let results = results(&read.replicas);
+        assert_eq!(
+            results[.. 2],
+            [(0, ReplicaResult::Agreed), (1, ReplicaResult::Agreed),]
+        );
+        assert_ne!(results[2], (2, ReplicaResult::Agreed));
+    });
+}
+
+#[test]
+fn failed_quorum_has_no_agreeing_replicas() {
+    simulate(async {
+        let error = read_with_replicas(&[Some("a"), Some("b"), Some("c")], &[])
+            .await
+            .expect_err("no two replicas agree");
+        let details =
+            QuorumDetails { asked: 3, answered: 3, agreeing: 1, required: 2 };
+        assert_eq!(error.details, details);
+        assert_eq!(
+            results(&error.replicas),
+            [
+                (0, ReplicaResult::Disagreed),
+                (1, ReplicaResult::Disagreed),
+                (2, ReplicaResult::Disagreed),
+            ]
+        );
+    });
+}
+
+#[test]
+fn tied_replies_have_no_agreeing_replicas() {
+    simulate(async {
+        let error = read_with_replicas(&[Some("a"), None, Some("b")], &[1])
+            .await
+            .expect_err("replies are tied");
+        let details =
+            QuorumDetails { asked: 3, answered: 2, agreeing: 1, required: 2 };
+        assert_eq!(error.details, details);
+        assert_eq!(
+            results(&error.replicas),
+            [
+                (0, ReplicaResult::Disagreed),
+                (1, ReplicaResult::TimedOut),
+                (2, ReplicaResult::Disagreed),
+            ]
+        );
+    });
+}
+
+#[test]
+fn stopped_node_drains_in_flight_calls_and_leaves() {
+    simulate(async {
+        let task_manager = TaskManager::new();
+        let options = ActorOptions::new(&task_manager);
+        let mut cluster =
+            SimCluster::spawn(&options, 0, &config(Faults::none()));
+        cluster.activate_all().await.expect("activation should not fail");
+        for node in 1 .. cluster.len() {
+            cluster.network().slow_down(node, Duration::from_millis(50));
+        }
+
+        let put = |bouncer: &bouncer::BouncerHandle, key_byte: u8| {
+            let bouncer = bouncer.clone();
+            tokio::spawn(async move {
+                bouncer
+                    .send(coordinator::Put {
+                        key: Key::from_bytes([key_byte; 32]),
+                        value: Value::Json(serde_json::json!(key_byte)),
+                        namespace: None,
+                        origin: None,
+                    })
+                    .await
+            })
+        };
+
+        let in_flight: Vec<_> = (0 .. KEYS)
+            .map(|key| put(cluster.node(0).bouncer(), key))
+            .collect();
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(in_flight.iter().all(|call| !call.is_finished()));
+
+        time::timeout(Duration::from_secs(10), cluster.shutdown(0))
+            .await
+            .expect("shutdown should not hang")
+            .expect("shutdown should be clean");
+        for call in in_flight {
+            call.await
+                .expect("put task should not panic")
+                .expect("in-flight put should be drained");
+        }
+
+        let mut leaving = Vec::new();
+        for key in 0 .. 16 {
+            let output = put(cluster.node(1).bouncer(), key)
+                .await
+                .expect("put task should not panic")
+                .expect("put should skip the leaving node");
+            leaving.extend(
+                output
+                    .replicas
+                    .into_iter()
+                    .filter(|replica| replica.node == 0)
+                    .map(|replica| replica.result),
+            );
+        }
+        assert!(!leaving.is_empty());
+        assert!(leaving.iter().all(|result| *result == ReplicaResult::Skipped));
+    });
+}
+
+#[test]
+fn same_seed_replays_same_schedule() {
+    for seed in 0 .. 20 {
+        let first = simulate(run_workload(seed, config(lossy()), true));
+        let second = simulate(run_workload(seed, config(lossy()), true));
+        assert_eq!(first, second, "seed {seed} diverged");
+    }
+}
@@ -0,0 +1,61 @@
+use spalhad_server::topology::Topology;
+use spalhad_spec::cluster::{ClusterConfig, PeerStatus, PeerTransport};
+
+fn cluster_config(addresses: &[&str]) -> ClusterConfig {
+    ClusterConfig {
+        replication: 2,
+        min_correct_reads: 1,
+        min_correct_writes: 1,
+        addresses: addresses
+            .iter()
+            .map(|&address| address.to_owned())
+            .collect(),
+        transport: PeerTransport::default(),
+        rpc_addresses: Vec::new(),
+    }
+}
+
+#[test]
+fn nodes_with_the_same_view_agree_on_the_version() {
+    let config = cluster_config(&["http://a", "http://b", "http://c"]);
+    let first = Topology::new(0, &config);
+    let second = Topology::new(2, &config);
+    assert_eq!(first.version(), second.version());
+    assert_eq!(first.snapshot().version, first.version());
+
+    first.set_status(1, PeerStatus::Leaving).expect("known node");
+    assert_ne!(first.version(), second.version());
+    second.set_status(1, PeerStatus::Leaving).expect("known node");
+    assert_eq!(first.version(), second.version());
+}
+
+#[test]
+fn version_follows_the_content_not_the_history() {
+    let config = cluster_config(&["http://a", "http://b"]);
+    let topology = Topology::new(0, &config);
+    let initial = topology.version();
+
+    topology.set_address(1, "http://b2").expect("known node");
+    let replaced = topology.version();
+    assert_ne!(replaced, initial);
+    assert_eq!(
+        replaced,
+        Topology::new(0, &cluster_config(&["http://a", "http://b2"])).version(),
+    );
+
+    topology.set_address(1, "http://b").expect("known node");
+    assert_eq!(topology.version(), initial);
+    topology.set_status(0, PeerStatus::Joined).expect("known node");
+    assert_eq!(topology.version(), initial);
+    assert!(topology.set_status(5, PeerStatus::Leaving).is_err());
+}
+
+#[test]
+fn replication_changes_the_version() {
+    let config = cluster_config(&["http://a", "http://b"]);
+    let other = ClusterConfig { replication: 1, ..config.clone() };
+    assert_ne!(
+        Topology::new(0, &config).version(),
+        Topology::new(0, &other).version(),
+    );
+}
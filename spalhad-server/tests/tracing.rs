@@ -0,0 +1,135 @@
+use std::{sync::Once, time::Duration};
+
+use anyhow::Result;
+use axum::{Json, Router, routing::get};
+use opentelemetry::{
+    global,
+    trace::{TraceContextExt, TraceId, TracerProvider},
+};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    trace::SdkTracerProvider,
+};
+use serde_json::json;
+use spalhad_actor::{ActorOptions, TrivialLoopActor};
+use spalhad_client::Client;
+use spalhad_server::{
+    actor::{
+        bouncer::{self, Bouncer},
+        coordinator::Coordinator,
+        storage::{self, RpcStorage, StorageCall},
+    },
+    http,
+    rpc,
+};
+use spalhad_spec::{
+    cluster::RunId,
+    kv::{Key, Value},
+};
+use spalhad_task::TaskManager;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+
+static TRACING: Once = Once::new();
+
+fn init_tracing() {
+    TRACING.call_once(|| {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let tracer = SdkTracerProvider::builder().build().tracer("test");
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer));
+        tracing::subscriber::set_global_default(subscriber)
+            .expect("no other subscriber is installed");
+    });
+}
+
+fn trace_id(span: &Span) -> TraceId {
+    span.context().span().span_context().trace_id()
+}
+
+async fn listen() -> (String, TcpListener) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let address = listener.local_addr().expect("local address").to_string();
+    (address, listener)
+}
+
+#[derive(Debug)]
+struct TracedStorage;
+
+impl TrivialLoopActor for TracedStorage {
+    type Call = StorageCall;
+
+    async fn on_call(&mut self, call: Self::Call) -> Result<()> {
+        match call {
+            StorageCall::Get(call) => {
+                let trace_id = trace_id(&Span::current()).to_string();
+                call.back.reply_ok(Some(Value::Json(json!(trace_id))));
+            },
+            _ => panic!("unexpected storage call"),
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn http_requests_continue_the_client_trace() {
+    init_tracing();
+    let router = Router::new().route(
+        "/spalhad/v1/kv/{key}",
+        get(async || {
+            let trace_id = trace_id(&Span::current()).to_string();
+            Json(json!({ "value": trace_id }))
+        }),
+    );
+    let (address, listener) = listen().await;
+    tokio::spawn(http::serve_listener(
+        listener,
+        router,
+        CancellationToken::new(),
+    ));
+
+    let client = Client::new(format!("http://{address}"));
+    let span = tracing::info_span!("client-request");
+    let expected = trace_id(&span);
+    assert_ne!(expected, TraceId::INVALID);
+    let served: Option<String> = client
+        .get_raw(Key::from_bytes([0; 32]))
+        .instrument(span)
+        .await
+        .expect("get");
+    assert_eq!(served, Some(expected.to_string()));
+}
+
+#[tokio::test]
+async fn rpc_requests_continue_the_caller_trace() {
+    init_tracing();
+    let task_manager = TaskManager::new();
+    let options = ActorOptions::new(&task_manager);
+    let storage = options.spawn(TracedStorage);
+    let coordinator =
+        options.spawn(Coordinator::new(1, 1, 1, 1, vec![storage.clone()]));
+    let run_id = RunId::generate();
+    let bouncer = options.spawn(Bouncer::open(run_id, storage, coordinator));
+    bouncer.send(bouncer::Activate { run_id }).await.expect("activate");
+    let (address, listener) = listen().await;
+    tokio::spawn(rpc::serve_listener(
+        listener,
+        bouncer,
+        CancellationToken::new(),
+    ));
+
+    let peer = options.spawn(RpcStorage::open(address, Duration::from_secs(5)));
+    let span = tracing::info_span!("coordinator-call");
+    let expected = trace_id(&span);
+    assert_ne!(expected, TraceId::INVALID);
+    let served = async {
+        peer.send(storage::Get { key: Key::from_bytes([0; 32]) }).await
+    }
+    .instrument(span)
+    .await
+    .expect("get");
+    assert_eq!(served, Some(Value::Json(json!(expected.to_string()))));
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActorsResponse {
+    pub actors: Vec<ActorStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActorStats {
+    pub name: String,
+    pub uptime_ms: u64,
+    pub mailbox_len: Option<usize>,
+    pub control_mailbox_len: Option<usize>,
+    pub calls: u64,
+    pub calls_per_second: u64,
+    pub error_replies: u64,
+    pub dropped_replies: u64,
+    pub latency: LatencyStats,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub count: u64,
+    pub total_us: u64,
+    pub buckets: Vec<LatencyBucket>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyBucket {
+    pub le_us: Option<u64>,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryResponse {
+    pub actors: Vec<RegisteredActor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisteredActor {
+    pub name: String,
+    pub call_type: String,
+    pub closed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplacePeerRequest {
+    pub node_id: usize,
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_address: Option<String>,
+}
+
+pub type ReplacePeerResponse = ReplacePeerRequest;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreakersResponse {
+    pub breakers: Vec<BreakerStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreakerStats {
+    pub node_id: usize,
+    pub state: BreakerStatus,
+    pub consecutive_failures: usize,
+    pub retry_in_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerStatus {
+    Closed,
+    Open,
+    HalfOpen,
+}
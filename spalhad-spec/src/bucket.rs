@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use crate::kv::Namespace;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketConfig {
+    pub replication: usize,
+    pub min_correct_reads: usize,
+    pub min_correct_writes: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bucket {
+    pub name: Namespace,
+    pub revision: u64,
+    pub updated_by: usize,
+    pub config: BucketConfig,
+}
+
+impl Bucket {
+    pub fn supersedes(&self, other: &Self) -> bool {
+        (self.revision, self.updated_by) > (other.revision, other.updated_by)
+    }
+}
+
+pub type PutBucketRequest = BucketConfig;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PutBucketResponse {
+    pub bucket: Bucket,
+    pub unreachable: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketsResponse {
+    pub buckets: Vec<Bucket>,
+    #[serde(default)]
+    pub written: Vec<Namespace>,
+}
+
+pub type SyncBucketRequest = Bucket;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncBucketResponse {
+    pub applied: bool,
+}
@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
 
 use crate::random_id::RandomId;
 
 pub type RunId = RandomId<32>;
 
+pub const TOPOLOGY_VERSION_HEADER: &str = "spalhad-topology-version";
+
 #[derive(
     Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
 )]
@@ -12,6 +15,30 @@ pub struct ClusterConfig {
     pub min_correct_reads: usize,
     pub min_correct_writes: usize,
     pub addresses: Vec<String>,
+    #[serde(default)]
+    pub transport: PeerTransport,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rpc_addresses: Vec<String>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerTransport {
+    #[default]
+    Http,
+    Rpc,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -30,3 +57,60 @@ pub struct IsActiveResponse {
 }
 
 pub type ActivateResponse = IsActiveResponse;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerStatus {
+    Joined,
+    Leaving,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerStatusRequest {
+    pub node_id: usize,
+    pub status: PeerStatus,
+}
+
+pub type PeerStatusResponse = PeerStatusRequest;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopologyResponse {
+    pub version: u64,
+    pub self_id: usize,
+    pub replication: usize,
+    pub nodes: Vec<TopologyNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopologyNode {
+    pub address: String,
+    pub status: PeerStatus,
+}
+
+pub fn topology_version(replication: usize, nodes: &[TopologyNode]) -> u64 {
+    let mut hasher = Sha3_256::new();
+    hasher.update((replication as u64).to_le_bytes());
+    for node in nodes {
+        hasher.update((node.address.len() as u64).to_le_bytes());
+        hasher.update(node.address.as_bytes());
+        hasher.update([match node.status {
+            PeerStatus::Joined => 0,
+            PeerStatus::Leaving => 1,
+        }]);
+    }
+    let digest = hasher.finalize();
+    let mut version = [0; 8];
+    version.copy_from_slice(&digest[.. 8]);
+    u64::from_le_bytes(version)
+}
@@ -1,6 +1,98 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
+use crate::replica::ReplicaOutcome;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Error {
+    #[serde(default)]
+    pub code: ErrorCode,
     pub trace: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumDetails>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replicas: Vec<ReplicaOutcome>,
+}
+
+impl Error {
+    pub fn new(code: ErrorCode, trace: Vec<String>) -> Self {
+        Self { code, trace, quorum: None, replicas: Vec::new() }
+    }
+
+    pub fn with_quorum(mut self, quorum: Option<QuorumDetails>) -> Self {
+        self.quorum = quorum;
+        self
+    }
+
+    pub fn with_replicas(mut self, replicas: Vec<ReplicaOutcome>) -> Self {
+        self.replicas = replicas;
+        self
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    NotActive,
+    QuorumFailed,
+    Timeout,
+    BadRunId,
+    InvalidKey,
+    InvalidRequest,
+    Forbidden,
+    Conflict,
+    UnsupportedMediaType,
+    Unavailable,
+    Internal,
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::NotFound => "not_found",
+            Self::NotActive => "not_active",
+            Self::QuorumFailed => "quorum_failed",
+            Self::Timeout => "timeout",
+            Self::BadRunId => "bad_run_id",
+            Self::InvalidKey => "invalid_key",
+            Self::InvalidRequest => "invalid_request",
+            Self::Forbidden => "forbidden",
+            Self::Conflict => "conflict",
+            Self::UnsupportedMediaType => "unsupported_media_type",
+            Self::Unavailable => "unavailable",
+            Self::Internal => "internal",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuorumDetails {
+    pub asked: usize,
+    pub answered: usize,
+    pub agreeing: usize,
+    pub required: usize,
+}
+
+impl fmt::Display for QuorumDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} replicas answered, {} agreed, {} required",
+            self.answered, self.asked, self.agreeing, self.required,
+        )
+    }
 }
@@ -52,8 +52,7 @@ pub fn divide_le<const N: usize>(
         if (*remainder).into_iter().rev().ge((*divisor).into_iter().rev()) {
             quotient[0] |= 1;
             let mut borrow = 0;
-            for (dest, src) in remainder.iter_mut().zip((*divisor).into_iter())
-            {
+            for (dest, src) in remainder.iter_mut().zip(*divisor) {
                 let (byte, borrow_a) = dest.overflowing_sub(src);
                 let (byte, borrow_b) = byte.overflowing_sub(borrow);
                 *dest = byte;
@@ -1,12 +1,62 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 pub use key::Key;
+pub use namespace::Namespace;
+pub use value::{BytesValue, Value};
 
 pub mod key;
+pub mod namespace;
+pub mod value;
+
+pub const KEY_ORIGIN_HEADER: &str = "spalhad-key-origin";
+
+#[derive(Debug, Error)]
+#[error("key origin header must be base64 encoded JSON")]
+pub struct ParseKeyOriginError;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyOrigin {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<Namespace>,
+    pub key: String,
+}
+
+impl KeyOrigin {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self { namespace: None, key: key.into() }
+    }
+
+    pub fn namespaced(namespace: Namespace, key: impl Into<String>) -> Self {
+        Self { namespace: Some(namespace), key: key.into() }
+    }
+
+    pub fn to_key(&self) -> Key {
+        match &self.namespace {
+            Some(namespace) => Key::from_namespaced_key(namespace, &self.key),
+            None => Key::from_str_key(&self.key),
+        }
+    }
+
+    pub fn to_header_value(&self) -> String {
+        let json = serde_json::to_vec(self).expect("key origin is JSON");
+        STANDARD.encode(json)
+    }
+
+    pub fn from_header_value(
+        value: &[u8],
+    ) -> Result<Self, ParseKeyOriginError> {
+        let json = STANDARD.decode(value).map_err(|_| ParseKeyOriginError)?;
+        serde_json::from_slice(&json).map_err(|_| ParseKeyOriginError)
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PutRequest<V> {
     pub value: V,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<KeyOrigin>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -18,3 +68,8 @@ pub struct GetResponse<V> {
 pub struct PutResponse {
     pub new: bool,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeleteResponse {
+    pub existed: bool,
+}
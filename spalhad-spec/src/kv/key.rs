@@ -5,10 +5,10 @@ use std::{
 };
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Visitor};
-use sha3::{Digest, Sha3_256};
+use sha3::{CShake256, CShake256Core, Digest, Sha3_256};
 use thiserror::Error;
 
-use crate::hex;
+use crate::{hex, kv::Namespace};
 
 #[derive(Debug, Error)]
 #[error("string is not a valid hexadecimal key")]
@@ -22,6 +22,62 @@ pub struct Key {
 impl Key {
     pub const SIZE: usize = 32;
 
+    const NAMESPACED_CUSTOMIZATION: &[u8] = b"spalhad namespaced key";
+
+    pub fn from_bytes_key(key_data: impl AsRef<[u8]>) -> Self {
+        Self::digest([key_data.as_ref()])
+    }
+
+    pub fn from_str_key(key_data: &str) -> Self {
+        Self::from_bytes_key(key_data)
+    }
+
+    pub fn from_namespaced_key(
+        namespace: &Namespace,
+        key_data: impl AsRef<[u8]>,
+    ) -> Self {
+        let namespace = namespace.as_str().as_bytes();
+        let namespace_len = u8::try_from(namespace.len())
+            .expect("namespace length is bounded by Namespace::MAX_LEN");
+        Self::customized_digest(
+            Self::NAMESPACED_CUSTOMIZATION,
+            [&[namespace_len], namespace, key_data.as_ref()],
+        )
+    }
+
+    pub fn legacy_str_key(key_data: &str) -> Self {
+        Self::digest([key_data.as_bytes(), &[0xff]])
+    }
+
+    fn digest<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let mut hasher = Sha3_256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        let mut bytes = [0; Self::SIZE];
+        bytes[..].copy_from_slice(hasher.finalize().as_slice());
+        Self::from_bytes(bytes)
+    }
+
+    fn customized_digest<'a>(
+        customization: &[u8],
+        parts: impl IntoIterator<Item = &'a [u8]>,
+    ) -> Self {
+        use sha3::digest::{ExtendableOutput, Update};
+
+        let core = CShake256Core::new(customization);
+        let mut hasher = CShake256::from_core(core);
+        for part in parts {
+            hasher.update(part);
+        }
+        let mut bytes = [0; Self::SIZE];
+        hasher.finalize_xof_into(&mut bytes);
+        Self::from_bytes(bytes)
+    }
+
+    #[deprecated(note = "depends on `std::hash::Hash` output, which is not \
+                         stable; use `Key::from_str_key` or \
+                         `Key::from_bytes_key` instead")]
     pub fn hashing<T>(key_data: T) -> Self
     where
         T: Hash + Eq,
@@ -81,8 +137,7 @@ impl Key {
         const INDEX_SIZE: usize = (usize::BITS as usize) / 8;
         let mut index_bytes = [0; INDEX_SIZE];
         index_bytes[..].copy_from_slice(&remainder[.. INDEX_SIZE]);
-        let index = usize::from_le_bytes(index_bytes);
-        index
+        usize::from_le_bytes(index_bytes)
     }
 }
 
@@ -0,0 +1,89 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Visitor};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error(
+    "namespace must have 1 to {} ASCII alphanumeric, '-', '_' or '.' \
+     characters",
+    Namespace::MAX_LEN
+)]
+pub struct ParseNamespaceError;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Namespace {
+    name: Box<str>,
+}
+
+impl Namespace {
+    pub const MAX_LEN: usize = 64;
+
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+
+    fn is_valid(name: &str) -> bool {
+        (1 ..= Self::MAX_LEN).contains(&name.len())
+            && name.bytes().all(|byte| {
+                byte.is_ascii_alphanumeric() || b"-_.".contains(&byte)
+            })
+    }
+}
+
+impl fmt::Display for Namespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl FromStr for Namespace {
+    type Err = ParseNamespaceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if Self::is_valid(s) {
+            Ok(Self { name: Box::from(s) })
+        } else {
+            Err(ParseNamespaceError)
+        }
+    }
+}
+
+impl Serialize for Namespace {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.name)
+    }
+}
+
+impl<'de> Deserialize<'de> for Namespace {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct NamespaceVisitor;
+
+        impl Visitor<'_> for NamespaceVisitor {
+            type Value = Namespace;
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                v.parse().map_err(E::custom)
+            }
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "expected a namespace name of up to {} characters",
+                    Namespace::MAX_LEN
+                )
+            }
+        }
+
+        deserializer.deserialize_str(NamespaceVisitor)
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use bytes::Bytes;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+    de::{self, SeqAccess, Visitor},
+};
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+pub const OCTET_STREAM_CONTENT_TYPE: &str = "application/octet-stream";
+
+pub fn is_json_content_type(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default();
+    essence.trim().eq_ignore_ascii_case(JSON_CONTENT_TYPE)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Value {
+    Json(serde_json::Value),
+    Bytes(BytesValue),
+}
+
+impl Value {
+    pub fn content_type(&self) -> &str {
+        match self {
+            Self::Json(_) => JSON_CONTENT_TYPE,
+            Self::Bytes(value) => &value.content_type,
+        }
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        Self::Json(value)
+    }
+}
+
+impl From<BytesValue> for Value {
+    fn from(value: BytesValue) -> Self {
+        Self::Bytes(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BytesValue {
+    pub content_type: String,
+    #[serde(serialize_with = "serialize_base64")]
+    #[serde(deserialize_with = "deserialize_base64")]
+    pub data: Bytes,
+}
+
+impl BytesValue {
+    pub fn new(
+        content_type: impl Into<String>,
+        data: impl Into<Bytes>,
+    ) -> Self {
+        Self { content_type: content_type.into(), data: data.into() }
+    }
+
+    pub fn octet_stream(data: impl Into<Bytes>) -> Self {
+        Self::new(OCTET_STREAM_CONTENT_TYPE, data)
+    }
+}
+
+impl From<Bytes> for BytesValue {
+    fn from(data: Bytes) -> Self {
+        Self::octet_stream(data)
+    }
+}
+
+impl From<Vec<u8>> for BytesValue {
+    fn from(data: Vec<u8>) -> Self {
+        Self::octet_stream(data)
+    }
+}
+
+fn serialize_base64<S>(data: &Bytes, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&STANDARD.encode(data))
+    } else {
+        serializer.serialize_bytes(data)
+    }
+}
+
+fn deserialize_base64<'de, D>(deserializer: D) -> Result<Bytes, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Bytes;
+
+        fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Bytes::copy_from_slice(v))
+        }
+
+        fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(Bytes::from(v))
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut data = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                data.push(byte);
+            }
+            Ok(Bytes::from(data))
+        }
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "expected a byte string")
+        }
+    }
+
+    if deserializer.is_human_readable() {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map(Bytes::from).map_err(de::Error::custom)
+    } else {
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
@@ -1,4 +1,4 @@
-pub use error::Error;
+pub use error::{Error, ErrorCode, QuorumDetails};
 
 mod hex;
 mod error;
@@ -6,3 +6,8 @@ mod error;
 pub mod random_id;
 pub mod kv;
 pub mod cluster;
+pub mod admin;
+pub mod bucket;
+pub mod wire;
+pub mod rpc;
+pub mod replica;
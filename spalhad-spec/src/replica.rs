@@ -0,0 +1,70 @@
+use std::{fmt, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+pub const DEBUG_HEADER: &str = "spalhad-debug";
+
+pub const DEBUG_REPLICAS: &str = "replicas";
+
+pub const REPLICAS_HEADER: &str = "spalhad-replicas";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplicaResult {
+    Agreed,
+    Disagreed,
+    Failed,
+    TimedOut,
+    Abandoned,
+    NotContacted,
+    Skipped,
+}
+
+impl ReplicaResult {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Agreed => "agreed",
+            Self::Disagreed => "disagreed",
+            Self::Failed => "failed",
+            Self::TimedOut => "timed_out",
+            Self::Abandoned => "abandoned",
+            Self::NotContacted => "not_contacted",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+impl fmt::Display for ReplicaResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplicaOutcome {
+    pub node: usize,
+    pub result: ReplicaResult,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+}
+
+impl ReplicaOutcome {
+    pub fn new(
+        node: usize,
+        result: ReplicaResult,
+        latency: Option<Duration>,
+    ) -> Self {
+        let latency_ms = latency.map(|latency| latency.as_secs_f64() * 1e3);
+        Self { node, result, latency_ms }
+    }
+}
+
+impl fmt::Display for ReplicaOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "node {}: {}", self.node, self.result)?;
+        if let Some(latency_ms) = self.latency_ms {
+            write!(f, " after {latency_ms:.1}ms")?;
+        }
+        Ok(())
+    }
+}
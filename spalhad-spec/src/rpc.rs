@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    Error,
+    kv::{Key, KeyOrigin, Value},
+    wire::{WireError, WireFormat},
+};
+
+pub const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+pub const MAX_BATCH_LEN: usize = 256;
+
+pub const MAX_IN_FLIGHT: usize = 1024;
+
+pub const WIRE_FORMAT: WireFormat = WireFormat::MessagePack;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub id: u64,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub trace: HashMap<String, String>,
+    pub call: RpcCall,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcCall {
+    Get {
+        key: Key,
+    },
+    Put {
+        key: Key,
+        value: Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        origin: Option<KeyOrigin>,
+    },
+    Delete {
+        key: Key,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub id: u64,
+    pub reply: RpcReply,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcReply {
+    Get { value: Option<Value> },
+    Put { new: bool },
+    Delete { existed: bool },
+    Error(Error),
+}
+
+pub fn encode_batch<T>(batch: &[T]) -> Result<Vec<u8>, WireError>
+where
+    T: Serialize,
+{
+    WIRE_FORMAT.encode(batch)
+}
+
+pub fn decode_batch<T>(frame: &[u8]) -> Result<Vec<T>, WireError>
+where
+    T: DeserializeOwned,
+{
+    WIRE_FORMAT.decode(frame)
+}
@@ -0,0 +1,124 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use thiserror::Error;
+
+use crate::kv::value::{JSON_CONTENT_TYPE, is_json_content_type};
+
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+#[derive(Debug, Error)]
+#[error("wire format must be one of \"json\" or \"msgpack\"")]
+pub struct ParseWireFormatError;
+
+#[derive(Debug, Error)]
+pub enum WireError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    MessagePackEncode(#[from] rmp_serde::encode::Error),
+    #[error(transparent)]
+    MessagePackDecode(#[from] rmp_serde::decode::Error),
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    #[serde(rename = "msgpack")]
+    MessagePack,
+}
+
+impl WireFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => JSON_CONTENT_TYPE,
+            Self::MessagePack => MSGPACK_CONTENT_TYPE,
+        }
+    }
+
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let essence = content_type.split(';').next().unwrap_or_default();
+        if is_json_content_type(essence) {
+            Some(Self::Json)
+        } else if essence.trim().eq_ignore_ascii_case(MSGPACK_CONTENT_TYPE) {
+            Some(Self::MessagePack)
+        } else {
+            None
+        }
+    }
+
+    pub fn from_accept(accept: &str) -> Self {
+        accept
+            .split(',')
+            .filter_map(|range| {
+                let format = Self::from_content_type(range)?;
+                let quality = quality(range)?;
+                (quality > 0.0).then_some((format, quality))
+            })
+            .max_by(|(format, quality), (other, other_quality)| {
+                let msgpack = *format == Self::MessagePack;
+                let other_msgpack = *other == Self::MessagePack;
+                quality
+                    .total_cmp(other_quality)
+                    .then(msgpack.cmp(&other_msgpack))
+            })
+            .map(|(format, _)| format)
+            .unwrap_or_default()
+    }
+
+    pub fn encode<T>(self, value: &T) -> Result<Vec<u8>, WireError>
+    where
+        T: Serialize + ?Sized,
+    {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(value)?),
+            Self::MessagePack => Ok(rmp_serde::to_vec_named(value)?),
+        }
+    }
+
+    pub fn decode<T>(self, bytes: &[u8]) -> Result<T, WireError>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            Self::Json => Ok(serde_json::from_slice(bytes)?),
+            Self::MessagePack => Ok(rmp_serde::from_slice(bytes)?),
+        }
+    }
+}
+
+fn quality(range: &str) -> Option<f32> {
+    let mut quality = 1.0;
+    for parameter in range.split(';').skip(1) {
+        let Some((name, value)) = parameter.split_once('=') else { continue };
+        if name.trim().eq_ignore_ascii_case("q") {
+            quality = value.trim().parse().ok()?;
+        }
+    }
+    (0.0 ..= 1.0).contains(&quality).then_some(quality)
+}
+
+impl fmt::Display for WireFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json => f.write_str("json"),
+            Self::MessagePack => f.write_str("msgpack"),
+        }
+    }
+}
+
+impl FromStr for WireFormat {
+    type Err = ParseWireFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "msgpack" => Ok(Self::MessagePack),
+            _ => Err(ParseWireFormatError),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use spalhad_spec::kv::{Key, Namespace};
+
+fn assert_digest(key: Key, expected: &str) {
+    assert_eq!(key.to_string(), expected);
+    assert_eq!(expected.parse::<Key>().expect("valid key"), key);
+}
+
+#[test]
+fn bytes_keys_are_plain_sha3_digests() {
+    let vectors: [(&[u8], &str); 4] = [
+        (
+            b"",
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+        ),
+        (
+            b"foo",
+            "76d3bc41c9f588f7fcd0d5bf4718f8f84b1c41b20882703100b9eb9413807c01",
+        ),
+        (
+            b"\xff\x00binary",
+            "ce3ed355fea44d3896535a52a77cbd97c0d48f02360c55bb154ba960689b043b",
+        ),
+        (
+            "chave-ção".as_bytes(),
+            "d5b3d6012fdb008f31c9c53c1f391b5aeb482068081ec24a59b4aae15ae59381",
+        ),
+    ];
+    for (key_data, expected) in vectors {
+        assert_digest(Key::from_bytes_key(key_data), expected);
+    }
+    assert_eq!(Key::from_str_key("foo"), Key::from_bytes_key(b"foo"));
+}
+
+#[test]
+fn legacy_keys_append_a_terminator() {
+    assert_digest(
+        Key::legacy_str_key("foo"),
+        "e87375240b24624cb65430249e7e796e7f46c62e1f435686fb55a66152ed2194",
+    );
+    assert_digest(
+        Key::legacy_str_key(""),
+        "444b89ecce395aec5dc98f19defd3a23bca0822fc72226f58ca46a17eeeca442",
+    );
+    assert_eq!(Key::legacy_str_key("foo"), Key::from_bytes_key(b"foo\xff"));
+}
+
+#[test]
+#[allow(deprecated)]
+fn legacy_keys_match_std_hashing_of_strings() {
+    for key_data in ["", "foo", "chave-ção"] {
+        assert_eq!(Key::hashing(key_data), Key::legacy_str_key(key_data));
+    }
+}
+
+#[test]
+fn namespaced_keys_use_customized_cshake() {
+    let vectors = [
+        (
+            "billing",
+            &b"foo"[..],
+            "8e923ed75277e3a2650a5250d4abd34c8242696bfbe31cdc7307acc28d8a4a78",
+        ),
+        (
+            "billing",
+            b"",
+            "5b3a8453cf06a65e34c497c95c5bc66632b0426df48cd23eac32597c35c2eee7",
+        ),
+        (
+            "users",
+            b"alice",
+            "4d38f1879afd6f49225a88b644ab56ce9368e7b7829a97ad505628da1f73ba55",
+        ),
+        (
+            "a",
+            b"b\0c",
+            "84dfe2e5c48044f866fcef1fe1cf50e98801f226c37a7cc88a8e01a0b98ba166",
+        ),
+    ];
+    for (namespace, key_data, expected) in vectors {
+        let namespace: Namespace = namespace.parse().expect("namespace");
+        assert_digest(Key::from_namespaced_key(&namespace, key_data), expected);
+    }
+}
+
+#[test]
+fn namespaced_keys_never_collide_with_plain_keys() {
+    let billing: Namespace = "billing".parse().expect("namespace");
+    let namespaced = Key::from_namespaced_key(&billing, "foo");
+    for plain in [
+        &b"billing\0foo"[..],
+        b"\x07billingfoo",
+        b"billingfoo",
+        b"spalhad namespaced key\x07billingfoo",
+    ] {
+        assert_ne!(Key::from_bytes_key(plain), namespaced);
+    }
+
+    let split: Namespace = "bill".parse().expect("namespace");
+    assert_ne!(Key::from_namespaced_key(&split, "ingfoo"), namespaced);
+}
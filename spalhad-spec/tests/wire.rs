@@ -0,0 +1,84 @@
+use serde_json::json;
+use spalhad_spec::{
+    kv::{BytesValue, GetResponse, PutRequest, Value},
+    wire::WireFormat,
+};
+
+#[test]
+fn accept_prefers_msgpack_when_listed() {
+    assert_eq!(WireFormat::from_accept(""), WireFormat::Json);
+    assert_eq!(WireFormat::from_accept("*/*"), WireFormat::Json);
+    assert_eq!(
+        WireFormat::from_accept("application/msgpack"),
+        WireFormat::MessagePack
+    );
+    assert_eq!(
+        WireFormat::from_accept("application/json, application/msgpack"),
+        WireFormat::MessagePack
+    );
+}
+
+#[test]
+fn accept_honours_quality_values() {
+    assert_eq!(
+        WireFormat::from_accept("application/msgpack;q=0"),
+        WireFormat::Json
+    );
+    assert_eq!(
+        WireFormat::from_accept("application/msgpack; q=0.0, application/json"),
+        WireFormat::Json
+    );
+    assert_eq!(
+        WireFormat::from_accept("application/msgpack;q=0.5, application/json"),
+        WireFormat::Json
+    );
+    assert_eq!(
+        WireFormat::from_accept(
+            "application/json;q=0.2, application/msgpack;q=0.9"
+        ),
+        WireFormat::MessagePack
+    );
+    assert_eq!(
+        WireFormat::from_accept("application/msgpack;Q=1, application/json"),
+        WireFormat::MessagePack
+    );
+    assert_eq!(
+        WireFormat::from_accept("application/msgpack;q=bogus"),
+        WireFormat::Json
+    );
+}
+
+#[test]
+fn bytes_values_round_trip_in_every_format() {
+    let data: &[u8] = b"\x00\xff\x89PNG\r\n";
+    let bytes = Value::Bytes(BytesValue::new("image/png".to_owned(), data));
+    let json = Value::Json(json!({"nested": [1, "two"]}));
+    for format in [WireFormat::Json, WireFormat::MessagePack] {
+        for value in [&bytes, &json] {
+            let request = PutRequest { value: value.clone(), origin: None };
+            let encoded = format.encode(&request).expect("encode");
+            let decoded: PutRequest<Value> =
+                format.decode(&encoded).expect("decode");
+            assert_eq!(decoded, request, "{format}");
+
+            let response = GetResponse { value: value.clone() };
+            let encoded = format.encode(&response).expect("encode");
+            let decoded: GetResponse<Value> =
+                format.decode(&encoded).expect("decode");
+            assert_eq!(decoded, response, "{format}");
+        }
+    }
+}
+
+#[test]
+fn msgpack_carries_bytes_unencoded() {
+    let data = vec![0xab; 64];
+    let value = Value::Bytes(BytesValue::new(
+        "application/octet-stream".to_owned(),
+        data,
+    ));
+    let msgpack = WireFormat::MessagePack.encode(&value).expect("msgpack");
+    let json = WireFormat::Json.encode(&value).expect("json");
+    assert!(msgpack.windows(64).any(|window| window == [0xab; 64]));
+    assert!(msgpack.len() < json.len());
+}
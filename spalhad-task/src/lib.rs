@@ -1,21 +1,52 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use anyhow::{Result, bail};
-use tokio::sync::Mutex;
+use anyhow::Result;
+use tokio::{select, sync::Mutex, time};
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Criticality {
+    Critical,
+    NonCritical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RestartPolicy {
+    pub max_restarts: usize,
+    pub backoff: Duration,
+}
+
+impl RestartPolicy {
+    pub fn new(max_restarts: usize, backoff: Duration) -> Self {
+        Self { max_restarts, backoff }
+    }
+}
+
+#[derive(Debug)]
+struct TaskFailure {
+    name: String,
+    criticality: Criticality,
+    error: anyhow::Error,
+}
+
 #[derive(Debug, Clone)]
 pub struct TaskManager {
     cancellation_token: CancellationToken,
-    failure: Arc<Mutex<bool>>,
+    failures: Arc<Mutex<Vec<TaskFailure>>>,
     tasks: TaskTracker,
 }
 
+impl Default for TaskManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TaskManager {
     pub fn new() -> Self {
         Self {
             cancellation_token: CancellationToken::new(),
-            failure: Arc::new(Mutex::new(false)),
+            failures: Arc::new(Mutex::new(Vec::new())),
             tasks: TaskTracker::new(),
         }
     }
@@ -24,30 +55,116 @@ impl TaskManager {
     where
         F: Future<Output = Result<()>> + Send + 'static,
     {
-        let failure = self.failure.clone();
+        self.spawn_named("anonymous", Criticality::NonCritical, job);
+    }
+
+    pub fn spawn_named<F>(
+        &self,
+        name: impl Into<String>,
+        criticality: Criticality,
+        job: F,
+    ) where
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let name = name.into();
+        let this = self.clone();
+        self.tasks.spawn(async move {
+            tracing::debug!(task = name, "task started");
+            match job.await {
+                Ok(()) => tracing::debug!(task = name, "task finished"),
+                Err(error) => this.fail(name, criticality, error).await,
+            }
+        });
+    }
+
+    pub fn spawn_with_restart<J, F>(
+        &self,
+        name: impl Into<String>,
+        policy: RestartPolicy,
+        mut job: J,
+    ) where
+        J: FnMut() -> F + Send + 'static,
+        F: Future<Output = Result<()>> + Send + 'static,
+    {
+        let name = name.into();
+        let this = self.clone();
         self.tasks.spawn(async move {
-            if let Err(error) = job.await {
-                let mut flag = failure.lock().await;
-                *flag = true;
-                eprintln!("Task failed!");
-                for error in error.chain() {
-                    eprintln!("Caused by:");
-                    eprintln!("  - {error}")
+            let mut restarts = 0;
+            tracing::debug!(task = name, "task started");
+            loop {
+                let error = match job().await {
+                    Ok(()) => {
+                        tracing::debug!(task = name, "task finished");
+                        break;
+                    },
+                    Err(error) => error,
+                };
+                if restarts >= policy.max_restarts {
+                    this.fail(name, Criticality::NonCritical, error).await;
+                    break;
+                }
+                restarts += 1;
+                tracing::warn!(
+                    task = name,
+                    restarts,
+                    error = format!("{error:#}"),
+                    "task failed, restarting",
+                );
+                select! {
+                    _ = this.cancellation_token.cancelled() => {
+                        tracing::debug!(task = name, "cancelled before restart");
+                        break;
+                    },
+                    _ = time::sleep(policy.backoff) => (),
                 }
             }
         });
     }
 
+    async fn fail(
+        &self,
+        name: String,
+        criticality: Criticality,
+        error: anyhow::Error,
+    ) {
+        tracing::error!(
+            task = name,
+            ?criticality,
+            error = format!("{error:#}"),
+            "task failed",
+        );
+        if criticality == Criticality::Critical {
+            tracing::error!(task = name, "critical task failed, cancelling");
+            self.cancel();
+        }
+        let mut failures = self.failures.lock().await;
+        failures.push(TaskFailure { name, criticality, error });
+    }
+
     pub fn cancellation_token(&self) -> CancellationToken {
         self.cancellation_token.clone()
     }
 
+    pub fn cancel(&self) {
+        self.cancellation_token.cancel();
+    }
+
     pub async fn wait_all(self) -> Result<()> {
         self.tasks.close();
         self.tasks.wait().await;
-        if *self.failure.lock().await {
-            bail!("At least one task failed, please consult the logs")
+        let mut failures = self.failures.lock().await;
+        if failures.is_empty() {
+            return Ok(());
         }
-        Ok(())
+        let first = failures
+            .iter()
+            .position(|failure| failure.criticality == Criticality::Critical)
+            .unwrap_or(0);
+        let failure = failures.remove(first);
+        let others = failures.len();
+        Err(failure.error.context(format!(
+            "task {} failed ({} other failure(s) logged)",
+            failure.name, others,
+        )))
     }
 }
@@ -0,0 +1,100 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::bail;
+use spalhad_task::{Criticality, RestartPolicy, TaskManager};
+use tokio::time;
+
+#[tokio::test]
+async fn cancellation_during_backoff_is_not_a_failure() {
+    let task_manager = TaskManager::new();
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let counter = attempts.clone();
+    let policy = RestartPolicy::new(5, Duration::from_secs(60));
+    task_manager.spawn_with_restart("flaky", policy, move || {
+        counter.fetch_add(1, Ordering::Relaxed);
+        async { bail!("not yet") }
+    });
+
+    time::sleep(Duration::from_millis(10)).await;
+    task_manager.cancel();
+    time::timeout(Duration::from_secs(5), task_manager.wait_all())
+        .await
+        .expect("cancellation should interrupt the backoff")
+        .expect("a cancelled restart is not a failure");
+    assert_eq!(attempts.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn exhausted_restarts_fail_without_cancelling() {
+    let task_manager = TaskManager::new();
+    let cancellation_token = task_manager.cancellation_token();
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let counter = attempts.clone();
+    let policy = RestartPolicy::new(2, Duration::from_millis(1));
+    task_manager.spawn_with_restart("flaky", policy, move || {
+        counter.fetch_add(1, Ordering::Relaxed);
+        async { bail!("never") }
+    });
+
+    let error = task_manager
+        .wait_all()
+        .await
+        .expect_err("exhausted restarts are a failure");
+    assert_eq!(
+        error.to_string(),
+        "task flaky failed (0 other failure(s) logged)"
+    );
+    assert_eq!(attempts.load(Ordering::Relaxed), 3);
+    assert!(!cancellation_token.is_cancelled());
+}
+
+#[tokio::test]
+async fn non_critical_failures_fail_without_cancelling() {
+    let task_manager = TaskManager::new();
+    let cancellation_token = task_manager.cancellation_token();
+    task_manager.spawn(async { bail!("anonymous") });
+    task_manager.spawn_named("optional", Criticality::NonCritical, async {
+        bail!("optional")
+    });
+
+    let error = task_manager
+        .wait_all()
+        .await
+        .expect_err("non-critical failures are still failures");
+    assert!(
+        error.to_string().ends_with("failed (1 other failure(s) logged)"),
+        "{error}",
+    );
+    assert!(!cancellation_token.is_cancelled());
+}
+
+#[tokio::test]
+async fn critical_failure_cancels_and_fails() {
+    let task_manager = TaskManager::new();
+    let cancellation_token = task_manager.cancellation_token();
+    task_manager.spawn_named("worker", Criticality::Critical, async move {
+        cancellation_token.cancelled().await;
+        Ok(())
+    });
+    task_manager.spawn_named("broken", Criticality::Critical, async {
+        bail!("broken")
+    });
+    task_manager.spawn_named("optional", Criticality::NonCritical, async {
+        bail!("optional")
+    });
+
+    let error = task_manager
+        .wait_all()
+        .await
+        .expect_err("critical failure should fail the manager");
+    assert_eq!(
+        error.to_string(),
+        "task broken failed (1 other failure(s) logged)",
+    );
+}